@@ -0,0 +1,23 @@
+// One collision pass over a packed pile of 50, 200 and 1000 fruits. Every iteration gets a
+// freshly packed pile, so the pass measured is always the first one and not a pile that has
+// already relaxed apart over earlier iterations.
+//
+//   cargo bench --bench collision
+
+use alpha_suika::bench::CollisionPile;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+const PILE_SIZES: [u32; 3] = [50, 200, 1000];
+
+fn collision_pass(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apply_collisions");
+    for count in PILE_SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            b.iter_batched(|| CollisionPile::new(count), |mut pile| pile.step(), BatchSize::LargeInput);
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, collision_pass);
+criterion_main!(benches);
@@ -0,0 +1,260 @@
+// Achievements, persisted to SAVE_DIR and announced with a toast when first unlocked.
+// The biggest fruit ever made is kept alongside them and gets a toast when beaten, and so is
+// whether the tutorial (see tutorial.rs) has been dismissed.
+// The file is only rewritten when something is unlocked or beaten; the running count of dropped
+// fruits is written then and on exit, not on every drop.
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::locale::Localization;
+use crate::{ComboTracker, FruitDropped, FruitMerged, FruitTable, SAVE_DIR};
+
+const ACHIEVEMENTS_FILE: &str = "achievements.txt";
+const COMBO_ACHIEVEMENT_POINTS: u32 = 100;
+const TOTAL_FRUITS_ACHIEVEMENT: u32 = 1000;
+
+const TOAST_DURATION: f32 = 3.0; // seconds a toast stays up
+const TOAST_FADE: f32 = 1.0; // seconds of fade out at the end of TOAST_DURATION
+const TOAST_FONT_SIZE: f32 = 30.0;
+const TOAST_SPACING: f32 = 40.0;
+const TOAST_PADDING: f32 = 20.0;
+const TOAST_COLOR: Color = Color::rgb(0.9, 0.6, 0.1);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Achievement {
+    FirstWatermelon,
+    HundredPointCombo,
+    ThousandFruits,
+}
+
+impl Achievement {
+    const ALL: [Achievement; 3] = [
+        Achievement::FirstWatermelon,
+        Achievement::HundredPointCombo,
+        Achievement::ThousandFruits,
+    ];
+
+    // Stable name used in the save file
+    fn key(&self) -> &'static str {
+        match self {
+            Achievement::FirstWatermelon => "first_watermelon",
+            Achievement::HundredPointCombo => "hundred_point_combo",
+            Achievement::ThousandFruits => "thousand_fruits",
+        }
+    }
+
+    // Localization key of the displayed name
+    fn title_key(&self) -> String {
+        return format!("achievement_{}", self.key());
+    }
+}
+
+#[derive(Event)]
+pub struct AchievementUnlocked(pub Achievement);
+
+// A merge produced a bigger fruit than any previous run
+#[derive(Event)]
+pub struct NewBestFruit(pub u8);
+
+#[derive(Resource, Default)]
+pub struct Achievements {
+    unlocked: HashSet<Achievement>,
+    total_fruits: u32, // fruits dropped across every run
+    best_group: Option<u8>, // biggest fruit made across every run
+    pub tutorial_seen: bool,
+}
+
+impl Achievements {
+    // A missing or unreadable file just means nothing has been unlocked yet
+    fn load() -> Achievements {
+        let mut achievements = Achievements::default();
+        let Ok(contents) = fs::read_to_string(Path::new(SAVE_DIR).join(ACHIEVEMENTS_FILE)) else {
+            return achievements;
+        };
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some("total_fruits"), Some(count)) => {
+                    achievements.total_fruits = count.parse().unwrap_or(0);
+                }
+                (Some("best_group"), Some(group)) => {
+                    achievements.best_group = group.parse().ok();
+                }
+                (Some("tutorial_seen"), None) => {
+                    achievements.tutorial_seen = true;
+                }
+                (Some("unlocked"), Some(key)) => {
+                    if let Some(achievement) = Achievement::ALL.iter().find(|a| a.key() == key) {
+                        achievements.unlocked.insert(*achievement);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return achievements;
+    }
+
+    pub fn save(&self) {
+        let mut contents: String = format!("total_fruits {}\n", self.total_fruits);
+        if let Some(group) = self.best_group {
+            contents += &format!("best_group {}\n", group);
+        }
+        if self.tutorial_seen {
+            contents += "tutorial_seen\n";
+        }
+        for achievement in Achievement::ALL.iter() {
+            if self.unlocked.contains(achievement) {
+                contents += &format!("unlocked {}\n", achievement.key());
+            }
+        }
+        let result = fs::create_dir_all(SAVE_DIR)
+            .and_then(|_| fs::write(Path::new(SAVE_DIR).join(ACHIEVEMENTS_FILE), contents));
+        if let Err(err) = result {
+            warn!("Could not save achievements: {}", err);
+        }
+    }
+
+    // Returns true the first time an achievement is unlocked
+    fn unlock(&mut self, achievement: Achievement) -> bool {
+        return self.unlocked.insert(achievement);
+    }
+}
+
+#[derive(Component)]
+struct Toast {
+    timer: Timer,
+}
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Achievements::load())
+            .add_event::<AchievementUnlocked>()
+            .add_event::<NewBestFruit>()
+            .add_systems(Update, (
+                check_achievements,
+                spawn_toasts,
+                update_toasts,
+            ).chain())
+            // AppExit is sent during Update, and the app only stops once the frame is done
+            .add_systems(Last, save_on_exit);
+    }
+}
+
+fn check_achievements(
+    fruit_table: Res<FruitTable>,
+    combo: Res<ComboTracker>,
+    mut achievements: ResMut<Achievements>,
+    mut merged_events: EventReader<FruitMerged>,
+    mut dropped_events: EventReader<FruitDropped>,
+    mut unlocked_events: EventWriter<AchievementUnlocked>,
+    mut best_events: EventWriter<NewBestFruit>,
+){
+    let mut newly_unlocked: Vec<Achievement> = Vec::new();
+    let mut changed: bool = false;
+
+    let mut new_best: Option<u8> = None;
+    for merged in merged_events.iter() {
+        if fruit_table.is_top(merged.group) {
+            newly_unlocked.push(Achievement::FirstWatermelon);
+        }
+        if achievements.best_group.map_or(true, |best| merged.group > best) {
+            achievements.best_group = Some(merged.group);
+            new_best = Some(merged.group);
+            changed = true;
+        }
+    }
+    // one toast for a whole cascade of new bests
+    if let Some(group) = new_best {
+        best_events.send(NewBestFruit(group));
+    }
+    if combo.points >= COMBO_ACHIEVEMENT_POINTS {
+        newly_unlocked.push(Achievement::HundredPointCombo);
+    }
+    achievements.total_fruits += dropped_events.iter().count() as u32;
+    if achievements.total_fruits >= TOTAL_FRUITS_ACHIEVEMENT {
+        newly_unlocked.push(Achievement::ThousandFruits);
+    }
+
+    for achievement in newly_unlocked {
+        if achievements.unlock(achievement) {
+            unlocked_events.send(AchievementUnlocked(achievement));
+            changed = true;
+        }
+    }
+    if changed {
+        achievements.save();
+    }
+}
+
+fn save_on_exit(
+    achievements: Res<Achievements>,
+    mut exit_events: EventReader<AppExit>,
+){
+    if exit_events.iter().next().is_some() {
+        achievements.save();
+    }
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    toast_query: Query<&Toast>,
+    localization: Res<Localization>,
+    mut unlocked_events: EventReader<AchievementUnlocked>,
+    mut best_events: EventReader<NewBestFruit>,
+){
+    let mut slot: usize = toast_query.iter().count();
+    let messages: Vec<String> = unlocked_events
+        .iter()
+        .map(|AchievementUnlocked(achievement)| {
+            localization.format("achievement_unlocked", &[&localization.get(&achievement.title_key())])
+        })
+        .chain(best_events.iter().map(|NewBestFruit(group)| localization.format("new_best_fruit", &[&(group + 1)])))
+        .collect();
+    for message in messages {
+        commands.spawn((
+            TextBundle::from_section(
+                message,
+                TextStyle {
+                    font_size: TOAST_FONT_SIZE,
+                    color: TOAST_COLOR,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(TOAST_PADDING + TOAST_SPACING * slot as f32),
+                left: Val::Px(TOAST_PADDING),
+                ..default()
+            }),
+            Toast {
+                timer: Timer::from_seconds(TOAST_DURATION, TimerMode::Once),
+            },
+        ));
+        slot += 1;
+    }
+}
+
+// Fades toasts out over their last TOAST_FADE seconds, then removes them
+fn update_toasts(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut toast_query: Query<(Entity, &mut Toast, &mut Text)>,
+){
+    for (entity, mut toast, mut text) in toast_query.iter_mut() {
+        toast.timer.tick(time.delta());
+        if toast.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha: f32 = (toast.timer.remaining_secs() / TOAST_FADE).min(1.0);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Drops per minute, for the stats overlay. Every drop is timestamped and kept for DROP_RATE_WINDOW
+// seconds, so the rate is the number of drops still in the window. Recomputed once a second.
+
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::FruitDropped;
+
+const DROP_RATE_WINDOW: f32 = 60.0; // seconds of drops counted
+const DROP_RATE_INTERVAL: f32 = 1.0; // seconds between updates of the shown rate
+
+#[derive(Resource)]
+pub struct DropRate {
+    drops: VecDeque<f32>, // Time::elapsed_seconds of each drop in the window, oldest first
+    timer: Timer,
+    pub per_minute: f32,
+}
+
+impl Default for DropRate {
+    fn default() -> Self {
+        DropRate {
+            drops: VecDeque::new(),
+            timer: Timer::from_seconds(DROP_RATE_INTERVAL, TimerMode::Repeating),
+            per_minute: 0.0,
+        }
+    }
+}
+
+pub struct DropRatePlugin;
+
+impl Plugin for DropRatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DropRate>()
+            .add_systems(Update, update_drop_rate);
+    }
+}
+
+fn update_drop_rate(
+    time: Res<Time>,
+    mut rate: ResMut<DropRate>,
+    mut dropped_events: EventReader<FruitDropped>,
+){
+    let now: f32 = time.elapsed_seconds();
+    for _ in dropped_events.iter() {
+        rate.drops.push_back(now);
+    }
+    // aged out every frame, so the buffer never holds more than a window of drops
+    while rate.drops.front().is_some_and(|dropped| now - *dropped > DROP_RATE_WINDOW) {
+        rate.drops.pop_front();
+    }
+    rate.timer.tick(time.delta());
+    if rate.timer.just_finished() {
+        rate.per_minute = rate.drops.len() as f32 * 60.0 / DROP_RATE_WINDOW;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::utils::{Duration, Instant};
+
+    // Sends `drops` cherries, then runs update_drop_rate with the clock at `seconds` after startup
+    fn drop_at(world: &mut World, startup: Instant, seconds: u64, drops: usize) {
+        for _ in 0..drops {
+            world.send_event(FruitDropped { group: 0, bomb: false, x: 0.0, launch: 0.0 });
+        }
+        world.resource_mut::<Time>().update_with_instant(startup + Duration::from_secs(seconds));
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_drop_rate);
+        schedule.run(world);
+    }
+
+    #[test]
+    fn drops_older_than_the_window_stop_counting() {
+        let startup: Instant = Instant::now();
+        let mut world: World = World::new();
+        world.insert_resource(Time::new(startup));
+        world.init_resource::<DropRate>();
+        world.init_resource::<Events<FruitDropped>>();
+
+        drop_at(&mut world, startup, 1, 3);
+        drop_at(&mut world, startup, 31, 1);
+        assert_eq!(world.resource::<DropRate>().per_minute, 4.0);
+
+        // the first three are past the window now, the last one isn't yet
+        drop_at(&mut world, startup, DROP_RATE_WINDOW as u64 + 2, 0);
+        assert_eq!(world.resource::<DropRate>().per_minute, 1.0);
+    }
+}
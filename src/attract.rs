@@ -0,0 +1,167 @@
+// Attract mode: a simple AI plays the game for demo purposes. It takes over after
+// ATTRACT_IDLE_TIME seconds of play without input, or right away with --attract.
+// It aims for the column whose topmost fruit matches the held fruit, otherwise the lowest
+// column, walks the player there and asks input_handler to drop. Any key hands control back.
+
+use bevy::prelude::*;
+
+use crate::board::{BoardSnapshot, FruitSnapshot};
+use crate::replay::ReplayPlayer;
+use crate::step::Simulation;
+use crate::{
+    clamp_player_x, input_handler, ArenaConfig, DropMode, FruitTable, GameSettings, GameState, Player,
+    BOTTOM_WALL, LEFT_WALL, PLAYER_SPEED, RIGHT_WALL, WALL_THICKNESS,
+};
+
+const ATTRACT_SAMPLES: usize = 24; // candidate drop columns across the arena
+const ATTRACT_MATCH_BONUS: f32 = 10000.0; // preference for landing on a same-group fruit
+const ATTRACT_AIM_TOLERANCE: f32 = 1.0; // pixels
+const ATTRACT_IDLE_TIME: f32 = 30.0; // seconds without input before the AI takes over
+
+#[derive(Resource, Default)]
+pub struct AttractMode {
+    pub active: bool,
+    pub drop_requested: bool, // consumed by input_handler on the next drop
+    target_x: Option<f32>,
+    idle: f32, // seconds since the last key or mouse button while playing
+}
+
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AttractMode>()
+            .add_systems(FixedUpdate, attract_player.before(input_handler).in_set(Simulation))
+            .add_systems(Update, watch_idle);
+    }
+}
+
+// Topmost fruit overlapping the column a held fruit of `radius` at `x` would fall through
+fn landing_fruit(snapshot: &BoardSnapshot, x: f32, radius: f32) -> Option<&FruitSnapshot> {
+    return snapshot
+        .fruits
+        .iter()
+        .filter(|fruit| (fruit.pos.x - x).abs() < (fruit.radius + radius))
+        .max_by(|a, b| (a.pos.y + a.radius).total_cmp(&(b.pos.y + b.radius)));
+}
+
+fn choose_target(snapshot: &BoardSnapshot, held_group: u8, radius: f32, top_wall: f32) -> f32 {
+    let left: f32 = clamp_player_x(LEFT_WALL, radius);
+    let right: f32 = clamp_player_x(RIGHT_WALL, radius);
+    let mut best_x: f32 = 0.0;
+    let mut best_score: f32 = f32::NEG_INFINITY;
+    for i in 0..ATTRACT_SAMPLES {
+        let x: f32 = left + (right - left) * (i as f32) / ((ATTRACT_SAMPLES - 1) as f32);
+        let score: f32 = match landing_fruit(snapshot, x, radius) {
+            // full column, input_handler may refuse the drop
+            Some(fruit) if (fruit.pos.y + fruit.radius) >= top_wall - WALL_THICKNESS/2.0 => continue,
+            Some(fruit) if fruit.group == held_group => ATTRACT_MATCH_BONUS + fruit.pos.y,
+            Some(fruit) => -(fruit.pos.y + fruit.radius),
+            None => -BOTTOM_WALL,
+        };
+        if score > best_score {
+            best_score = score;
+            best_x = x;
+        }
+    }
+    return best_x;
+}
+
+fn attract_player(
+    time_step: Res<FixedTime>,
+    snapshot: Res<BoardSnapshot>,
+    fruit_table: Res<FruitTable>,
+    settings: Res<GameSettings>,
+    arena_config: Res<ArenaConfig>,
+    mut attract: ResMut<AttractMode>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+){
+    if !attract.active {
+        return;
+    }
+    let mut player_transform = player_query.single_mut();
+    let radius: f32 = fruit_table.get(snapshot.current_group).radius;
+
+    // a new target once the previous drop has been taken
+    if attract.target_x.is_none() && !attract.drop_requested {
+        attract.target_x = match settings.drop_mode {
+            DropMode::Free => Some(choose_target(&snapshot, snapshot.current_group, radius, arena_config.top_wall)),
+            DropMode::FixedCenter => Some(0.0),
+        };
+    }
+    let Some(target_x) = attract.target_x else {
+        return;
+    };
+
+    let max_step: f32 = PLAYER_SPEED * time_step.period.as_secs_f32();
+    let offset: f32 = target_x - player_transform.translation.x;
+    player_transform.translation.x += offset.clamp(-max_step, max_step);
+
+    if offset.abs() <= ATTRACT_AIM_TOLERANCE {
+        attract.drop_requested = true;
+        attract.target_x = None;
+    }
+}
+
+// Starts attract mode once nobody has touched the keyboard or mouse for a while, and hands
+// control back on any key or button
+fn watch_idle(
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    game_state: Res<State<GameState>>,
+    replay: Res<ReplayPlayer>,
+    mut attract: ResMut<AttractMode>,
+){
+    if keys.get_pressed().next().is_some() || mouse.get_pressed().next().is_some() {
+        attract.idle = 0.0;
+        if attract.active && (keys.get_just_pressed().next().is_some() || mouse.get_just_pressed().next().is_some()) {
+            attract.active = false;
+            attract.drop_requested = false;
+            attract.target_x = None;
+        }
+        return;
+    }
+    if *game_state.get() != GameState::Playing || replay.active {
+        return; // a replay plays itself
+    }
+    attract.idle += time.delta_seconds();
+    if !attract.active && attract.idle >= ATTRACT_IDLE_TIME {
+        attract.active = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle_world(idle: f32) -> World {
+        let mut world: World = World::new();
+        world.insert_resource(Time::default());
+        world.insert_resource(State::new(GameState::Playing));
+        world.init_resource::<Input<KeyCode>>();
+        world.init_resource::<Input<MouseButton>>();
+        world.init_resource::<ReplayPlayer>();
+        world.insert_resource(AttractMode { idle, ..default() });
+        return world;
+    }
+
+    #[test]
+    fn idling_starts_attract_mode_and_a_key_stops_it() {
+        let mut schedule: Schedule = Schedule::default();
+        schedule.add_systems(watch_idle);
+
+        let mut world: World = idle_world(ATTRACT_IDLE_TIME - 1.0);
+        schedule.run(&mut world);
+        assert!(!world.resource::<AttractMode>().active);
+
+        world.resource_mut::<AttractMode>().idle = ATTRACT_IDLE_TIME;
+        schedule.run(&mut world);
+        assert!(world.resource::<AttractMode>().active);
+
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Left);
+        schedule.run(&mut world);
+        let attract = world.resource::<AttractMode>();
+        assert!(!attract.active && attract.idle == 0.0);
+    }
+}
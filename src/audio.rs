@@ -0,0 +1,227 @@
+// Sound effects. Every clip is played through MasterVolume, and merges rise in pitch through a combo.
+// Drops play the held fruit's voice from the skin pack if it has one, else the generic drop sound.
+
+use bevy::audio::Volume;
+use bevy::prelude::*;
+
+use crate::skin::{load_pack_voices, SkinPack};
+use crate::{
+    CollisionEvent, ComboTracker, FruitDropped, FruitMerged, FruitTable, HardLanding, COLLISION_EVENT_MIN_SPEED,
+    HARD_LANDING_SPEED,
+};
+
+const MERGE_SOUNDS: [&str; 3] = [
+    "sounds/merge_0.wav",
+    "sounds/merge_1.wav",
+    "sounds/merge_2.wav",
+];
+const MAX_MERGE_SOUNDS: usize = 4; // merge clips allowed to play at once
+const MERGE_SPEED_SMALLEST: f32 = 1.5; // playback speed (pitch) for merges into the smallest fruit
+const MERGE_SPEED_LARGEST: f32 = 0.6; // and into the top-tier fruit
+// Each merge in a combo plays higher than the last: COMBO_SEMITONES per merge after the first,
+// eased off by COMBO_PITCH_EASING so the steps shrink as the combo grows, topping out at COMBO_MAX_SEMITONES
+const COMBO_SEMITONES: f32 = 1.0;
+const COMBO_PITCH_EASING: f32 = 0.9; // 1.0 keeps every step the same size
+const COMBO_MAX_SEMITONES: f32 = 7.0; // a fifth up
+const THUMP_SOUND: &str = "sounds/thump.wav";
+const THUMP_MIN_VOLUME: f32 = 0.4; // relative volume of a landing right at HARD_LANDING_SPEED
+const TAP_SOUND: &str = "sounds/tap.wav";
+const MAX_TAPS_PER_FRAME: usize = 2; // the hardest impacts of the frame, the rest are dropped
+const MAX_TAP_SOUNDS: usize = 6; // tap clips allowed to play at once
+const TAP_FULL_SPEED: f32 = 1200.0; // px/s, impacts this hard tap at full volume and pitch
+const TAP_MIN_VOLUME: f32 = 0.15; // relative volume of an impact right at COLLISION_EVENT_MIN_SPEED
+const TAP_SPEED_RANGE: std::ops::RangeInclusive<f32> = 0.8..=1.3; // playback speed, soft to hard
+const DROP_SOUND: &str = TAP_SOUND; // a soft, low tap
+const DROP_SPEED: f32 = 0.7;
+const DROP_VOLUME: f32 = 0.5; // relative to MasterVolume
+
+#[derive(Resource)]
+pub struct MasterVolume(pub f32); // 0.0 ..= 1.0
+
+impl Default for MasterVolume {
+    fn default() -> Self {
+        MasterVolume(1.0)
+    }
+}
+
+#[derive(Resource)]
+pub struct AudioAssets {
+    merge: Vec<Handle<AudioSource>>,
+    thump: Handle<AudioSource>,
+    tap: Handle<AudioSource>,
+    drop: Handle<AudioSource>,
+    voices: Vec<Option<Handle<AudioSource>>>, // per group from the skin pack, None plays `drop`
+}
+
+// Marks a playing merge clip so the number of overlapping ones can be capped
+#[derive(Component)]
+struct MergeSound;
+
+#[derive(Component)]
+struct TapSound;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MasterVolume>()
+            .add_systems(Startup, load_audio)
+            .add_systems(Update, (play_merge_sounds, play_landing_sounds, play_collision_sounds))
+            .add_systems(Update, (load_skin_voices, play_drop_sounds).chain());
+    }
+}
+
+fn load_audio(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+){
+    commands.insert_resource(AudioAssets {
+        merge: MERGE_SOUNDS.iter().map(|path| asset_server.load(*path)).collect(),
+        thump: asset_server.load(THUMP_SOUND),
+        tap: asset_server.load(TAP_SOUND),
+        drop: asset_server.load(DROP_SOUND),
+        voices: Vec::new(),
+    });
+}
+
+// Follows the skin pack like apply_skin_pack does its art
+fn load_skin_voices(
+    skin: Res<SkinPack>,
+    asset_server: Res<AssetServer>,
+    fruit_table: Res<FruitTable>,
+    mut audio_assets: ResMut<AudioAssets>,
+){
+    // AudioAssets is inserted after the pack was first set, so load on that first run too
+    if !skin.is_changed() && !audio_assets.is_added() {
+        return;
+    }
+    audio_assets.voices = match &skin.name {
+        Some(name) => load_pack_voices(name, fruit_table.len(), &asset_server),
+        None => Vec::new(),
+    };
+}
+
+fn play_drop_sounds(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    master_volume: Res<MasterVolume>,
+    mut dropped_events: EventReader<FruitDropped>,
+){
+    for dropped in dropped_events.iter() {
+        // a bomb isn't the cherry its group says, so it gets the plain drop sound
+        let voice: Option<&Handle<AudioSource>> = if dropped.bomb {
+            None
+        } else {
+            audio_assets.voices.get(dropped.group as usize).and_then(Option::as_ref)
+        };
+        let settings: PlaybackSettings = match voice {
+            Some(_) => PlaybackSettings::DESPAWN.with_volume(Volume::new_relative(master_volume.0)),
+            None => PlaybackSettings::DESPAWN
+                .with_speed(DROP_SPEED)
+                .with_volume(Volume::new_relative(master_volume.0 * DROP_VOLUME)),
+        };
+        commands.spawn(AudioBundle {
+            source: voice.unwrap_or(&audio_assets.drop).clone(),
+            settings,
+        });
+    }
+}
+
+// Bigger fruits merge with a deeper sound
+fn merge_speed(group: u8, fruit_table: &FruitTable) -> f32 {
+    let top: f32 = (fruit_table.len() - 1).max(1) as f32;
+    let size: f32 = (group as f32 / top).min(1.0);
+    return MERGE_SPEED_SMALLEST + (MERGE_SPEED_LARGEST - MERGE_SPEED_SMALLEST) * size;
+}
+
+// Speed multiplier for the nth merge of a combo, 1 for a lone merge
+fn combo_pitch(combo: u32) -> f32 {
+    let steps: i32 = combo.saturating_sub(1) as i32;
+    // geometric series: COMBO_SEMITONES * (1 + e + e^2 + ... ) over the steps taken
+    let semitones: f32 = if COMBO_PITCH_EASING == 1.0 {
+        COMBO_SEMITONES * steps as f32
+    } else {
+        COMBO_SEMITONES * (1.0 - COMBO_PITCH_EASING.powi(steps)) / (1.0 - COMBO_PITCH_EASING)
+    };
+    return 2f32.powf(semitones.min(COMBO_MAX_SEMITONES) / 12.0);
+}
+
+fn play_merge_sounds(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    fruit_table: Res<FruitTable>,
+    master_volume: Res<MasterVolume>,
+    combo: Res<ComboTracker>,
+    playing_query: Query<(), With<MergeSound>>,
+    mut merged_events: EventReader<FruitMerged>,
+){
+    let mut playing: usize = playing_query.iter().count();
+    // the combo already counts every merge read here, so the first of them was merge number first_combo
+    let merges: Vec<&FruitMerged> = merged_events.iter().collect();
+    let first_combo: u32 = (combo.count + 1).saturating_sub(merges.len() as u32).max(1);
+    for (index, merged) in merges.into_iter().enumerate() {
+        if playing >= MAX_MERGE_SOUNDS || audio_assets.merge.is_empty() {
+            continue; // still drain the events
+        }
+        let clip: &Handle<AudioSource> = &audio_assets.merge[merged.group as usize % audio_assets.merge.len()];
+        commands.spawn((
+            AudioBundle {
+                source: clip.clone(),
+                settings: PlaybackSettings::DESPAWN
+                    .with_speed(merge_speed(merged.group, &fruit_table) * combo_pitch(first_combo + index as u32))
+                    .with_volume(Volume::new_relative(master_volume.0)),
+            },
+            MergeSound,
+        ));
+        playing += 1;
+    }
+}
+
+// Harder landings thump louder
+fn play_landing_sounds(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    master_volume: Res<MasterVolume>,
+    mut landing_events: EventReader<HardLanding>,
+){
+    // simultaneous landings play a single thump at the loudest volume
+    let Some(speed) = landing_events.iter().map(|landing| landing.speed).reduce(f32::max) else {
+        return;
+    };
+    let volume: f32 = (THUMP_MIN_VOLUME * speed / HARD_LANDING_SPEED).min(1.0);
+    commands.spawn(AudioBundle {
+        source: audio_assets.thump.clone(),
+        settings: PlaybackSettings::DESPAWN.with_volume(Volume::new_relative(master_volume.0 * volume)),
+    });
+}
+
+// Fruits knocking into each other and the walls. A busy pile sends dozens of impacts a frame,
+// so only the hardest few play, and only while fewer than MAX_TAP_SOUNDS are still ringing.
+fn play_collision_sounds(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    master_volume: Res<MasterVolume>,
+    playing_query: Query<(), With<TapSound>>,
+    mut collision_events: EventReader<CollisionEvent>,
+){
+    let mut speeds: Vec<f32> = collision_events.iter().map(|collision| collision.speed).collect();
+    let room: usize = MAX_TAP_SOUNDS.saturating_sub(playing_query.iter().count()).min(MAX_TAPS_PER_FRAME);
+    if speeds.is_empty() || room == 0 {
+        return;
+    }
+    speeds.sort_by(|a, b| b.total_cmp(a));
+    for speed in speeds.into_iter().take(room) {
+        let strength: f32 = ((speed - COLLISION_EVENT_MIN_SPEED) / (TAP_FULL_SPEED - COLLISION_EVENT_MIN_SPEED)).clamp(0.0, 1.0);
+        let volume: f32 = TAP_MIN_VOLUME + (1.0 - TAP_MIN_VOLUME) * strength;
+        let playback_speed: f32 = TAP_SPEED_RANGE.start() + (TAP_SPEED_RANGE.end() - TAP_SPEED_RANGE.start()) * strength;
+        commands.spawn((
+            AudioBundle {
+                source: audio_assets.tap.clone(),
+                settings: PlaybackSettings::DESPAWN
+                    .with_speed(playback_speed)
+                    .with_volume(Volume::new_relative(master_volume.0 * volume)),
+            },
+            TapSound,
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+// Save on exit: closing the window (or Esc) mid-run writes the board to SAVE_DIR, and the next
+// launch picks the run up where it was left. Only a run still being played is saved; quitting
+// from the game over or win screen removes any old save, so a finished board never comes back.
+// Bombs aren't kept, and the resumed run draws its fruits from a fresh seed. Can be turned off
+// on the settings screen. A board that isn't the player's own run (the attract demo, a replay,
+// the stress test or one with sandbox fruits) is neither saved nor allowed to remove a save.
+// A launch that picks its own seed (--seed, --replay) or records a replay, which starts from an
+// empty board, leaves the save for a later launch too.
+// Time attack runs aren't saved either, quitting would stop their clock. An endless save waits
+// while time attack is on, so it can't be resumed as a timed run with its score already banked.
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::attract::AttractMode;
+use crate::replay::{ReplayPlayer, ReplayRecorder};
+use crate::sandbox::Sandbox;
+use crate::win::{RoundTimer, RunClock};
+use crate::{
+    held_color, spawn_fruit_bundle, BiggestFruit, Bomb, Fruit, FruitAssets, FruitBundle, FruitIterator, FruitTable,
+    GameRng, GameSeed, GameSettings, GameState, Player, Scoreboard, SeedChosen, StressTest, SAVE_DIR,
+};
+
+const RUN_FILE: &str = "run.ron";
+
+#[derive(Serialize, Deserialize)]
+struct SavedFruit {
+    id: u32,
+    group: u8,
+    pos: Vec2,
+    pos_last: Vec2, // with pos, the velocity
+    a_pos: f32,
+    a_pos_last: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedRun {
+    score: u32,
+    next_id: u32,
+    next_group: u8,
+    #[serde(default)] // missing from saves made before it was kept
+    clock: f32, // RunClock, for the goal mode's time
+    fruits: Vec<SavedFruit>,
+}
+
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        // after setup has spawned the player
+        app.add_systems(PostStartup, resume_run)
+            // AppExit is sent during Update, and the app only stops once the frame is done
+            .add_systems(Last, save_run_on_exit);
+    }
+}
+
+fn run_path() -> std::path::PathBuf {
+    return Path::new(SAVE_DIR).join(RUN_FILE);
+}
+
+fn save_run_on_exit(
+    settings: Res<GameSettings>,
+    game_state: Res<State<GameState>>,
+    scoreboard: Res<Scoreboard>,
+    attract: Res<AttractMode>,
+    replay: Res<ReplayPlayer>,
+    sandbox: Res<Sandbox>,
+    stress_test: Res<StressTest>,
+    clock: Res<RunClock>,
+    round_timer: Res<RoundTimer>,
+    mut exit_events: EventReader<AppExit>,
+    fruit_query: Query<&Fruit, Without<Bomb>>,
+    player_query: Query<&FruitIterator, With<Player>>,
+){
+    if exit_events.iter().next().is_none() {
+        return;
+    }
+    // the saved run, if any, is still waiting for the player
+    if attract.active || replay.active || sandbox.placed || stress_test.active || round_timer.limit.is_some() {
+        return;
+    }
+    if *game_state.get() != GameState::Playing {
+        // a missing file is what we want anyway
+        let _ = fs::remove_file(run_path());
+        return;
+    }
+    if !settings.save_on_exit {
+        return;
+    }
+    let Ok(fruit_iterator) = player_query.get_single() else {
+        return;
+    };
+    let run = SavedRun {
+        score: scoreboard.score,
+        next_id: fruit_iterator.next_id,
+        next_group: fruit_iterator.next_group,
+        clock: clock.seconds(),
+        fruits: fruit_query
+            .iter()
+            .map(|fruit| SavedFruit {
+                id: fruit.id,
+                group: fruit.group,
+                pos: fruit.pos,
+                pos_last: fruit.pos_last,
+                a_pos: fruit.a_pos,
+                a_pos_last: fruit.a_pos_last,
+            })
+            .collect(),
+    };
+    let result = ron::to_string(&run)
+        .map_err(|err| err.to_string())
+        .and_then(|contents| {
+            fs::create_dir_all(SAVE_DIR)
+                .and_then(|_| fs::write(run_path(), contents))
+                .map_err(|err| err.to_string())
+        });
+    if let Err(err) = result {
+        warn!("Could not save the run: {}", err);
+    }
+}
+
+// The save is removed once read, so a run is only ever resumed once
+fn resume_run(
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    attract: Res<AttractMode>,
+    replay: Res<ReplayPlayer>,
+    recorder: Res<ReplayRecorder>,
+    sandbox: Res<Sandbox>,
+    stress_test: Res<StressTest>,
+    seed_chosen: Res<SeedChosen>,
+    fruit_table: Res<FruitTable>,
+    fruit_assets: Res<FruitAssets>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut clock: ResMut<RunClock>,
+    mut biggest_fruit: ResMut<BiggestFruit>,
+    mut player_query: Query<(&mut FruitIterator, &mut Sprite), With<Player>>,
+){
+    // a demo, a replay, the sandbox and the stress test have boards of their own, and a time
+    // attack run starts empty
+    if attract.active || replay.active || settings.time_attack || sandbox.active || stress_test.active {
+        return;
+    }
+    // resuming would draw the rest of the run from another seed than the one asked for or recorded
+    if seed_chosen.0 || recorder.recording() {
+        return;
+    }
+    let Ok(contents) = fs::read_to_string(run_path()) else {
+        return;
+    };
+    let _ = fs::remove_file(run_path());
+    let run: SavedRun = match ron::from_str(&contents) {
+        Ok(run) => run,
+        Err(err) => {
+            warn!("Could not read the saved run, starting a new one: {}", err);
+            return;
+        }
+    };
+    let Ok((mut fruit_iterator, mut sprite)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    // a save from a game with a different fruit table keeps only the fruits this one has
+    let groups: usize = fruit_table.len();
+    for saved in run.fruits.iter().filter(|saved| (saved.group as usize) < groups) {
+        let mut bundle = FruitBundle::new(saved.id, saved.group, saved.pos, &fruit_table, fruit_assets.texture(saved.group));
+        bundle.fruit.pos_last = saved.pos_last;
+        bundle.fruit.a_pos = saved.a_pos;
+        bundle.fruit.a_pos_last = saved.a_pos_last;
+        spawn_fruit_bundle(&mut commands, &fruit_assets, bundle);
+        biggest_fruit.record(saved.group);
+    }
+    scoreboard.score = run.score;
+    // a hand-edited save could hold a negative or NaN time
+    clock.0 = Duration::try_from_secs_f32(run.clock).unwrap_or_default();
+    fruit_iterator.next_id = run.next_id;
+    if (run.next_group as usize) < groups {
+        fruit_iterator.next_group = run.next_group;
+    }
+    fruit_iterator.next_is_bomb = false;
+    sprite.custom_size = Some(Vec2::splat(2.0*fruit_table.get(fruit_iterator.next_group).radius));
+    sprite.color = held_color(&fruit_iterator, &fruit_table);
+
+    let seed: u64 = rand::random();
+    commands.insert_resource(GameSeed(seed));
+    commands.insert_resource(GameRng(StdRng::seed_from_u64(seed)));
+}
@@ -0,0 +1,86 @@
+// Timing for physics_update on a large pile, without a window or any other system.
+//
+//   cargo run --release -- --bench-physics
+//
+// CollisionPile is the same idea for one collision pass, used by the Criterion bench:
+//
+//   cargo bench --bench collision
+
+use bevy::prelude::*;
+use std::time::Instant;
+
+use crate::{
+    physics_update, resolve_collisions, Fruit, FruitBundle, FruitTable, PhysicsConfig, LEFT_WALL, RIGHT_WALL, BOTTOM_WALL,
+    TOP_WALL, WALL_THICKNESS,
+};
+
+const BENCH_FRUITS: u32 = 1000;
+const BENCH_STEPS: u32 = 1000;
+const BENCH_DT: f32 = 1.0 / 60.0;
+const BENCH_COLUMNS: u32 = 40;
+const PILE_OVERLAP: f32 = 0.98; // spacing between pile neighbors as a fraction of their diameter, so every pair in contact overlaps
+
+pub fn run() {
+    let mut world = World::new();
+    world.insert_resource(FixedTime::new_from_secs(BENCH_DT));
+    world.insert_resource(PhysicsConfig::default());
+    let fruit_table = FruitTable::default();
+    // a grid filling the arena, each fruit with a little sideways velocity so nothing is trivially still
+    let spacing: Vec2 = Vec2::new(
+        (RIGHT_WALL - LEFT_WALL) / BENCH_COLUMNS as f32,
+        (TOP_WALL - BOTTOM_WALL) / (BENCH_FRUITS / BENCH_COLUMNS) as f32,
+    );
+    for id in 0..BENCH_FRUITS {
+        let cell: Vec2 = Vec2::new((id % BENCH_COLUMNS) as f32, (id / BENCH_COLUMNS) as f32);
+        let pos: Vec2 = Vec2::new(LEFT_WALL, BOTTOM_WALL) + (cell + 0.5) * spacing;
+        let mut bundle = FruitBundle::new(id, 0, pos, &fruit_table, Handle::default());
+        bundle.fruit.pos_last = pos - Vec2::new(if id % 2 == 0 { 1.0 } else { -1.0 }, 0.0);
+        world.spawn(bundle);
+    }
+
+    let mut schedule = Schedule::new();
+    schedule.add_systems(physics_update);
+    schedule.run(&mut world); // first run initializes the system
+
+    let start = Instant::now();
+    for _ in 0..BENCH_STEPS {
+        schedule.run(&mut world);
+    }
+    let per_step = start.elapsed() / BENCH_STEPS;
+    println!("physics_update: {} fruits, {:?} per step over {} steps", BENCH_FRUITS, per_step, BENCH_STEPS);
+}
+
+// Fruits packed hexagonally from the floor up, smallest tier, as a settled pile presses
+// them together. Counts that don't fit the arena just stack higher.
+pub struct CollisionPile {
+    fruits: Vec<Fruit>,
+}
+
+impl CollisionPile {
+    pub fn new(count: u32) -> CollisionPile {
+        let fruit_table = FruitTable::default();
+        let radius: f32 = fruit_table.get(0).radius;
+        let spacing: f32 = 2.0 * radius * PILE_OVERLAP;
+        let inner_left: f32 = LEFT_WALL + WALL_THICKNESS/2.0 + radius;
+        let columns: u32 = (((RIGHT_WALL - WALL_THICKNESS/2.0 - radius - inner_left) / spacing) as u32).max(1);
+        let fruits: Vec<Fruit> = (0..count)
+            .map(|id| {
+                let row: u32 = id / columns;
+                // odd rows sit in the hollows of the row below
+                let shift: f32 = if row % 2 == 1 { spacing / 2.0 } else { 0.0 };
+                let pos: Vec2 = Vec2::new(
+                    inner_left + shift + (id % columns) as f32 * spacing,
+                    BOTTOM_WALL + WALL_THICKNESS/2.0 + radius + row as f32 * spacing * 3f32.sqrt() / 2.0,
+                );
+                return FruitBundle::new(id, 0, pos, &fruit_table, Handle::default()).fruit;
+            })
+            .collect();
+        return CollisionPile { fruits };
+    }
+
+    // One apply_collisions pass
+    pub fn step(&mut self) {
+        let mut fruits: Vec<&mut Fruit> = self.fruits.iter_mut().collect();
+        resolve_collisions(&mut fruits, PhysicsConfig::default().max_correction, false, BENCH_DT, &mut 0, None);
+    }
+}
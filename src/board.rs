@@ -0,0 +1,120 @@
+// Read-only view of the board for bots, tools and debugging.
+// BoardSnapshot is refreshed at the end of every FixedUpdate step and can also be built on
+// demand from a World with BoardSnapshot::from_world.
+// Debug builds print it to stdout with F1, one `key=value` record per line: a `board` line, then
+// a `fruit` line per fruit. On a terminal each group number is colored, piped it's plain text.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+#[cfg(debug_assertions)]
+use std::io::IsTerminal;
+
+use crate::{Bomb, Fruit, FruitIterator, Player, Scoreboard};
+
+#[cfg(debug_assertions)]
+const DUMP_KEY: KeyCode = KeyCode::F1;
+#[cfg(debug_assertions)]
+const GROUP_COLORS: [u8; 6] = [31, 32, 33, 34, 35, 36]; // ANSI foreground codes, cycled by group
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FruitSnapshot {
+    pub id: u32,
+    pub group: u8,
+    pub pos: Vec2,
+    pub vel: Vec2,
+    pub radius: f32,
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BoardSnapshot {
+    pub fruits: Vec<FruitSnapshot>, // sorted by id
+    pub current_group: u8, // fruit held by the player, dropped next
+    pub player_x: f32,
+    pub score: u32,
+}
+
+impl BoardSnapshot {
+    fn build<'a>(
+        fruits: impl Iterator<Item = &'a Fruit>,
+        player_x: f32,
+        current_group: u8,
+        score: u32,
+        dt: f32,
+    ) -> BoardSnapshot {
+        let mut fruits: Vec<FruitSnapshot> = fruits
+            .map(|fruit| FruitSnapshot {
+                id: fruit.id,
+                group: fruit.group,
+                pos: fruit.pos,
+                vel: fruit.get_vel(dt),
+                radius: fruit.radius,
+            })
+            .collect();
+        fruits.sort_by_key(|fruit| fruit.id);
+        BoardSnapshot {
+            fruits,
+            current_group,
+            player_x,
+            score,
+        }
+    }
+
+    // Builds a fresh snapshot outside the schedule, e.g. from a test or an external driver
+    pub fn from_world(world: &mut World) -> BoardSnapshot {
+        let dt: f32 = world.resource::<FixedTime>().period.as_secs_f32();
+        let score: u32 = world.resource::<Scoreboard>().score;
+        let mut player_query = world.query_filtered::<(&Transform, &FruitIterator), With<Player>>();
+        let (player_x, current_group) = player_query
+            .get_single(world)
+            .map(|(transform, fruit_iterator)| (transform.translation.x, fruit_iterator.next_group))
+            .unwrap_or((0.0, 0));
+        let mut fruit_query = world.query_filtered::<&Fruit, Without<Bomb>>();
+        return BoardSnapshot::build(fruit_query.iter(world), player_x, current_group, score, dt);
+    }
+}
+
+pub fn update_board_snapshot(
+    time_step: Res<FixedTime>,
+    scoreboard: Res<Scoreboard>,
+    fruit_query: Query<&Fruit, Without<Bomb>>, // a bomb isn't part of the pile
+    player_query: Query<(&Transform, &FruitIterator), With<Player>>,
+    mut snapshot: ResMut<BoardSnapshot>,
+){
+    let Ok((player_transform, fruit_iterator)) = player_query.get_single() else {
+        return;
+    };
+    *snapshot = BoardSnapshot::build(
+        fruit_query.iter(),
+        player_transform.translation.x,
+        fruit_iterator.next_group,
+        scoreboard.score,
+        time_step.period.as_secs_f32(),
+    );
+}
+
+#[cfg(debug_assertions)]
+pub fn dump_board(
+    keys: Res<Input<KeyCode>>,
+    snapshot: Res<BoardSnapshot>,
+){
+    if !keys.just_pressed(DUMP_KEY) {
+        return;
+    }
+    let color: bool = std::io::stdout().is_terminal();
+    let group = |group: u8| -> String {
+        if !color {
+            return group.to_string();
+        }
+        return format!("\x1b[{}m{}\x1b[0m", GROUP_COLORS[group as usize % GROUP_COLORS.len()], group);
+    };
+    println!(
+        "board score={} next={} player_x={:.2} fruits={}",
+        snapshot.score, group(snapshot.current_group), snapshot.player_x, snapshot.fruits.len()
+    );
+    for fruit in snapshot.fruits.iter() {
+        println!(
+            "fruit id={} group={} pos={:.2},{:.2} vel={:.2},{:.2} radius={:.2}",
+            fruit.id, group(fruit.group), fruit.pos.x, fruit.pos.y, fruit.vel.x, fruit.vel.y, fruit.radius
+        );
+    }
+}
@@ -0,0 +1,144 @@
+// Camera follow for the tall arena (--tall). The view scrolls up as the pile grows and back
+// down as it shrinks, always keeping some headroom above the highest settled fruit. The player
+// rides along just below the top of the view, so there's always room to drop.
+//
+// Windows too small for the arena zoom the camera out until the walls, the floor and the held
+// fruit all fit, centered on them. Bigger windows keep the unscaled view.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::{
+    ArenaConfig, Fruit, FruitTable, Player, BOTTOM_WALL, LEFT_WALL, PLAYER_ABOVE_TOP_WALL, RIGHT_WALL, TOP_WALL,
+    WALL_THICKNESS,
+};
+
+const CAMERA_HEADROOM: f32 = 250.0; // px of view kept above the highest settled fruit
+const CAMERA_SMOOTHING: f32 = 3.0; // per second, how quickly the camera closes on its target
+const CAMERA_SETTLED_SPEED: f32 = 150.0; // px/s, faster fruits (falling, bouncing) are ignored
+const CAMERA_TOP_MARGIN: f32 = 100.0; // px shown above the player once the camera reaches the top
+const PLAYER_VIEW_MARGIN: f32 = 80.0; // px between the player and the top of the view
+const FIT_MARGIN: f32 = 20.0; // px kept around the arena when zoomed out to fit the window
+
+#[derive(Resource, Default)]
+pub struct CameraFollow {
+    pub enabled: bool,
+    pub y: f32, // camera height before screen shake, 0.0 is the fixed view
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraFollow>()
+            .add_systems(Update, (fit_arena_to_window, camera_follow).chain());
+    }
+}
+
+// World-space box that has to be visible: the walls, the floor and the held fruit above the
+// top wall. The tall arena only needs its bottom screenful, the rest scrolls into view.
+fn arena_view(fruit_table: &FruitTable) -> Rect {
+    let held_radius: f32 = fruit_table.get((fruit_table.spawnable - 1) as u8).radius;
+    return Rect::new(
+        LEFT_WALL - WALL_THICKNESS/2.0 - FIT_MARGIN,
+        BOTTOM_WALL - WALL_THICKNESS/2.0 - FIT_MARGIN,
+        RIGHT_WALL + WALL_THICKNESS/2.0 + FIT_MARGIN,
+        TOP_WALL + PLAYER_ABOVE_TOP_WALL + held_radius + FIT_MARGIN,
+    );
+}
+
+fn fit_arena_to_window(
+    fruit_table: Res<FruitTable>,
+    mut follow: ResMut<CameraFollow>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+){
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    if window.width() <= 0.0 || window.height() <= 0.0 {
+        return; // minimized
+    }
+    let view: Rect = arena_view(&fruit_table);
+    let scale: f32 = (view.width() / window.width()).max(view.height() / window.height()).max(1.0);
+    for (mut transform, mut projection) in camera_query.iter_mut() {
+        if projection.scale != scale {
+            projection.scale = scale;
+        }
+        if !follow.enabled {
+            // camera_follow moves the camera itself in the tall arena
+            follow.y = if scale > 1.0 { view.center().y } else { 0.0 };
+            transform.translation.y = follow.y;
+        }
+    }
+}
+
+pub fn camera_follow(
+    time: Res<Time>,
+    time_step: Res<FixedTime>,
+    arena_config: Res<ArenaConfig>,
+    mut follow: ResMut<CameraFollow>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    fruit_query: Query<&Fruit>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), (With<Camera2d>, Without<Player>)>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+){
+    if !follow.enabled {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Ok((mut camera_transform, projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+    let dt: f32 = time_step.period.as_secs_f32();
+    let half_height: f32 = window.height() * projection.scale / 2.0;
+
+    // a fruit in flight would yank the camera around, only count those resting on the pile
+    let highest: f32 = fruit_query
+        .iter()
+        .filter(|fruit| fruit.get_vel(dt).length() < CAMERA_SETTLED_SPEED)
+        .map(|fruit| fruit.pos.y + fruit.radius)
+        .fold(BOTTOM_WALL, f32::max);
+    let top_limit: f32 = (arena_config.top_wall + PLAYER_ABOVE_TOP_WALL + CAMERA_TOP_MARGIN - half_height).max(0.0);
+    let target: f32 = (highest + CAMERA_HEADROOM - half_height).clamp(0.0, top_limit);
+
+    // exponential smoothing, frame rate independent
+    follow.y += (target - follow.y) * (1.0 - (-CAMERA_SMOOTHING * time.delta_seconds()).exp());
+
+    camera_transform.translation.y = follow.y;
+    for mut transform in player_query.iter_mut() {
+        transform.translation.y = (follow.y + half_height - PLAYER_VIEW_MARGIN)
+            .min(arena_config.top_wall + PLAYER_ABOVE_TOP_WALL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Camera scale and height after fitting the arena into a window of the given size
+    fn fitted(width: f32, height: f32) -> (f32, f32) {
+        let mut world: World = World::new();
+        world.insert_resource(FruitTable::default());
+        world.init_resource::<CameraFollow>();
+        let mut window: Window = Window::default();
+        window.resolution.set(width, height);
+        world.spawn((window, PrimaryWindow));
+        let camera: Entity = world.spawn((Transform::default(), OrthographicProjection::default(), Camera2d::default())).id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(fit_arena_to_window);
+        schedule.run(&mut world);
+        return (world.get::<OrthographicProjection>(camera).unwrap().scale, world.get::<Transform>(camera).unwrap().translation.y);
+    }
+
+    #[test]
+    fn small_windows_zoom_out_around_the_arena() {
+        let view: Rect = arena_view(&FruitTable::default());
+        let (scale, y) = fitted(view.width() / 2.0, view.height());
+        assert!((scale - 2.0).abs() < 1e-4);
+        assert_eq!(y, view.center().y);
+        assert_eq!(fitted(2.0 * view.width(), 2.0 * view.height()), (1.0, 0.0));
+    }
+}
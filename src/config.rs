@@ -0,0 +1,188 @@
+// Physics tuning loaded from PHYSICS_CONFIG_PATH (RON). Missing fields keep their defaults and a
+// missing file keeps them all. Out-of-range values are clamped to something the simulation can
+// run with rather than rejected, with a warning saying what was changed.
+
+use bevy::prelude::*;
+use std::fs;
+use std::time::Duration;
+
+use crate::PhysicsConfig;
+
+const PHYSICS_CONFIG_PATH: &str = "physics.ron";
+
+const MAX_GRAVITY: f32 = 20000.0; // px/s^2
+const MIN_GRAVITY_SIZE_FACTOR: f32 = 0.1;
+const MAX_GRAVITY_SIZE_FACTOR: f32 = 10.0;
+const MIN_MAX_CORRECTION: f32 = 0.1; // px, anything smaller never separates overlapping fruits
+const MIN_TIMESTEP: f32 = 1.0 / 480.0;
+const MAX_TIMESTEP: f32 = 1.0 / 15.0; // longer steps tunnel small fruits through each other
+const MAX_MAGNET_RANGE: f32 = 200.0; // px, further and the magnet reaches across the arena
+const MIN_FALL_SPEED: f32 = 50.0; // px/s, slower and dropped fruits float down
+const MAX_NUDGE_SPEED: f32 = 300.0; // px/s, faster and a nudge slams the pile into the wall
+const MAX_FLOOR_RISE: f32 = 100.0; // px/s, faster and the floor fills the arena before a fruit lands
+
+// Clamps `value` into `min ..= max`, replacing NaN with `fallback`. Describes any change in `problems`.
+fn clamp_field(name: &str, value: &mut f32, min: f32, max: f32, fallback: f32, problems: &mut Vec<String>) {
+    let clamped: f32 = if value.is_nan() { fallback } else { value.clamp(min, max) };
+    if clamped != *value {
+        problems.push(format!("{} = {} is outside {} ..= {}, using {}", name, value, min, max, clamped));
+        *value = clamped;
+    }
+}
+
+impl PhysicsConfig {
+    // Returns the clamped config, plus a description of each out-of-range value
+    fn clamped(&self) -> (PhysicsConfig, Vec<String>) {
+        let defaults: PhysicsConfig = PhysicsConfig::default();
+        let mut config: PhysicsConfig = self.clone();
+        let mut problems: Vec<String> = Vec::new();
+        // gravity is a magnitude, the direction is always down
+        clamp_field("gravity", &mut config.gravity, 0.0, MAX_GRAVITY, defaults.gravity, &mut problems);
+        clamp_field("gravity_size_factor", &mut config.gravity_size_factor,
+            MIN_GRAVITY_SIZE_FACTOR, MAX_GRAVITY_SIZE_FACTOR, defaults.gravity_size_factor, &mut problems);
+        clamp_field("max_correction", &mut config.max_correction,
+            MIN_MAX_CORRECTION, f32::INFINITY, defaults.max_correction, &mut problems);
+        // > 1.0 would add energy on every bounce
+        clamp_field("wall_bounce", &mut config.wall_bounce, 0.0, 1.0, defaults.wall_bounce, &mut problems);
+        // 0.0 would divide by zero in every velocity calculation
+        clamp_field("timestep", &mut config.timestep, MIN_TIMESTEP, MAX_TIMESTEP, defaults.timestep, &mut problems);
+        // a speed, so negative is meaningless
+        clamp_field("merge_max_rel_speed", &mut config.merge_max_rel_speed,
+            0.0, f32::INFINITY, defaults.merge_max_rel_speed, &mut problems);
+        clamp_field("merge_spawn_bias", &mut config.merge_spawn_bias, 0.0, 1.0, defaults.merge_spawn_bias, &mut problems);
+        clamp_field("magnet_range", &mut config.magnet_range, 0.0, MAX_MAGNET_RANGE, defaults.magnet_range, &mut problems);
+        clamp_field("max_fall_speed", &mut config.max_fall_speed,
+            MIN_FALL_SPEED, f32::INFINITY, defaults.max_fall_speed, &mut problems);
+        // > 1.0 would reverse the spin
+        clamp_field("angular_damping", &mut config.angular_damping, 0.0, 1.0, defaults.angular_damping, &mut problems);
+        clamp_field("air_damping", &mut config.air_damping, 0.0, 1.0, defaults.air_damping, &mut problems);
+        clamp_field("nudge_speed", &mut config.nudge_speed, 0.0, MAX_NUDGE_SPEED, defaults.nudge_speed, &mut problems);
+        clamp_field("nudge_cooldown", &mut config.nudge_cooldown, 0.0, f32::INFINITY, defaults.nudge_cooldown, &mut problems);
+        clamp_field("merge_cooldown", &mut config.merge_cooldown, 0.0, f32::INFINITY, defaults.merge_cooldown, &mut problems);
+        // negative would slowly switch gravity off
+        clamp_field("gravity_ramp", &mut config.gravity_ramp, 0.0, f32::INFINITY, defaults.gravity_ramp, &mut problems);
+        // negative would sink the floor out of the arena
+        clamp_field("floor_rise", &mut config.floor_rise, 0.0, MAX_FLOOR_RISE, defaults.floor_rise, &mut problems);
+        return (config, problems);
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let (_, problems) = self.clamped();
+        if problems.is_empty() {
+            return Ok(());
+        }
+        return Err(problems.join("; "));
+    }
+}
+
+fn parse_physics_config(contents: &str) -> PhysicsConfig {
+    let config: PhysicsConfig = match ron::from_str(contents) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!("Could not parse {}: {}, using the default physics", PHYSICS_CONFIG_PATH, err);
+            return PhysicsConfig::default();
+        }
+    };
+    if let Err(problems) = config.validate() {
+        warn!("Invalid values in {}: {}", PHYSICS_CONFIG_PATH, problems);
+        return config.clamped().0;
+    }
+    return config;
+}
+
+pub fn load_physics_config() -> PhysicsConfig {
+    let Ok(contents) = fs::read_to_string(PHYSICS_CONFIG_PATH) else {
+        return PhysicsConfig::default();
+    };
+    return parse_physics_config(&contents);
+}
+
+// Every physics system divides by the FixedUpdate period, so one that's zero, negative or long
+// enough to tunnel (set by hand, or by a plugin) is put back to the configured timestep before
+// the simulation steps, or to the default if that's unusable too.
+pub fn sanitize_timestep(
+    physics_config: Res<PhysicsConfig>,
+    mut time_step: ResMut<FixedTime>,
+){
+    let dt: f32 = time_step.period.as_secs_f32();
+    if (MIN_TIMESTEP..=MAX_TIMESTEP).contains(&dt) {
+        return;
+    }
+    let fallback: f32 = if (MIN_TIMESTEP..=MAX_TIMESTEP).contains(&physics_config.timestep) {
+        physics_config.timestep
+    } else {
+        PhysicsConfig::default().timestep
+    };
+    warn!("FixedUpdate period of {} s is outside {} ..= {}, using {}", dt, MIN_TIMESTEP, MAX_TIMESTEP, fallback);
+    time_step.period = Duration::from_secs_f32(fallback);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{integrate, Kinematics};
+
+    #[test]
+    fn missing_fields_keep_defaults_and_bad_ones_are_clamped() {
+        let defaults: PhysicsConfig = PhysicsConfig::default();
+        let config: PhysicsConfig = parse_physics_config("(gravity: -5.0, wall_bounce: 2.0, gravity_size_factor: 2.0)");
+        assert_eq!(config.gravity, 0.0);
+        assert_eq!(config.wall_bounce, 1.0);
+        assert_eq!(config.gravity_size_factor, 2.0);
+        assert_eq!(config.timestep, defaults.timestep);
+        assert!(config.validate().is_ok());
+
+        let unparsable: PhysicsConfig = parse_physics_config("gravity = 5");
+        assert_eq!(unparsable.gravity, defaults.gravity);
+    }
+
+    #[test]
+    fn air_damping_is_a_fraction_and_none_leaves_velocity_alone() {
+        assert_eq!(parse_physics_config("(air_damping: 0.5)").air_damping, 0.5);
+        assert_eq!(parse_physics_config("(air_damping: 2.0)").air_damping, 1.0);
+        assert_eq!(parse_physics_config("(air_damping: -0.5)").air_damping, 0.0);
+
+        let undamped: PhysicsConfig = PhysicsConfig::default();
+        assert_eq!(undamped.air_damping, 0.0);
+        let dt: f32 = 0.01;
+        let mut body: Kinematics = Kinematics {
+            pos: Vec2::ZERO,
+            pos_last: Vec2::new(-1.0, 0.5),
+            acc: Vec2::ZERO,
+            a_pos: 0.0,
+            a_pos_last: 0.0,
+            a_acc: 0.0,
+            spin_locked: false,
+        };
+        for _ in 0..10 {
+            integrate(&mut body, dt, &undamped);
+        }
+        assert!(((body.pos - body.pos_last) / dt - Vec2::new(100.0, -50.0)).length() < 1e-2);
+    }
+
+    #[test]
+    fn nan_falls_back_to_the_default() {
+        let config: PhysicsConfig = PhysicsConfig { timestep: f32::NAN, ..default() };
+        let (clamped, problems) = config.clamped();
+        assert_eq!(clamped.timestep, PhysicsConfig::default().timestep);
+        assert_eq!(problems.len(), 1);
+    }
+
+    // The FixedUpdate period after sanitize_timestep, starting from `period` seconds
+    fn sanitized(period: f32, configured: f32) -> f32 {
+        let mut world: World = World::new();
+        world.insert_resource(PhysicsConfig { timestep: configured, ..default() });
+        world.insert_resource(FixedTime::new(Duration::from_secs_f32(period)));
+        let mut schedule = Schedule::default();
+        schedule.add_systems(sanitize_timestep);
+        schedule.run(&mut world);
+        return world.resource::<FixedTime>().period.as_secs_f32();
+    }
+
+    #[test]
+    fn unusable_periods_fall_back_to_the_configured_timestep() {
+        assert_eq!(sanitized(1.0 / 60.0, 1.0 / 120.0), 1.0 / 60.0);
+        assert!((sanitized(0.0, 1.0 / 120.0) - 1.0 / 120.0).abs() < 1e-6);
+        assert!((sanitized(1.0, 0.0) - PhysicsConfig::default().timestep).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,188 @@
+// Physics debug overlay (F3): every fruit's velocity as an arrow from its center, and the contact
+// normals found by apply_collisions in the last step.
+// Merge overlay (F12): a line between every same-group pair, showing how close apply_merges is to
+// merging them. Green will merge, yellow is touching but too fast or still on its merge cooldown,
+// or a near miss within MERGE_NEAR_MISS px, red is apart.
+// Fruit ids (I, debug builds only): each fruit's id drawn upright on it, to match fruits on screen
+// with ids in logs and board dumps.
+
+use bevy::prelude::*;
+
+use crate::{Bomb, Fruit, FruitPart, FruitTable, MergeCooldown, PhysicsConfig, TEXT_COLOR};
+
+const DEBUG_DRAW_KEY: KeyCode = KeyCode::F3;
+const MERGE_DEBUG_KEY: KeyCode = KeyCode::F12;
+const MERGE_NEAR_MISS: f32 = 5.0; // px of gap still drawn yellow
+const MERGE_COLOR: Color = Color::rgb(0.1, 0.9, 0.1);
+const MERGE_NEAR_COLOR: Color = Color::rgb(0.95, 0.85, 0.1);
+const MERGE_APART_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+#[cfg(debug_assertions)]
+const FRUIT_ID_KEY: KeyCode = KeyCode::I;
+const FRUIT_ID_FONT_SIZE: f32 = 16.0;
+const FRUIT_ID_Z: f32 = 5.0; // above the fruit and its decals
+const VELOCITY_SCALE: f32 = 0.1; // arrow length in px per px/s
+const VELOCITY_COLOR: Color = Color::rgb(0.1, 0.6, 0.1);
+const NORMAL_LENGTH: f32 = 15.0;
+const NORMAL_COLOR: Color = Color::rgb(0.9, 0.1, 0.9);
+const ARROW_HEAD_LENGTH: f32 = 6.0;
+const ARROW_HEAD_ANGLE: f32 = 0.5; // radians either side of the shaft
+
+#[derive(Resource, Default)]
+pub struct DebugDraw {
+    pub enabled: bool,
+    pub contacts: Vec<(Vec2, Vec2)>, // (point, normal), only collected while enabled
+    merges: bool, // the merge overlay, independent of the rest
+    ids: bool,
+}
+
+#[derive(Component)]
+struct FruitIdLabel;
+
+// On a fruit that has a FruitIdLabel child
+#[derive(Component)]
+struct Labelled;
+
+pub struct DebugDrawPlugin;
+
+impl Plugin for DebugDrawPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (toggle_debug_draw, draw_debug, draw_merge_debug, update_fruit_ids, keep_ids_upright).chain());
+    }
+}
+
+fn toggle_debug_draw(
+    input: Res<Input<KeyCode>>,
+    mut debug_draw: ResMut<DebugDraw>,
+){
+    if input.just_pressed(DEBUG_DRAW_KEY) {
+        debug_draw.enabled = !debug_draw.enabled;
+        debug_draw.contacts.clear();
+    }
+    if input.just_pressed(MERGE_DEBUG_KEY) {
+        debug_draw.merges = !debug_draw.merges;
+    }
+    #[cfg(debug_assertions)]
+    if input.just_pressed(FRUIT_ID_KEY) {
+        debug_draw.ids = !debug_draw.ids;
+    }
+}
+
+// Gizmos has no arrows yet, so the head is two short lines
+fn arrow(gizmos: &mut Gizmos, start: Vec2, end: Vec2, color: Color) {
+    gizmos.line_2d(start, end, color);
+    let back: Vec2 = (start - end).normalize_or_zero() * ARROW_HEAD_LENGTH;
+    if back == Vec2::ZERO {
+        return;
+    }
+    for angle in [ARROW_HEAD_ANGLE, -ARROW_HEAD_ANGLE] {
+        gizmos.line_2d(end, end + Vec2::from_angle(angle).rotate(back), color);
+    }
+}
+
+fn draw_debug(
+    mut gizmos: Gizmos,
+    time_step: Res<FixedTime>,
+    debug_draw: Res<DebugDraw>,
+    fruit_query: Query<&Fruit>,
+){
+    if !debug_draw.enabled {
+        return;
+    }
+    let dt = time_step.period.as_secs_f32();
+    for fruit in fruit_query.iter() {
+        arrow(&mut gizmos, fruit.pos, fruit.pos + fruit.get_vel(dt) * VELOCITY_SCALE, VELOCITY_COLOR);
+    }
+    for (point, normal) in debug_draw.contacts.iter() {
+        arrow(&mut gizmos, *point, *point + *normal * NORMAL_LENGTH, NORMAL_COLOR);
+    }
+}
+
+// The same test as apply_merges, so a pair that looked like it should have merged can be checked
+fn draw_merge_debug(
+    mut gizmos: Gizmos,
+    time_step: Res<FixedTime>,
+    debug_draw: Res<DebugDraw>,
+    fruit_table: Res<FruitTable>,
+    physics_config: Res<PhysicsConfig>,
+    fruit_query: Query<(&Fruit, Option<&MergeCooldown>), Without<Bomb>>,
+){
+    if !debug_draw.merges {
+        return;
+    }
+    let dt = time_step.period.as_secs_f32();
+    let fruits: Vec<(&Fruit, bool)> = fruit_query
+        .iter()
+        .filter(|(fruit, _)| !fruit_table.is_top(fruit.group))
+        .map(|(fruit, cooldown)| (fruit, cooldown.is_some()))
+        .collect();
+    for (i, (a, a_cooling)) in fruits.iter().enumerate() {
+        for (b, b_cooling) in fruits[i+1..].iter().filter(|(b, _)| b.group == a.group) {
+            let gap: f32 = (b.pos - a.pos).length() - (a.radius + b.radius);
+            let slow: bool = (b.get_vel(dt) - a.get_vel(dt)).length() < physics_config.merge_max_rel_speed;
+            let ready: bool = !a_cooling && !b_cooling;
+            let color: Color = if gap < 0.0 && slow && ready {
+                MERGE_COLOR
+            } else if gap < MERGE_NEAR_MISS {
+                MERGE_NEAR_COLOR
+            } else {
+                MERGE_APART_COLOR
+            };
+            gizmos.line_2d(a.pos, b.pos, color);
+        }
+    }
+}
+
+// Labels new fruits as they spawn while ids are on, and removes every label when they go off
+fn update_fruit_ids(
+    mut commands: Commands,
+    debug_draw: Res<DebugDraw>,
+    fruit_query: Query<(Entity, &Fruit), Without<Labelled>>,
+    labelled_query: Query<Entity, With<Labelled>>,
+    label_query: Query<Entity, With<FruitIdLabel>>,
+){
+    if !debug_draw.ids {
+        if !debug_draw.is_changed() {
+            return;
+        }
+        for label in label_query.iter() {
+            commands.entity(label).despawn_recursive();
+        }
+        for entity in labelled_query.iter() {
+            commands.entity(entity).remove::<Labelled>();
+        }
+        return;
+    }
+    for (entity, fruit) in fruit_query.iter() {
+        let label: Entity = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        fruit.id.to_string(),
+                        TextStyle {
+                            font_size: FRUIT_ID_FONT_SIZE,
+                            color: TEXT_COLOR,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, 0.0, FRUIT_ID_Z),
+                    ..default()
+                },
+                FruitIdLabel,
+                FruitPart,
+            ))
+            .id();
+        commands.entity(entity).add_child(label).insert(Labelled);
+    }
+}
+
+// A label is a child of its fruit, so it follows it, but it would spin with it too
+fn keep_ids_upright(
+    fruit_query: Query<&Transform, (With<Labelled>, Without<FruitIdLabel>)>,
+    mut label_query: Query<(&Parent, &mut Transform), With<FruitIdLabel>>,
+){
+    for (parent, mut transform) in label_query.iter_mut() {
+        if let Ok(fruit_transform) = fruit_query.get(parent.get()) {
+            transform.rotation = fruit_transform.rotation.inverse();
+        }
+    }
+}
@@ -0,0 +1,187 @@
+// Dynamic difficulty: the spawn cooldown and the range of fruits handed to the player shift with
+// how full the arena is. Relief gives a rising pile smaller fruits and more time, Hard does the
+// opposite. Off by default; --relief or --hard-mode turns it on.
+//
+// The cooldown curve (settings screen) is the cooldown part on its own: more thinking time as the
+// pile nears the danger line, or less. It stacks with dynamic difficulty. Constant by default.
+//
+// The gravity ramp makes fruits fall and settle faster as a run goes on: gravity_ramp (physics
+// config) of the configured gravity is added every GRAVITY_RAMP_PERIOD, up to
+// GRAVITY_RAMP_MAX_SCALE times it. Off by default; --gravity-ramp turns it on. A restart puts
+// gravity back where it started.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::step::Simulation;
+use crate::{input_handler, ArenaConfig, Fruit, FruitTable, GameState, PhysicsConfig, SPAWN_INTERVAL};
+
+const RELIEF_MAX_COOLDOWN_SCALE: f32 = 1.5; // cooldown multiplier with the pile at the danger line
+const RELIEF_MIN_SPAWNABLE_SCALE: f32 = 0.5; // fraction of the spawnable groups kept at the danger line
+const HARD_MIN_COOLDOWN_SCALE: f32 = 0.6;
+const HARD_EXTRA_SPAWNABLE: f32 = 2.0; // extra (bigger) groups handed out at the danger line
+const CURVE_LENGTHEN_SCALE: f32 = 1.6; // cooldown multiplier at the danger line for CooldownCurve::Lengthen
+const CURVE_SHORTEN_SCALE: f32 = 0.7; // and for CooldownCurve::Shorten
+const CURVE_EXPONENT: f32 = 2.0; // > 1.0 holds the change back until the pile is near the line
+pub const GRAVITY_RAMP: f32 = 0.1; // gravity_ramp set by --gravity-ramp
+const GRAVITY_RAMP_PERIOD: f32 = 30.0; // seconds of play per gravity_ramp added
+const GRAVITY_RAMP_MAX_SCALE: f32 = 3.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DifficultyMode {
+    Relief,
+    Hard,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum CooldownCurve {
+    Constant,
+    Lengthen, // more time as the pile rises
+    Shorten,
+}
+
+impl CooldownCurve {
+    // Cooldown multiplier for a pile this full
+    fn scale(&self, fullness: f32) -> f32 {
+        let max_scale: f32 = match self {
+            CooldownCurve::Constant => return 1.0,
+            CooldownCurve::Lengthen => CURVE_LENGTHEN_SCALE,
+            CooldownCurve::Shorten => CURVE_SHORTEN_SCALE,
+        };
+        return 1.0 + (max_scale - 1.0) * fullness.powf(CURVE_EXPONENT);
+    }
+}
+
+#[derive(Resource)]
+pub struct DynamicDifficulty {
+    pub enabled: bool,
+    pub mode: DifficultyMode,
+    pub cooldown_curve: CooldownCurve,
+    fullness: f32, // 0.0 for an empty arena, 1.0 once the pile reaches the danger line
+}
+
+impl Default for DynamicDifficulty {
+    fn default() -> Self {
+        DynamicDifficulty {
+            enabled: false,
+            mode: DifficultyMode::Relief,
+            cooldown_curve: CooldownCurve::Constant,
+            fullness: 0.0,
+        }
+    }
+}
+
+impl DynamicDifficulty {
+    // Seconds the player waits between drops
+    pub fn spawn_interval(&self) -> f32 {
+        let curve: f32 = self.cooldown_curve.scale(self.fullness);
+        if !self.enabled {
+            return SPAWN_INTERVAL * curve;
+        }
+        let max_scale: f32 = match self.mode {
+            DifficultyMode::Relief => RELIEF_MAX_COOLDOWN_SCALE,
+            DifficultyMode::Hard => HARD_MIN_COOLDOWN_SCALE,
+        };
+        return SPAWN_INTERVAL * (1.0 + (max_scale - 1.0) * self.fullness) * curve;
+    }
+
+    // Number of groups, from the smallest, the next held fruit is picked from
+    pub fn spawnable(&self, fruit_table: &FruitTable) -> usize {
+        let base: f32 = fruit_table.spawnable as f32;
+        if !self.enabled {
+            return fruit_table.spawnable;
+        }
+        let spawnable: f32 = match self.mode {
+            DifficultyMode::Relief => base * (1.0 - (1.0 - RELIEF_MIN_SPAWNABLE_SCALE) * self.fullness),
+            DifficultyMode::Hard => base + HARD_EXTRA_SPAWNABLE * self.fullness,
+        };
+        // never the top tier, which can't merge any further
+        return (spawnable.round() as usize).clamp(1, (fruit_table.len() - 1).max(1));
+    }
+}
+
+// The configured gravity, kept while the ramp is raising PhysicsConfig::gravity
+#[derive(Resource, Default)]
+struct GravityRamp {
+    base: Option<f32>,
+    elapsed: f32, // seconds of this run
+}
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DynamicDifficulty>()
+            .init_resource::<GravityRamp>()
+            .add_systems(FixedUpdate, (update_difficulty, ramp_gravity).before(input_handler).in_set(Simulation))
+            .add_systems(OnEnter(GameState::Playing), reset_gravity_ramp);
+    }
+}
+
+fn update_difficulty(
+    arena_config: Res<ArenaConfig>,
+    fruit_query: Query<&Fruit>,
+    mut difficulty: ResMut<DynamicDifficulty>,
+){
+    if !difficulty.enabled && difficulty.cooldown_curve == CooldownCurve::Constant {
+        return;
+    }
+    let floor: f32 = arena_config.floor();
+    let top: f32 = fruit_query
+        .iter()
+        .map(|fruit| fruit.pos.y + fruit.radius)
+        .fold(floor, f32::max);
+    let range: f32 = (arena_config.danger_line_y - floor).max(1.0);
+    difficulty.fullness = ((top - floor) / range).clamp(0.0, 1.0);
+}
+
+fn ramp_gravity(
+    time_step: Res<FixedTime>,
+    mut ramp: ResMut<GravityRamp>,
+    mut physics_config: ResMut<PhysicsConfig>,
+){
+    if physics_config.gravity_ramp <= 0.0 && ramp.base.is_none() {
+        return;
+    }
+    ramp.elapsed += time_step.period.as_secs_f32();
+    let base: f32 = *ramp.base.get_or_insert(physics_config.gravity);
+    let scale: f32 = 1.0 + physics_config.gravity_ramp * ramp.elapsed / GRAVITY_RAMP_PERIOD;
+    physics_config.gravity = base * scale.min(GRAVITY_RAMP_MAX_SCALE);
+}
+
+fn reset_gravity_ramp(
+    mut ramp: ResMut<GravityRamp>,
+    mut physics_config: ResMut<PhysicsConfig>,
+){
+    if let Some(base) = ramp.base {
+        physics_config.gravity = base;
+    }
+    *ramp = GravityRamp::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gravity_ramps_up_to_its_cap_and_a_new_run_restores_it() {
+        let mut world: World = World::new();
+        let dt: f32 = 0.5;
+        world.insert_resource(FixedTime::new_from_secs(dt));
+        world.insert_resource(PhysicsConfig { gravity: 1000.0, gravity_ramp: 0.5, ..default() });
+        world.insert_resource(GravityRamp { base: None, elapsed: GRAVITY_RAMP_PERIOD - dt });
+        let mut ramp = Schedule::default();
+        ramp.add_systems(ramp_gravity);
+
+        ramp.run(&mut world);
+        assert!((world.resource::<PhysicsConfig>().gravity - 1500.0).abs() < 1e-2);
+        world.resource_mut::<GravityRamp>().elapsed = 100.0 * GRAVITY_RAMP_PERIOD;
+        ramp.run(&mut world);
+        assert!((world.resource::<PhysicsConfig>().gravity - 1000.0 * GRAVITY_RAMP_MAX_SCALE).abs() < 1e-2);
+
+        let mut reset = Schedule::default();
+        reset.add_systems(reset_gravity_ramp);
+        reset.run(&mut world);
+        assert_eq!(world.resource::<PhysicsConfig>().gravity, 1000.0);
+    }
+}
@@ -0,0 +1,58 @@
+// Screen shake for hard landings. Purely visual: the camera moves, the simulation doesn't.
+// The shake is an offset from CameraFollow's height, so it works in the tall arena too.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::camera::{camera_follow, CameraFollow};
+use crate::{HardLanding, HARD_LANDING_SPEED};
+
+const SHAKE_MAX_OFFSET: f32 = 8.0; // pixels at full trauma
+const SHAKE_DECAY: f32 = 2.5; // trauma lost per second
+const SHAKE_REFERENCE_RADIUS: f32 = 125.0; // a landing this big at twice HARD_LANDING_SPEED is full trauma
+
+// 0.0 ..= 1.0, the offset grows with its square so small thumps stay subtle
+#[derive(Resource, Default)]
+struct CameraShake {
+    trauma: f32,
+}
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraShake>()
+            .add_systems(Update, (
+                start_landing_shake,
+                shake_camera,
+            ).chain().after(camera_follow));
+    }
+}
+
+fn start_landing_shake(
+    mut shake: ResMut<CameraShake>,
+    mut landing_events: EventReader<HardLanding>,
+){
+    for landing in landing_events.iter() {
+        let strength: f32 = (landing.speed / (2.0 * HARD_LANDING_SPEED)) * (landing.radius / SHAKE_REFERENCE_RADIUS);
+        shake.trauma = shake.trauma.max(strength.min(1.0));
+    }
+}
+
+fn shake_camera(
+    time: Res<Time>,
+    follow: Res<CameraFollow>,
+    mut shake: ResMut<CameraShake>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+){
+    if shake.trauma <= 0.0 {
+        return;
+    }
+    shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_seconds()).max(0.0);
+    let amount: f32 = SHAKE_MAX_OFFSET * shake.trauma * shake.trauma;
+    let mut rng = rand::thread_rng();
+    for mut transform in camera_query.iter_mut() {
+        transform.translation.x = rng.gen_range(-1.0..=1.0) * amount;
+        transform.translation.y = follow.y + rng.gen_range(-1.0..=1.0) * amount;
+    }
+}
@@ -0,0 +1,132 @@
+// Merge estimate (F4): the score you'd gain if every touching same-group pair on the board
+// merged right now, cascades included. A heuristic lookahead read off the BoardSnapshot,
+// so it ignores how the merged fruits would roll afterwards. Off by default, it's a spoiler.
+
+use bevy::prelude::*;
+
+use crate::board::BoardSnapshot;
+use crate::locale::Localization;
+use crate::{FruitTable, OVERLAY_FONT_SIZE, SCOREBOARD_TEXT_PADDING};
+
+const ESTIMATE_KEY: KeyCode = KeyCode::F4;
+const ESTIMATE_INTERVAL: f32 = 0.25; // seconds between recomputes, the scan is O(n^2) per pass
+const ESTIMATE_RANGE: f32 = 2.0; // gap below which two resting fruits count as touching
+const ESTIMATE_TEXT_TOP: Val = Val::Px(75.0); // just below the biggest fruit
+const ESTIMATE_COLOR: Color = Color::rgba(0.5, 0.5, 1.0, 0.5); // faint TEXT_COLOR
+
+#[derive(Resource, Default)]
+pub struct MergeEstimate {
+    pub enabled: bool,
+    score: u32,
+}
+
+#[derive(Component)]
+struct MergeEstimateText;
+
+pub struct EstimatePlugin;
+
+impl Plugin for EstimatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MergeEstimate>()
+            .add_systems(Startup, spawn_estimate_text)
+            .add_systems(Update, (
+                toggle_estimate,
+                update_estimate,
+                update_estimate_text,
+            ).chain());
+    }
+}
+
+fn spawn_estimate_text(mut commands: Commands) {
+    let mut text = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: OVERLAY_FONT_SIZE,
+            color: ESTIMATE_COLOR,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: ESTIMATE_TEXT_TOP,
+        left: SCOREBOARD_TEXT_PADDING,
+        ..default()
+    });
+    text.visibility = Visibility::Hidden;
+    commands.spawn((text, MergeEstimateText));
+}
+
+fn toggle_estimate(
+    input: Res<Input<KeyCode>>,
+    mut estimate: ResMut<MergeEstimate>,
+){
+    if input.just_pressed(ESTIMATE_KEY) {
+        estimate.enabled = !estimate.enabled;
+    }
+}
+
+// Same passes and cascade multiplier as apply_merges, on (group, pos, radius) copies
+fn estimate_merge_score(snapshot: &BoardSnapshot, fruit_table: &FruitTable) -> u32 {
+    let mut fruits: Vec<(u8, Vec2, f32, bool)> = snapshot
+        .fruits
+        .iter()
+        .map(|fruit| (fruit.group, fruit.pos, fruit.radius, false))
+        .collect();
+    let mut score: u32 = 0;
+    let mut cascade_depth: u32 = 1;
+    let mut new_start: usize = 0;
+    loop {
+        let n: usize = fruits.len();
+        for i in 0..n {
+            for j in (i+1).max(new_start)..n {
+                let (a, b) = (fruits[i], fruits[j]);
+                if a.3 || b.3 || a.0 != b.0 || fruit_table.is_top(a.0) {
+                    continue;
+                }
+                if (b.1 - a.1).length() - a.2 - b.2 < ESTIMATE_RANGE {
+                    fruits[i].3 = true;
+                    fruits[j].3 = true;
+                    score += fruit_table.get(a.0).score * cascade_depth;
+                    fruits.push((a.0 + 1, (a.1 + b.1) / 2.0, fruit_table.get(a.0 + 1).radius, false));
+                }
+            }
+        }
+        if fruits.len() == n {
+            return score;
+        }
+        new_start = n;
+        cascade_depth += 1;
+    }
+}
+
+fn update_estimate(
+    time: Res<Time>,
+    snapshot: Res<BoardSnapshot>,
+    fruit_table: Res<FruitTable>,
+    mut estimate: ResMut<MergeEstimate>,
+    mut since_update: Local<f32>,
+){
+    if !estimate.enabled {
+        return;
+    }
+    *since_update += time.delta_seconds();
+    if *since_update < ESTIMATE_INTERVAL && !estimate.is_changed() {
+        return;
+    }
+    *since_update = 0.0;
+    estimate.score = estimate_merge_score(&snapshot, &fruit_table);
+}
+
+fn update_estimate_text(
+    estimate: Res<MergeEstimate>,
+    localization: Res<Localization>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<MergeEstimateText>>,
+){
+    if !estimate.is_changed() && !localization.is_changed() {
+        return;
+    }
+    for (mut text, mut visibility) in text_query.iter_mut() {
+        text.sections[0].value = localization.format("merge_estimate", &[&estimate.score]);
+        *visibility = if estimate.enabled { Visibility::Inherited } else { Visibility::Hidden };
+    }
+}
@@ -0,0 +1,426 @@
+// Game over. A settled fruit left above the danger line for GAME_OVER_DELAY ends the run, except
+// in the stress test, the sandbox and no-merge chaos mode, which are meant to fill up. The
+// simulation stops, and the GameOverSequence clears the board: fruits fly off from the top of the
+// pile down, shrinking and fading, then the final score, the biggest fruit made, the run's
+// share code, buttons to restart with a new seed or retry this one (see restart.rs) and one for
+// the high scores (see leaderboard.rs) are shown.
+// A time attack run ends here too when its time is up, with the same screen headed "Time's up".
+// Any key skips to the score. Debug builds can end the run on the spot with F11, to work on
+// everything that follows without having to lose first.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::leaderboard::LeaderboardButton;
+use crate::locale::Localization;
+use crate::restart::{RestartButton, RestartSeed};
+use crate::sandbox::sandbox_active;
+use crate::share::{share_code, ShareCodeText};
+use crate::step::Simulation;
+use crate::win::{RoundTimer, RunClock};
+use crate::{
+    merging_enabled, over_danger_line, physics_update, stress_test_active, ArenaConfig, BiggestFruit, Fruit, GameSeed,
+    GameState, Scoreboard, BOTTOM_WALL, OVERLAY_FONT_SIZE, SCOREBOARD_FONT_SIZE, SCORE_COLOR, TEXT_COLOR,
+};
+
+const GAME_OVER_DELAY: f32 = 2.0; // seconds over the line, so a bounce doesn't end the run
+const OVERFLOW_REST_SPEED: f32 = 100.0; // px/s, faster fruits over the line are passing through, not resting
+const CLEAR_STAGGER: f32 = 1.2; // seconds between the top and the bottom fruit starting to clear
+const CLEAR_DURATION: f32 = 0.6; // seconds each fruit takes to shrink away
+const CLEAR_SPEED: std::ops::Range<f32> = 200.0..400.0; // px/s of the initial fling
+const CLEAR_GRAVITY: f32 = 1200.0; // px/s^2, pulls the flung fruits back down as they fade
+const CLEAR_SPIN: f32 = 8.0; // rad/s at most
+const RESTART_BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.35);
+const RESTART_BUTTON_PADDING: f32 = 10.0;
+const RESTART_BUTTON_GAP: f32 = 20.0;
+#[cfg(debug_assertions)]
+const FORCE_GAME_OVER_KEY: KeyCode = KeyCode::F11;
+
+// Seconds the board has been overflowing, reset as soon as it isn't
+#[derive(Resource, Default)]
+struct OverflowTimer(f32);
+
+#[derive(Resource, Default)]
+pub struct GameOverSequence {
+    elapsed: f32,
+    finished: bool, // board cleared (or skipped), final score shown
+}
+
+// A fruit being cleared. Its Transform is animated directly, the simulation no longer runs.
+#[derive(Component)]
+struct Clearing {
+    delay: f32,
+    velocity: Vec2,
+    spin: f32,
+}
+
+#[derive(Component)]
+struct FinalScoreText;
+
+// Root of the final score screen
+#[derive(Component)]
+struct GameOverScreen;
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OverflowTimer>()
+            .init_resource::<GameOverSequence>()
+            .add_systems(FixedUpdate, check_overflow
+                .run_if(not(stress_test_active))
+                .run_if(not(sandbox_active))
+                .run_if(merging_enabled)
+                .after(physics_update).in_set(Simulation))
+            .add_systems(OnEnter(GameState::GameOver), start_game_over_sequence)
+            .add_systems(OnEnter(GameState::Playing), clear_game_over)
+            .add_systems(Update, (
+                skip_game_over_sequence,
+                animate_clearing,
+                show_final_score,
+            ).chain().run_if(in_state(GameState::GameOver)));
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, force_game_over.run_if(in_state(GameState::Playing)));
+    }
+}
+
+// True once the board is cleared and the final score is up
+pub fn final_score_shown(sequence: Res<GameOverSequence>) -> bool {
+    return sequence.finished;
+}
+
+fn check_overflow(
+    time_step: Res<FixedTime>,
+    arena_config: Res<ArenaConfig>,
+    fruit_query: Query<&Fruit>,
+    mut overflow: ResMut<OverflowTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+){
+    let dt = time_step.period.as_secs_f32();
+    // a fruit bounced up over the line doesn't start the timer, only one resting there
+    let overflowing: bool = fruit_query
+        .iter()
+        .any(|fruit| over_danger_line(fruit, &arena_config, dt) && fruit.get_vel(dt).length() < OVERFLOW_REST_SPEED);
+    if !overflowing {
+        overflow.0 = 0.0;
+        return;
+    }
+    overflow.0 += dt;
+    if overflow.0 >= GAME_OVER_DELAY {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+#[cfg(debug_assertions)]
+fn force_game_over(
+    keys: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+){
+    if keys.just_pressed(FORCE_GAME_OVER_KEY) {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+fn start_game_over_sequence(
+    mut commands: Commands,
+    arena_config: Res<ArenaConfig>,
+    mut sequence: ResMut<GameOverSequence>,
+    fruit_query: Query<(Entity, &Fruit)>,
+){
+    *sequence = GameOverSequence::default();
+    // cosmetic, so the thread rng rather than GameRng
+    let mut rng = rand::thread_rng();
+    let height: f32 = arena_config.top_wall - BOTTOM_WALL;
+    for (entity, fruit) in fruit_query.iter() {
+        let from_top: f32 = ((arena_config.top_wall - fruit.pos.y) / height).clamp(0.0, 1.0);
+        // outward from the center line, and up
+        let outward: f32 = if fruit.pos.x < 0.0 { -1.0 } else { 1.0 };
+        let direction: Vec2 = Vec2::new(outward * rng.gen_range(0.2..1.0), 1.0).normalize();
+        commands.entity(entity).insert(Clearing {
+            delay: from_top * CLEAR_STAGGER,
+            velocity: direction * rng.gen_range(CLEAR_SPEED),
+            spin: rng.gen_range(-CLEAR_SPIN..=CLEAR_SPIN),
+        });
+    }
+}
+
+// A restart (see restart.rs) goes straight back to Playing
+fn clear_game_over(
+    mut commands: Commands,
+    mut overflow: ResMut<OverflowTimer>,
+    mut sequence: ResMut<GameOverSequence>,
+    screen_query: Query<Entity, With<GameOverScreen>>,
+){
+    overflow.0 = 0.0;
+    *sequence = GameOverSequence::default();
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn skip_game_over_sequence(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut sequence: ResMut<GameOverSequence>,
+    clearing_query: Query<Entity, With<Clearing>>,
+){
+    if sequence.finished || input.get_just_pressed().next().is_none() {
+        return;
+    }
+    for entity in clearing_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    sequence.finished = true;
+}
+
+fn animate_clearing(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sequence: ResMut<GameOverSequence>,
+    mut clearing_query: Query<(Entity, &mut Clearing, &mut Transform, &mut Sprite, Option<&Children>)>,
+    mut child_query: Query<&mut Sprite, Without<Clearing>>,
+){
+    if sequence.finished {
+        return;
+    }
+    let dt: f32 = time.delta_seconds();
+    sequence.elapsed += dt;
+    if clearing_query.is_empty() {
+        sequence.finished = true;
+        return;
+    }
+    for (entity, mut clearing, mut transform, mut sprite, children) in clearing_query.iter_mut() {
+        let t: f32 = (sequence.elapsed - clearing.delay) / CLEAR_DURATION;
+        if t < 0.0 {
+            continue;
+        }
+        if t >= 1.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        clearing.velocity.y -= CLEAR_GRAVITY * dt;
+        transform.translation += (clearing.velocity * dt).extend(0.0);
+        transform.rotate_z(clearing.spin * dt);
+        transform.scale = Vec3::splat(1.0 - t);
+        sprite.color.set_a(1.0 - t);
+        // decals and outlines fade along with their fruit, but never get more opaque than they were
+        for child in children.into_iter().flatten() {
+            if let Ok(mut child_sprite) = child_query.get_mut(*child) {
+                let alpha: f32 = child_sprite.color.a().min(1.0 - t);
+                child_sprite.color.set_a(alpha);
+            }
+        }
+    }
+}
+
+fn show_final_score(
+    mut commands: Commands,
+    sequence: Res<GameOverSequence>,
+    scoreboard: Res<Scoreboard>,
+    biggest_fruit: Res<BiggestFruit>,
+    seed: Res<GameSeed>,
+    clock: Res<RunClock>,
+    round_timer: Res<RoundTimer>,
+    localization: Res<Localization>,
+    text_query: Query<(), With<FinalScoreText>>,
+){
+    if !sequence.finished || !text_query.is_empty() {
+        return;
+    }
+    // a time attack run can still overflow before its time is up
+    let heading: &str = if round_timer.expired(&clock) { "time_up" } else { "final_score" };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            GameOverScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                TextBundle::from_section(
+                    localization.format(heading, &[&scoreboard.score]),
+                    TextStyle {
+                        font_size: SCOREBOARD_FONT_SIZE,
+                        color: SCORE_COLOR,
+                        ..default()
+                    },
+                ),
+                FinalScoreText,
+            ));
+            let style = TextStyle {
+                font_size: OVERLAY_FONT_SIZE,
+                color: TEXT_COLOR,
+                ..default()
+            };
+            let biggest: String = match biggest_fruit.group {
+                Some(group) => localization.format("tier", &[&(group + 1)]),
+                None => "-".to_string(),
+            };
+            root.spawn(TextBundle::from_sections([
+                TextSection::new(localization.get("biggest"), style.clone()),
+                TextSection::new(biggest, style.clone()),
+            ]));
+            root.spawn((
+                TextBundle::from_sections([
+                    TextSection::new(localization.format("share_code", &[&share_code(seed.0)]), style.clone()),
+                    TextSection::new("", style.clone()), // copy result, see share.rs
+                ]),
+                ShareCodeText,
+            ));
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(RESTART_BUTTON_GAP),
+                    margin: UiRect::top(Val::Px(RESTART_BUTTON_GAP)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                for (restart_seed, label) in [(RestartSeed::New, "restart_new_seed"), (RestartSeed::Retry, "restart_retry_seed")] {
+                    row.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(RESTART_BUTTON_PADDING)),
+                                ..default()
+                            },
+                            background_color: RESTART_BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                        RestartButton(restart_seed),
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(localization.get(label), style.clone()));
+                    });
+                }
+                row.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            padding: UiRect::all(Val::Px(RESTART_BUTTON_PADDING)),
+                            ..default()
+                        },
+                        background_color: RESTART_BUTTON_COLOR.into(),
+                        ..default()
+                    },
+                    LeaderboardButton,
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(localization.get("leaderboard_button"), style.clone()));
+                });
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settle::{headless_game, settle_board};
+    use crate::{FruitBundle, FruitIterator, FruitTable, GameSettings, Player, SpawnHeight, WALL_THICKNESS};
+
+    // Whether a cherry poking over the danger line at `speed` px/s upward ends a run that has
+    // already been overflowing for GAME_OVER_DELAY
+    fn ends_run_at_speed(speed: f32) -> bool {
+        let mut world: World = World::new();
+        world.insert_resource(FixedTime::new_from_secs(1.0 / 60.0));
+        let arena_config: ArenaConfig = ArenaConfig::default();
+        let mut fruit: Fruit = FruitBundle::new(0, 0, Vec2::new(0.0, arena_config.danger_line_y), &FruitTable::default(), Handle::default()).fruit;
+        fruit.set_vel(1.0 / 60.0, Vec2::new(0.0, speed));
+        world.spawn(fruit);
+        world.insert_resource(arena_config);
+        world.insert_resource(OverflowTimer(GAME_OVER_DELAY));
+        world.init_resource::<NextState<GameState>>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(check_overflow);
+        schedule.run(&mut world);
+        return world.resource::<NextState<GameState>>().0 == Some(GameState::GameOver);
+    }
+
+    #[test]
+    fn only_a_fruit_resting_over_the_line_ends_the_run() {
+        assert!(ends_run_at_speed(0.0));
+        assert!(!ends_run_at_speed(2.0 * OVERFLOW_REST_SPEED));
+    }
+
+    // A headless game that spawns dropped fruits inside the top wall and watches for overflow,
+    // with the bottom wall at `bottom_wall` and a cherry held over the middle
+    fn inside_top_game(bottom_wall: f32) -> App {
+        let mut app: App = headless_game(None);
+        app.init_resource::<OverflowTimer>()
+            .add_systems(FixedUpdate, check_overflow.after(physics_update).in_set(Simulation));
+        app.world.resource_mut::<GameSettings>().spawn_height = SpawnHeight::InsideTop;
+        app.world.resource_mut::<ArenaConfig>().bottom_wall = bottom_wall;
+        app.world.run_schedule(Startup);
+        let mut player_query = app.world.query_filtered::<(&mut Transform, &mut FruitIterator), With<Player>>();
+        let (mut transform, mut fruit_iterator) = player_query.single_mut(&mut app.world);
+        transform.translation.x = 0.0;
+        fruit_iterator.next_group = 0;
+        fruit_iterator.next_is_bomb = false;
+        return app;
+    }
+
+    fn drop_held_fruit(app: &mut App) {
+        app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+        app.world.run_schedule(FixedUpdate);
+        app.world.resource_mut::<Input<KeyCode>>().release(KeyCode::Space);
+    }
+
+    fn game_over_set(app: &App) -> bool {
+        return app.world.resource::<NextState<GameState>>().0 == Some(GameState::GameOver);
+    }
+
+    #[test]
+    fn a_fruit_spawned_inside_the_top_lands_on_the_pile() {
+        let mut app: App = inside_top_game(BOTTOM_WALL);
+        let fruit_table: FruitTable = FruitTable::default();
+        let floor: f32 = app.world.resource::<ArenaConfig>().floor();
+        let persimmon: f32 = fruit_table.get(4).radius;
+        app.world.spawn(FruitBundle::new(1000, 4, Vec2::new(0.0, floor + persimmon), &fruit_table, Handle::default()));
+        drop_held_fruit(&mut app);
+        settle_board(&mut app.world, 60);
+
+        let fruits: Vec<(u8, Vec2)> = app.world.query::<&Fruit>().iter(&app.world).map(|fruit| (fruit.group, fruit.pos)).collect();
+        let cherry: Vec2 = fruits.iter().find(|(group, _)| *group == 0).expect("the cherry was never dropped").1;
+        let bed: Vec2 = fruits.iter().find(|(group, _)| *group == 4).unwrap().1;
+        assert!(cherry.y > bed.y, "the cherry went through the persimmon to {:?}", cherry);
+        assert!(cherry.distance(bed) > fruit_table.get(0).radius + persimmon - 2.0, "the cherry sank into the persimmon");
+        assert!(!game_over_set(&app));
+    }
+
+    #[test]
+    fn a_fruit_spawned_inside_the_top_still_overflows() {
+        // the floor so high that the cherry comes to rest poking over the danger line
+        let danger_line_y: f32 = ArenaConfig::default().danger_line_y;
+        let mut app: App = inside_top_game(danger_line_y - 10.0 - WALL_THICKNESS / 2.0);
+        drop_held_fruit(&mut app);
+        settle_board(&mut app.world, 10);
+        assert!(!game_over_set(&app), "dropping in ended the run");
+        settle_board(&mut app.world, (GAME_OVER_DELAY * 60.0) as usize + 10);
+        assert!(game_over_set(&app));
+    }
+
+    #[test]
+    fn a_clearing_fruit_fades_its_decal_too() {
+        let mut world: World = World::new();
+        world.insert_resource(Time::default());
+        world.insert_resource(GameOverSequence { elapsed: 0.5 * CLEAR_DURATION, finished: false });
+        let decal: Entity = world.spawn(Sprite::default()).id();
+        world
+            .spawn((Clearing { delay: 0.0, velocity: Vec2::ZERO, spin: 0.0 }, Transform::default(), Sprite::default()))
+            .push_children(&[decal]);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(animate_clearing);
+        schedule.run(&mut world);
+        let alpha: f32 = world.get::<Sprite>(decal).unwrap().color.a();
+        assert!(alpha < 0.75, "decal still at alpha {}", alpha);
+    }
+}
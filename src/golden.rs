@@ -0,0 +1,162 @@
+// Golden-master check for the physics.
+// Replays a fixed sequence of drops with a fixed seed through the FixedUpdate systems and
+// compares every fruit's final state against the checked-in baseline.
+//
+//   cargo test                     runs the comparison as the stack_matches_baseline test
+//   cargo run -- --golden          the same comparison from the command line
+//   cargo run -- --golden-update   regenerate the baseline after an intentional physics change
+
+use bevy::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::board::BoardSnapshot;
+use crate::settle::settle_board;
+use crate::{add_game, seed_game, Player};
+
+const GOLDEN_PATH: &str = "golden/stack.txt"; // checked in, relative to the crate root
+const GOLDEN_SEED: u64 = 5;
+const GOLDEN_DT: f32 = 1.0 / 60.0;
+const GOLDEN_EPSILON: f32 = 0.01; // allowed position error in pixels
+const STEPS_PER_DROP: usize = 45; // longer than SPAWN_INTERVAL so every drop fires
+const SETTLE_STEPS: usize = 600;
+const GOLDEN_DROPS: [f32; 16] = [
+    0.0, -120.0, 120.0, -200.0,
+    200.0, -40.0, 40.0, 0.0,
+    -160.0, 160.0, -80.0, 80.0,
+    0.0, -220.0, 220.0, 0.0,
+];
+
+// Drops GOLDEN_DROPS with GOLDEN_SEED and lets the pile settle, returning every fruit's
+// id, group and position, in id order
+fn simulate() -> Vec<(u32, u8, Vec2)> {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()));
+    add_game(&mut app);
+    app.init_resource::<Input<KeyCode>>()
+        .insert_resource(FixedTime::new_from_secs(GOLDEN_DT));
+    seed_game(&mut app, GOLDEN_SEED);
+
+    app.world.run_schedule(Startup);
+
+    for drop_x in GOLDEN_DROPS {
+        let mut player_query = app.world.query_filtered::<&mut Transform, With<Player>>();
+        player_query.single_mut(&mut app.world).translation.x = drop_x;
+
+        // only FixedUpdate runs here, so it's the held key that drops, not the press buffered in Update
+        app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+        app.world.run_schedule(FixedUpdate);
+        app.world.resource_mut::<Input<KeyCode>>().release(KeyCode::Space);
+        settle_board(&mut app.world, STEPS_PER_DROP - 1);
+    }
+    settle_board(&mut app.world, SETTLE_STEPS);
+
+    return BoardSnapshot::from_world(&mut app.world)
+        .fruits
+        .iter()
+        .map(|fruit| (fruit.id, fruit.group, fruit.pos))
+        .collect();
+}
+
+// The baseline inside the crate, wherever cargo was run from
+fn golden_path() -> PathBuf {
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join(GOLDEN_PATH);
+}
+
+fn format_baseline(fruits: &[(u32, u8, Vec2)]) -> String {
+    return fruits
+        .iter()
+        .map(|(id, group, pos)| format!("{} {} {:.4} {:.4}\n", id, group, pos.x, pos.y))
+        .collect();
+}
+
+// Everything in `fruits` that differs from `baseline`, empty when they match
+fn compare(baseline: &str, fruits: &[(u32, u8, Vec2)]) -> Vec<String> {
+    let mut mismatches: Vec<String> = Vec::new();
+    let expected: Vec<&str> = baseline.lines().filter(|line| !line.trim().is_empty()).collect();
+    if expected.len() != fruits.len() {
+        mismatches.push(format!("expected {} fruits, found {}", expected.len(), fruits.len()));
+    }
+    for (line, (id, group, pos)) in expected.iter().zip(fruits.iter()) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let parsed = (
+            fields.first().and_then(|f| f.parse::<u32>().ok()),
+            fields.get(1).and_then(|f| f.parse::<u8>().ok()),
+            fields.get(2).and_then(|f| f.parse::<f32>().ok()),
+            fields.get(3).and_then(|f| f.parse::<f32>().ok()),
+        );
+        let (Some(expected_id), Some(expected_group), Some(x), Some(y)) = parsed else {
+            mismatches.push(format!("malformed baseline line: {}", line));
+            continue;
+        };
+        if expected_id != *id || expected_group != *group {
+            mismatches.push(format!("fruit {} (group {}) found where fruit {} (group {}) was expected", id, group, expected_id, expected_group));
+        } else if (Vec2::new(x, y) - *pos).length() > GOLDEN_EPSILON {
+            mismatches.push(format!("fruit {} at ({:.4}, {:.4}), expected ({:.4}, {:.4})", id, pos.x, pos.y, x, y));
+        }
+    }
+    return mismatches;
+}
+
+pub fn run(update: bool) {
+    let fruits: Vec<(u32, u8, Vec2)> = simulate();
+    let path: PathBuf = golden_path();
+
+    if update {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).expect("could not create golden directory");
+        }
+        fs::write(&path, format_baseline(&fruits)).expect("could not write golden baseline");
+        println!("Wrote {} fruits to {}", fruits.len(), path.display());
+        return;
+    }
+
+    let baseline = match fs::read_to_string(&path) {
+        Ok(baseline) => baseline,
+        Err(err) => {
+            eprintln!("Could not read {} ({}), create it with `cargo run -- --golden-update` and commit it", path.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    let mismatches: Vec<String> = compare(&baseline, &fruits);
+    if mismatches.is_empty() {
+        println!("Golden master matches ({} fruits)", fruits.len());
+    } else {
+        for mismatch in mismatches.iter() {
+            eprintln!("{}", mismatch);
+        }
+        eprintln!("Golden master mismatch, run with --golden-update if the change was intentional");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stack_matches_baseline() {
+        let path: PathBuf = golden_path();
+        let baseline: String = fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("could not read {} ({}), create it with `cargo run -- --golden-update` and commit it", path.display(), err));
+        let mismatches: Vec<String> = compare(&baseline, &simulate());
+        assert!(
+            mismatches.is_empty(),
+            "golden master mismatch, if the change was intentional regenerate {} with `cargo run -- --golden-update`:\n{}",
+            GOLDEN_PATH,
+            mismatches.join("\n")
+        );
+    }
+
+    #[test]
+    fn compare_reports_moved_and_missing_fruits() {
+        let fruits: Vec<(u32, u8, Vec2)> = vec![(0, 1, Vec2::new(10.0, 20.0)), (1, 2, Vec2::new(-5.0, 0.0))];
+        let baseline: String = format_baseline(&fruits);
+        assert!(compare(&baseline, &fruits).is_empty());
+
+        let moved: Vec<(u32, u8, Vec2)> = vec![(0, 1, Vec2::new(10.0, 20.5)), (1, 2, Vec2::new(-5.0, 0.0))];
+        assert_eq!(compare(&baseline, &moved).len(), 1);
+        assert!(!compare(&baseline, &fruits[..1]).is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+// Graphics options from the settings screen: MSAA, which smooths the fruit edges at some GPU
+// cost, vsync, and whether fruits are drawn as sprites or circle meshes. The primary window
+// starts with the saved present mode, and everything is applied again whenever it changes. An
+// MSAA level the GPU can't do falls back to the next one down.
+
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use bevy::render::renderer::RenderAdapter;
+use bevy::render::texture::BevyDefault;
+use bevy::sprite::MaterialMesh2dBundle;
+use bevy::window::{PresentMode, PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::{CollisionShape, Fruit, FruitPart};
+
+const CIRCLE_VERTICES: usize = 64; // of the mesh circle, enough to look round at the largest fruit
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub enum MsaaLevel {
+    Off,
+    X2,
+    X4,
+}
+
+impl MsaaLevel {
+    pub const ALL: [MsaaLevel; 3] = [MsaaLevel::Off, MsaaLevel::X2, MsaaLevel::X4];
+
+    fn msaa(&self) -> Msaa {
+        match self {
+            MsaaLevel::Off => Msaa::Off,
+            MsaaLevel::X2 => Msaa::Sample2,
+            MsaaLevel::X4 => Msaa::Sample4,
+        }
+    }
+}
+
+// Sprites take their roundness from the icon's alpha, so they keep the art (and skin packs).
+// Meshes are real circles in the fruit's color, crisp at any size.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum RenderMode {
+    Sprite,
+    Mesh,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 2] = [RenderMode::Sprite, RenderMode::Mesh];
+}
+
+#[derive(Resource, Clone, Copy)]
+pub struct GraphicsSettings {
+    pub msaa: MsaaLevel,
+    pub vsync: bool,
+    pub render_mode: RenderMode,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        // Bevy's own defaults
+        GraphicsSettings {
+            msaa: MsaaLevel::X4,
+            vsync: true,
+            render_mode: RenderMode::Sprite,
+        }
+    }
+}
+
+impl GraphicsSettings {
+    pub fn present_mode(&self) -> PresentMode {
+        return if self.vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync };
+    }
+}
+
+// On a fruit drawn as a mesh: the child holding the mesh
+#[derive(Component)]
+struct FruitMesh(Entity);
+
+pub struct GraphicsPlugin;
+
+impl Plugin for GraphicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GraphicsSettings>()
+            .add_systems(Update, (apply_graphics_settings, apply_render_mode, sync_fruit_mesh_colors).chain());
+    }
+}
+
+// Highest level no higher than `wanted` the adapter supports. Without an adapter to ask, trust the setting.
+fn supported_msaa(wanted: MsaaLevel, adapter: Option<&RenderAdapter>) -> MsaaLevel {
+    let Some(adapter) = adapter else {
+        return wanted;
+    };
+    let flags = adapter.get_texture_format_features(TextureFormat::bevy_default()).flags;
+    return MsaaLevel::ALL
+        .iter()
+        .copied()
+        .filter(|level| *level <= wanted)
+        .filter(|level| flags.sample_count_supported(level.msaa().samples()))
+        .last()
+        .unwrap_or(MsaaLevel::Off);
+}
+
+fn apply_graphics_settings(
+    graphics: Res<GraphicsSettings>,
+    adapter: Option<Res<RenderAdapter>>,
+    mut msaa: ResMut<Msaa>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+){
+    if !graphics.is_changed() {
+        return;
+    }
+    // the saved choice is kept, it may work on another machine
+    let level: MsaaLevel = supported_msaa(graphics.msaa, adapter.as_deref());
+    if level != graphics.msaa {
+        warn!("MSAA {:?} is not supported here, using {:?}", graphics.msaa, level);
+    }
+    *msaa = level.msaa();
+    for mut window in window_query.iter_mut() {
+        window.present_mode = graphics.present_mode();
+    }
+}
+
+// In mesh mode every circle fruit gets a circle mesh child, picked up as it spawns, and its sprite
+// is shrunk to nothing. Outlines, decals and cracks are children of their own and stay as they are.
+// Switching back to sprites removes the meshes. update_sprites moves the fruit, and the mesh with it.
+fn apply_render_mode(
+    mut commands: Commands,
+    graphics: Res<GraphicsSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut circle: Local<Option<Handle<Mesh>>>,
+    mut sprite_query: Query<(Entity, &Fruit, &mut Sprite), Without<FruitMesh>>,
+    mut mesh_query: Query<(Entity, &Fruit, &mut Sprite, &FruitMesh)>,
+){
+    if graphics.render_mode == RenderMode::Sprite {
+        if !graphics.is_changed() {
+            return;
+        }
+        for (entity, fruit, mut sprite, fruit_mesh) in mesh_query.iter_mut() {
+            commands.entity(fruit_mesh.0).despawn_recursive();
+            commands.entity(entity).remove::<FruitMesh>();
+            sprite.custom_size = Some(Vec2::splat(2.0*fruit.radius));
+        }
+        return;
+    }
+    let circle: &Handle<Mesh> = circle.get_or_insert_with(|| {
+        return meshes.add(Mesh::from(shape::Circle { radius: 1.0, vertices: CIRCLE_VERTICES }));
+    });
+    for (entity, fruit, mut sprite) in sprite_query.iter_mut() {
+        // boxes are drawn untextured already
+        if !matches!(fruit.shape, CollisionShape::Circle) {
+            continue;
+        }
+        let mesh: Entity = commands
+            .spawn(MaterialMesh2dBundle {
+                mesh: circle.clone().into(),
+                material: materials.add(ColorMaterial::from(sprite.color)),
+                transform: Transform::from_scale(Vec3::splat(fruit.radius)),
+                ..default()
+            })
+            .insert(FruitPart)
+            .id();
+        commands.entity(entity).add_child(mesh).insert(FruitMesh(mesh));
+        sprite.custom_size = Some(Vec2::ZERO);
+    }
+}
+
+// The sprite still holds the fruit's color, and whatever tints it, so the mesh follows it
+fn sync_fruit_mesh_colors(
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    fruit_query: Query<(&Sprite, &FruitMesh), Changed<Sprite>>,
+    material_query: Query<&Handle<ColorMaterial>>,
+){
+    for (sprite, fruit_mesh) in fruit_query.iter() {
+        let Ok(handle) = material_query.get(fruit_mesh.0) else {
+            continue;
+        };
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = sprite.color;
+        }
+    }
+}
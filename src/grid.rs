@@ -0,0 +1,68 @@
+// Broad phase for apply_merges. Fruits are bucketed into square cells as wide as the widest
+// fruit, so two fruits that touch are always in the same or neighboring cells and only those
+// pairs get a closer look.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+pub struct SpatialGrid {
+    cells: HashMap<(i32, i32), Vec<usize>>, // entry indices, in order
+    entry_cells: Vec<(i32, i32)>, // cell of each entry
+}
+
+impl SpatialGrid {
+    // Entries are (center, reach), reach being how far any part of the entry is from its center
+    pub fn new(entries: impl Iterator<Item = (Vec2, f32)>) -> SpatialGrid {
+        let entries: Vec<(Vec2, f32)> = entries.collect();
+        let cell_size: f32 = entries.iter().map(|(_, reach)| 2.0 * reach).fold(f32::EPSILON, f32::max);
+        let entry_cells: Vec<(i32, i32)> = entries
+            .iter()
+            .map(|(pos, _)| ((pos.x / cell_size).floor() as i32, (pos.y / cell_size).floor() as i32))
+            .collect();
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (index, cell) in entry_cells.iter().enumerate() {
+            cells.entry(*cell).or_default().push(index);
+        }
+        return SpatialGrid { cells, entry_cells };
+    }
+
+    // The pairs (i, j), i < j and j >= from, of entries in the same or neighboring cells. Sorted,
+    // so they come in the order a nested loop over i then j would visit them.
+    pub fn pairs(&self, from: usize) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for j in from..self.entry_cells.len() {
+            let (x, y) = self.entry_cells[j];
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    if let Some(cell) = self.cells.get(&(x + dx, y + dy)) {
+                        pairs.extend(cell.iter().filter(|i| **i < j).map(|i| (*i, j)));
+                    }
+                }
+            }
+        }
+        pairs.sort_unstable();
+        return pairs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_include_every_touching_pair() {
+        // a loose row of fruits of mixed sizes, some touching and some not
+        let entries: Vec<(Vec2, f32)> = (0..40)
+            .map(|index| (Vec2::new(index as f32 * 23.0, (index % 3) as f32 * 30.0), 10.0 + (index % 4) as f32 * 5.0))
+            .collect();
+        let pairs: Vec<(usize, usize)> = SpatialGrid::new(entries.iter().copied()).pairs(0);
+        for i in 0..entries.len() {
+            for j in (i + 1)..entries.len() {
+                let touching: bool = (entries[j].0 - entries[i].0).length() < entries[i].1 + entries[j].1;
+                assert!(!touching || pairs.contains(&(i, j)), "missed ({}, {})", i, j);
+            }
+        }
+        assert!(pairs.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(SpatialGrid::new(entries.iter().copied()).pairs(30).iter().all(|(_, j)| *j >= 30));
+    }
+}
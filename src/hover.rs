@@ -0,0 +1,81 @@
+// Hover tooltip: the name and merge score of the fruit under the mouse cursor
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::window::PrimaryWindow;
+
+use crate::locale::Localization;
+use crate::{Bomb, Fruit, FruitDef, FruitTable, OVERLAY_FONT_SIZE, TEXT_COLOR};
+
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(12.0, 12.0); // from the cursor, so the text isn't under it
+const TOOLTIP_Z: f32 = 10.0; // above fruits and their decals
+
+#[derive(Component)]
+struct Tooltip;
+
+pub struct HoverPlugin;
+
+impl Plugin for HoverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_tooltip)
+            .add_systems(Update, update_tooltip);
+    }
+}
+
+fn spawn_tooltip(mut commands: Commands) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: OVERLAY_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ),
+            text_anchor: Anchor::BottomLeft,
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        Tooltip,
+    ));
+}
+
+// Of all fruits containing `point`, the one whose center is nearest
+fn fruit_at(point: Vec2, fruits: impl Iterator<Item = (Vec2, f32, u8)>) -> Option<u8> {
+    return fruits
+        .map(|(pos, radius, group)| ((pos - point).length(), radius, group))
+        .filter(|(dist, radius, _)| dist < radius)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, _, group)| group);
+}
+
+fn update_tooltip(
+    fruit_table: Res<FruitTable>,
+    localization: Res<Localization>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    fruit_query: Query<&Fruit, Without<Bomb>>,
+    mut tooltip_query: Query<(&mut Text, &mut Transform, &mut Visibility), With<Tooltip>>,
+){
+    let (mut text, mut transform, mut visibility) = tooltip_query.single_mut();
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    let cursor: Option<Vec2> = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor));
+    let hovered: Option<u8> = cursor.and_then(|cursor| {
+        fruit_at(cursor, fruit_query.iter().map(|fruit| (fruit.pos, fruit.radius, fruit.group)))
+    });
+    let (Some(cursor), Some(group)) = (cursor, hovered) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let def: &FruitDef = fruit_table.get(group);
+    text.sections[0].value = localization.format("fruit_tooltip", &[&def.name, &def.score]);
+    transform.translation = (cursor + TOOLTIP_OFFSET).extend(TOOLTIP_Z);
+    *visibility = Visibility::Visible;
+}
@@ -0,0 +1,421 @@
+// Local top-10 leaderboard, kept in SAVE_DIR. A game over whose score makes the list asks for a
+// name, typed through ReceivedCharacter. While the prompt is up every other key is held back, so
+// typing can't restart, pause or quit. The list is shown by the button on the game over screen or
+// with L anywhere, and a click or L again closes it. A missing or unreadable file, or lines that
+// don't parse, just mean fewer entries. Time attack runs (see win.rs) have a list of their own,
+// and the one for the current mode is shown.
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::gameover::final_score_shown;
+use crate::locale::Localization;
+use crate::win::RoundTimer;
+use crate::{GameState, Scoreboard, SAVE_DIR, SCORE_COLOR, TEXT_COLOR};
+
+const LEADERBOARD_FILE: &str = "leaderboard.txt";
+const TIME_ATTACK_LEADERBOARD_FILE: &str = "leaderboard_time_attack.txt";
+const LEADERBOARD_SIZE: usize = 10;
+const MAX_NAME_LENGTH: usize = 12; // characters
+const DEFAULT_NAME: &str = "???"; // saved for an empty name
+const LEADERBOARD_KEY: KeyCode = KeyCode::L;
+const LEADERBOARD_FONT_SIZE: f32 = 26.0;
+const LEADERBOARD_BACKDROP: Color = Color::rgba(0.0, 0.0, 0.0, 0.75);
+const LEADERBOARD_ROW_GAP: f32 = 6.0;
+
+struct LeaderboardEntry {
+    name: String,
+    score: u32,
+    date: String, // YYYY-MM-DD
+}
+
+struct Leaderboard {
+    file: &'static str, // in SAVE_DIR
+    entries: Vec<LeaderboardEntry>, // best first
+}
+
+// One list per mode, so a minute's score isn't ranked against an endless run
+#[derive(Resource)]
+struct Leaderboards {
+    endless: Leaderboard,
+    time_attack: Leaderboard,
+}
+
+impl Leaderboards {
+    fn get(&self, timed: bool) -> &Leaderboard {
+        if timed {
+            return &self.time_attack;
+        }
+        return &self.endless;
+    }
+
+    fn get_mut(&mut self, timed: bool) -> &mut Leaderboard {
+        if timed {
+            return &mut self.time_attack;
+        }
+        return &mut self.endless;
+    }
+}
+
+impl Leaderboard {
+    fn load(file: &'static str) -> Leaderboard {
+        return Leaderboard::load_from(file, &Path::new(SAVE_DIR).join(file));
+    }
+
+    // One entry per line: score, date, then the name, which may contain spaces
+    fn load_from(file: &'static str, path: &Path) -> Leaderboard {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Leaderboard { file, entries: Vec::new() };
+        };
+        let mut entries: Vec<LeaderboardEntry> = contents
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let score: u32 = fields.next()?.parse().ok()?;
+                let date: String = fields.next()?.to_string();
+                let name: String = fields.next()?.chars().take(MAX_NAME_LENGTH).collect();
+                return Some(LeaderboardEntry { name, score, date });
+            })
+            .collect();
+        // a hand-edited file may be out of order or too long
+        entries.sort_by(|a, b| b.score.cmp(&a.score));
+        entries.truncate(LEADERBOARD_SIZE);
+        return Leaderboard { file, entries };
+    }
+
+    fn save(&self) {
+        let contents: String = self
+            .entries
+            .iter()
+            .map(|entry| format!("{} {} {}\n", entry.score, entry.date, entry.name))
+            .collect();
+        let result = fs::create_dir_all(SAVE_DIR)
+            .and_then(|_| fs::write(Path::new(SAVE_DIR).join(self.file), contents));
+        if let Err(err) = result {
+            warn!("Could not save the leaderboard: {}", err);
+        }
+    }
+
+    fn qualifies(&self, score: u32) -> bool {
+        if score == 0 {
+            return false;
+        }
+        return self.entries.len() < LEADERBOARD_SIZE || self.entries.last().is_some_and(|last| score > last.score);
+    }
+
+    // Below any equal score already on the list, so the earlier run keeps its place
+    fn insert(&mut self, entry: LeaderboardEntry) {
+        let index: usize = self
+            .entries
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+        self.entries.truncate(LEADERBOARD_SIZE);
+    }
+}
+
+// The name being typed for `score`, None while no prompt is up
+#[derive(Resource, Default)]
+struct NameEntry {
+    pending: bool, // this game over hasn't been checked against the list yet
+    typing: Option<String>,
+    score: u32,
+    timed: bool, // goes on the time attack list
+}
+
+#[derive(Component)]
+struct NamePrompt;
+
+#[derive(Component)]
+struct NamePromptText;
+
+#[derive(Component)]
+struct LeaderboardScreen;
+
+// Shows the list when clicked, spawned on the game over screen
+#[derive(Component)]
+pub struct LeaderboardButton;
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboards {
+                endless: Leaderboard::load(LEADERBOARD_FILE),
+                time_attack: Leaderboard::load(TIME_ATTACK_LEADERBOARD_FILE),
+            })
+            .init_resource::<NameEntry>()
+            .add_systems(OnEnter(GameState::GameOver), arm_name_entry)
+            // before anything in Update sees the keys the name is typed with
+            .add_systems(PreUpdate, type_name.after(InputSystem))
+            .add_systems(Update, (
+                start_name_entry.run_if(in_state(GameState::GameOver)).run_if(final_score_shown),
+                update_name_prompt,
+                toggle_leaderboard,
+            ).chain());
+    }
+}
+
+fn arm_name_entry(mut entry: ResMut<NameEntry>) {
+    entry.pending = true;
+}
+
+// Today's date in UTC, from the days since the Unix epoch
+fn today() -> String {
+    let seconds: u64 = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+    // Howard Hinnant's civil_from_days
+    let z: i64 = (seconds / 86400) as i64 + 719468;
+    let era: i64 = z.div_euclid(146097);
+    let day_of_era: i64 = z - era * 146097;
+    let year_of_era: i64 = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: i64 = (5 * day_of_year + 2) / 153; // March is 0
+    let day: i64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month: i64 = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year: i64 = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return format!("{:04}-{:02}-{:02}", year, month, day);
+}
+
+fn start_name_entry(
+    mut commands: Commands,
+    scoreboard: Res<Scoreboard>,
+    round_timer: Res<RoundTimer>,
+    leaderboards: Res<Leaderboards>,
+    localization: Res<Localization>,
+    mut entry: ResMut<NameEntry>,
+){
+    if !entry.pending {
+        return;
+    }
+    entry.pending = false;
+    let timed: bool = round_timer.limit.is_some();
+    if !leaderboards.get(timed).qualifies(scoreboard.score) {
+        return;
+    }
+    entry.typing = Some(String::new());
+    entry.score = scoreboard.score;
+    entry.timed = timed;
+
+    let text_style = TextStyle {
+        font_size: LEADERBOARD_FONT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(LEADERBOARD_ROW_GAP),
+                    ..default()
+                },
+                background_color: LEADERBOARD_BACKDROP.into(),
+                z_index: ZIndex::Global(1), // over the game over screen
+                focus_policy: FocusPolicy::Block, // and its restart buttons
+                ..default()
+            },
+            NamePrompt,
+        ))
+        .with_children(|root| {
+            root.spawn(TextBundle::from_section(localization.get("name_prompt"), text_style.clone()));
+            root.spawn((
+                TextBundle::from_section("", TextStyle { color: SCORE_COLOR, ..text_style.clone() }),
+                NamePromptText,
+            ));
+            root.spawn(TextBundle::from_section(localization.get("name_confirm"), text_style));
+        });
+}
+
+// Enter saves the name, Esc skips the leaderboard for this run
+fn type_name(
+    mut commands: Commands,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut entry: ResMut<NameEntry>,
+    mut leaderboards: ResMut<Leaderboards>,
+    prompt_query: Query<Entity, With<NamePrompt>>,
+){
+    let Some(name) = entry.typing.as_mut() else {
+        characters.clear();
+        return;
+    };
+    for character in characters.iter() {
+        // Enter and Backspace arrive as control characters on some platforms, they're handled below
+        if !character.char.is_control() && name.chars().count() < MAX_NAME_LENGTH {
+            name.push(character.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        name.pop();
+    }
+    let submitted: bool = keys.just_pressed(KeyCode::Return);
+    let skipped: bool = keys.just_pressed(KeyCode::Escape);
+    keys.reset_all();
+    if !submitted && !skipped {
+        return;
+    }
+
+    if submitted {
+        let trimmed: &str = name.trim();
+        let entry_name: String = if trimmed.is_empty() { DEFAULT_NAME.to_string() } else { trimmed.to_string() };
+        let leaderboard: &mut Leaderboard = leaderboards.get_mut(entry.timed);
+        leaderboard.insert(LeaderboardEntry {
+            name: entry_name,
+            score: entry.score,
+            date: today(),
+        });
+        leaderboard.save();
+    }
+    entry.typing = None;
+    for prompt in prompt_query.iter() {
+        commands.entity(prompt).despawn_recursive();
+    }
+}
+
+fn update_name_prompt(
+    entry: Res<NameEntry>,
+    mut text_query: Query<&mut Text, With<NamePromptText>>,
+){
+    let Some(name) = &entry.typing else {
+        return;
+    };
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("{}_", name);
+    }
+}
+
+fn toggle_leaderboard(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    leaderboards: Res<Leaderboards>,
+    round_timer: Res<RoundTimer>,
+    localization: Res<Localization>,
+    clicked_query: Query<&Interaction, (Changed<Interaction>, Or<(With<LeaderboardButton>, With<LeaderboardScreen>)>)>,
+    screen_query: Query<Entity, With<LeaderboardScreen>>,
+){
+    let clicked: bool = clicked_query.iter().any(|interaction| *interaction == Interaction::Pressed);
+    if !keys.just_pressed(LEADERBOARD_KEY) && !clicked {
+        return;
+    }
+    if !screen_query.is_empty() {
+        for screen in screen_query.iter() {
+            commands.entity(screen).despawn_recursive();
+        }
+        return;
+    }
+
+    let timed: bool = round_timer.limit.is_some();
+    let leaderboard: &Leaderboard = leaderboards.get(timed);
+    let title: &str = if timed { "leaderboard_time_attack_title" } else { "leaderboard_title" };
+    let text_style = TextStyle {
+        font_size: LEADERBOARD_FONT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(LEADERBOARD_ROW_GAP),
+                    ..default()
+                },
+                background_color: LEADERBOARD_BACKDROP.into(),
+                z_index: ZIndex::Global(1), // over the game over screen
+                focus_policy: FocusPolicy::Block, // a click closes it rather than pressing a button underneath
+                ..default()
+            },
+            Interaction::default(),
+            LeaderboardScreen,
+        ))
+        .with_children(|root| {
+            root.spawn(TextBundle::from_section(
+                localization.get(title),
+                TextStyle { color: SCORE_COLOR, ..text_style.clone() },
+            ));
+            if leaderboard.entries.is_empty() {
+                root.spawn(TextBundle::from_section(localization.get("leaderboard_empty"), text_style.clone()));
+            }
+            for (rank, entry) in leaderboard.entries.iter().enumerate() {
+                root.spawn(TextBundle::from_section(
+                    format!("{:>2}. {:<width$}  {:>7}  {}", rank + 1, entry.name, entry.score, entry.date, width = MAX_NAME_LENGTH),
+                    text_style.clone(),
+                ));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // The list read back from a file holding `contents`
+    fn load_contents(name: &str, contents: &[u8]) -> Leaderboard {
+        let path: PathBuf = std::env::temp_dir().join(name);
+        fs::write(&path, contents).unwrap();
+        let leaderboard: Leaderboard = Leaderboard::load_from(LEADERBOARD_FILE, &path);
+        fs::remove_file(&path).ok();
+        return leaderboard;
+    }
+
+    fn scores(leaderboard: &Leaderboard) -> Vec<u32> {
+        return leaderboard.entries.iter().map(|entry| entry.score).collect();
+    }
+
+    #[test]
+    fn a_missing_file_starts_an_empty_list() {
+        let path: PathBuf = std::env::temp_dir().join("alpha_suika_no_such_leaderboard.txt");
+        fs::remove_file(&path).ok();
+        let leaderboard: Leaderboard = Leaderboard::load_from(LEADERBOARD_FILE, &path);
+        assert!(leaderboard.entries.is_empty());
+        assert!(leaderboard.qualifies(1));
+    }
+
+    #[test]
+    fn lines_that_dont_parse_are_skipped() {
+        let contents: &str = "120 2024-03-01 Ann\n\
+            garbage\n\
+            75\n\
+            -5 2024-03-02 Bob\n\
+            many 2024-03-03 Cal\n\
+            300 2024-03-04 Dee Eff\n\
+            90 2024-03-05\n";
+        let leaderboard: Leaderboard = load_contents("alpha_suika_partial_leaderboard.txt", contents.as_bytes());
+        assert_eq!(scores(&leaderboard), vec![300, 120]);
+        assert_eq!(leaderboard.entries[0].name, "Dee Eff");
+        assert_eq!(leaderboard.entries[0].date, "2024-03-04");
+
+        // not even text
+        let corrupt: Leaderboard = load_contents("alpha_suika_corrupt_leaderboard.txt", &[0xff, 0xfe, 0x00, 0x9c]);
+        assert!(corrupt.entries.is_empty());
+    }
+
+    #[test]
+    fn only_the_best_ten_are_kept() {
+        let contents: String = (1..=15).map(|score| format!("{} 2024-03-01 Player{}\n", score * 10, score)).collect();
+        let mut leaderboard: Leaderboard = load_contents("alpha_suika_long_leaderboard.txt", contents.as_bytes());
+        assert_eq!(scores(&leaderboard), (6..=15).rev().map(|score| score * 10).collect::<Vec<u32>>());
+
+        assert!(!leaderboard.qualifies(60));
+        assert!(leaderboard.qualifies(65));
+        leaderboard.insert(LeaderboardEntry { name: "New".to_string(), score: 65, date: today() });
+        assert_eq!(leaderboard.entries.len(), LEADERBOARD_SIZE);
+        assert_eq!(leaderboard.entries.last().map(|entry| entry.name.as_str()), Some("New"));
+    }
+}
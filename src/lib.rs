@@ -0,0 +1,3700 @@
+// Bevy systems take every resource and query they touch as a parameter, so they run long
+#![allow(clippy::too_many_arguments)]
+
+use bevy::prelude::*;
+use bevy::math::*;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+use std::f32::consts::*;
+use std::ops::DerefMut;
+use bevy::time::Stopwatch;
+use bevy::diagnostic::{DiagnosticId, DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::window::PrimaryWindow;
+use bevy::winit::WinitWindows;
+use std::time::Duration;
+use winit::window::Icon;
+use serde::{Deserialize, Serialize};
+
+mod achievements;
+mod apm;
+mod attract;
+mod audio;
+mod autosave;
+pub mod bench;
+mod board;
+mod camera;
+mod config;
+mod debug;
+mod difficulty;
+mod effects;
+mod estimate;
+mod gameover;
+mod golden;
+mod graphics;
+mod grid;
+mod hover;
+mod leaderboard;
+mod locale;
+mod music;
+mod nudge;
+mod preview;
+mod profiling;
+mod replay;
+mod restart;
+mod sandbox;
+mod settings;
+mod settle;
+mod share;
+mod skin;
+mod step;
+mod theme;
+mod timeline;
+mod tutorial;
+mod well;
+mod win;
+
+use achievements::{Achievements, AchievementsPlugin};
+use apm::{DropRate, DropRatePlugin};
+use attract::{AttractMode, AttractPlugin};
+use audio::SoundPlugin;
+use autosave::AutosavePlugin;
+use board::BoardSnapshot;
+use camera::{CameraFollow, CameraPlugin};
+use debug::{DebugDraw, DebugDrawPlugin};
+use difficulty::{DifficultyMode, DifficultyPlugin, DynamicDifficulty, GRAVITY_RAMP};
+use effects::EffectsPlugin;
+use estimate::EstimatePlugin;
+use gameover::GameOverPlugin;
+use graphics::GraphicsPlugin;
+use grid::SpatialGrid;
+use hover::HoverPlugin;
+use leaderboard::LeaderboardPlugin;
+use music::MusicPlugin;
+use locale::Localization;
+use nudge::{NudgePlugin, NUDGE_MODIFIERS};
+use preview::PreviewPlugin;
+use profiling::ProfilingPlugin;
+use replay::{ReplayPlayer, ReplayPlugin, ReplayRecorder};
+use restart::RestartPlugin;
+use sandbox::{Sandbox, SandboxPlugin};
+use settings::SettingsPlugin;
+use settle::SettlePlugin;
+use share::SharePlugin;
+use skin::SkinPlugin;
+use step::{Simulation, StepMode, StepPlugin};
+use theme::ThemePlugin;
+use timeline::TimelinePlugin;
+use tutorial::TutorialPlugin;
+use well::{GravityWell, GravityWellPlugin};
+use win::{WinCondition, WinPlugin};
+
+// constants
+const WINDOW_TITLE: &str = "Alpha Suika";
+const WINDOW_ICON_PATH: &str = "assets/fruit_icon.png";
+const PLAYER_SPEED: f32 = 600.0;
+const GRAVITY: f32 = 20.0 * 100.0;
+const WALL_BOUNCE_CONST: f32 = 0.4;
+const TIMESTEP: f32 = 1.0 / 60.0; // seconds per FixedUpdate step
+const POS_RESPONSE_CONST: f32 = 1.0;
+const VEL_RESPONSE_CONST: f32 = 0.01;
+const LINEAR_FRICTION_CONST: f32 = 0.95;
+const ROT_FRICTION_CONST: f32 = 0.20;
+const MARGIN:f32 = 2.0;
+const SPAWN_INTERVAL: f32 = 0.5; // seconds between spawning fruits
+const MAX_VEL: f32 = 800.0; // clamp velocity magnitude
+const MAX_FALL_SPEED: f32 = MAX_VEL; // default terminal fall speed, px/s. Only a lower one changes anything
+const MAX_A_VEL: f32 = 200.0; // clamp velocity magnitude
+const MAX_CORRECTION: f32 = 10.0; // default px a colliding pair is pushed apart per step at most
+const CRACK_IMPACT_SPEED: f32 = 500.0; // px/s, slower impacts leave no crack
+const CRACK_FULL_SPEED: f32 = 1200.0; // px/s for a fully opaque crack
+const CRACK_DECAY: f32 = 0.5; // crack opacity lost per second
+const CRACK_Z: f32 = 0.2; // relative to the parent fruit, above the face decal
+const ROLL_CONTACT_SLOP: f32 = 1.0; // px, resting fruits hover about this far apart after collisions
+const ROLL_GRIP: f32 = 0.2; // fraction of the slip against a surface closed per step
+const ROLL_MIN_SUPPORT: f32 = 0.3; // contact normals flatter than this hold nothing up, so don't roll
+const RAIN_INTERVAL: f32 = 0.02; // seconds between fruits in the rain stress test
+const STRESS_TEST_KEY: KeyCode = KeyCode::F9;
+const SPAWN_CLEARANCE: f32 = 2.0; // px between the top wall and a fruit spawned inside it
+const SPAWN_ENTRY_SPEED: f32 = 150.0; // px/s downward given to fruits spawned inside the top wall
+const DROP_BUFFER_TIME: f32 = 0.12; // seconds a Space press is remembered
+// Throw mode: holding A/D charges a sideways throw at THROW_CHARGE_RATE up to THROW_MAX_SPEED.
+// At the max a fruit moves 10px per step, well under the smallest radius, so it can't tunnel.
+const THROW_CHARGE_RATE: f32 = 900.0; // px/s gained per second held
+const THROW_MAX_SPEED: f32 = 600.0; // px/s
+const FLICK_FRACTION: f32 = 0.5; // of the player's speed at the drop a flicked fruit keeps
+const FLICK_MAX_SPEED: f32 = 250.0; // px/s
+const NUDGE_SPEED: f32 = 120.0; // px/s a nudge gives the pile with --nudge, see nudge.rs
+const NUDGE_COOLDOWN: f32 = 20.0; // default seconds between nudges
+const BOMB_CHANCE: f32 = 0.02; // chance that the next held fruit is a bomb, with --bombs
+const RESTING_CONTACT_SPEED: f32 = 20.0; // px/s, touching pairs slower than this relative to each other are resting
+const PACKING_POINTS_PER_CONTACT: f32 = 0.02; // per resting contact per second, in ScoringMode::Packing
+const BOMB_FUSE: f32 = 5.0; // seconds a bomb that touches nothing lasts before fizzling out
+const BOMB_CONTACT_SLOP: f32 = 1.0; // px, collisions keep fruits from ever quite overlapping
+const BOMB_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const COMBO_WINDOW: f32 = 1.0; // seconds between merges that keep a combo going
+const SAVE_DIR: &str = "save"; // persisted progress, relative to the working directory
+const BLOCKED_FLASH_RATE: f32 = 4.0; // flashes per second when the drop column is full
+
+const LEFT_WALL: f32 = -540.0/2.;
+const RIGHT_WALL: f32 = 540.0/2.;
+const BOTTOM_WALL: f32 = -700.0/2.;
+const TOP_WALL: f32 = 500.0/2.;
+const TALL_TOP_WALL: f32 = TOP_WALL + 1500.0; // --tall, for stacks that outgrow the window
+const PLAYER_ABOVE_TOP_WALL: f32 = 50.0; // height the held fruit hovers over the top wall
+const WALL_THICKNESS: f32 = 10.0;
+const WALL_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
+const GUTTER_WIDTH: f32 = 120.0; // horizontal reach of each corner ramp
+const GUTTER_HEIGHT: f32 = 60.0; // height of each corner ramp at the wall
+const LINE_REST_SPEED: f32 = 30.0; // slower normal impacts on a line collider don't bounce
+const HARD_LANDING_SPEED: f32 = 600.0; // px/s into the floor for a landing to thump
+const HARD_LANDING_MIN_RADIUS: f32 = 50.0; // smaller fruits never thump
+const COLLISION_EVENT_MIN_SPEED: f32 = 150.0; // px/s, slower impacts send no CollisionEvent
+const LANDING_NUDGE_RANGE: f32 = 150.0; // gap between fruits within which a thump nudges neighbors
+const LANDING_NUDGE_KICK: f32 = 40.0; // px/s downward for a touching neighbor, fading with the gap
+const LANDING_NUDGE_SPEED: f32 = 30.0; // px/s, faster neighbors are already on the move and aren't nudged
+const SOFT_WALL_STIFFNESS: f32 = 400.0; // px/s^2 of push per px a fruit is into a soft wall
+const SOFT_WALL_DAMPING: f32 = 10.0; // px/s^2 per px/s of speed into a soft wall, so it doesn't ring
+const SOFT_WALL_MAX_DEPTH: f32 = 15.0; // px, deeper than this a soft wall is hard
+const MAGNET_RANGE: f32 = 40.0; // default gap between same-group fruits within which the merge magnet pulls
+const MAGNET_GRAVITY_FRACTION: f32 = 0.1; // pull on a touching pair, as a fraction of gravity
+const PERFECT_DROP_WINDOW: f32 = 0.75; // seconds after a drop lands that a merge counts as a perfect drop, when on
+const PERFECT_DROP_BONUS: u32 = 10; // points added to a perfect drop merge
+const LANDING_SLOP: f32 = 1.0; // px, a dropped fruit this close to the floor or another fruit has landed
+const DANGER_LINE_OFFSET: f32 = 60.0; // default distance of the danger line below the top wall
+const FLOOR_RISE_SPEED: f32 = 3.0; // px/s the floor climbs with --rising-floor, unless physics.ron sets floor_rise
+const DANGER_LINE_THICKNESS: f32 = 2.0;
+const DANGER_LINE_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 0.6);
+const DANGER_PULSE_RATE: f32 = 3.0; // pulses per second while a fruit is over the line
+const DANGER_FALL_SPEED: f32 = 100.0; // fruits falling faster than this are still dropping in
+
+const DROP_SHADOW_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.25);
+const DROP_SHADOW_WIDTH: f32 = 1.6; // times the held fruit's radius
+const DROP_SHADOW_HEIGHT: f32 = 8.0;
+const DROP_SHADOW_SEGMENTS: usize = 24;
+
+const SCOREBOARD_FONT_SIZE: f32 = 40.0;
+const SCOREBOARD_TEXT_PADDING: Val = Val::Px(5.0);
+const BIGGEST_FRUIT_TEXT_TOP: Val = Val::Px(50.0); // just below the score
+const SCORE_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+const OVERLAY_FONT_SIZE: f32 = 20.0;
+
+const BACKGROUND_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const TEXT_COLOR: Color = Color::rgb(0.5, 0.5, 1.0);
+
+// One tier of the merge chain
+#[derive(Clone)]
+pub struct FruitDef {
+    pub name: &'static str,
+    pub radius: f32,
+    pub hue: f32,
+    pub score: u32, // awarded when two of these merge
+    pub decal: Option<&'static str>, // optional overlay drawn on top of the tinted fruit and rotating with it
+    pub spin_locked: bool, // never rotates, it keeps its spawn angle whatever torque it gets
+}
+
+const DEFAULT_FRUITS: [FruitDef; 11] = [
+    FruitDef { name: "Cherry", radius: 20.0, hue: 0.0, score: 1, decal: Some("fruit_face.png"), spin_locked: false },
+    FruitDef { name: "Strawberry", radius: 25.0, hue: 10.0, score: 3, decal: Some("fruit_face.png"), spin_locked: false },
+    FruitDef { name: "Grape", radius: 35.0, hue: 20.0, score: 6, decal: Some("fruit_face.png"), spin_locked: false },
+    FruitDef { name: "Dekopon", radius: 40.0, hue: 30.0, score: 10, decal: Some("fruit_face.png"), spin_locked: false },
+    FruitDef { name: "Persimmon", radius: 50.0, hue: 40.0, score: 15, decal: Some("fruit_face.png"), spin_locked: false },
+    FruitDef { name: "Apple", radius: 60.0, hue: 50.0, score: 21, decal: Some("fruit_face.png"), spin_locked: false },
+    FruitDef { name: "Pear", radius: 75.0, hue: 60.0, score: 28, decal: Some("fruit_face.png"), spin_locked: false },
+    FruitDef { name: "Peach", radius: 95.0, hue: 70.0, score: 36, decal: Some("fruit_face.png"), spin_locked: false },
+    FruitDef { name: "Pineapple", radius: 105.0, hue: 80.0, score: 45, decal: None, spin_locked: false },
+    FruitDef { name: "Melon", radius: 115.0, hue: 90.0, score: 55, decal: None, spin_locked: false },
+    FruitDef { name: "Watermelon", radius: 125.0, hue: 100.0, score: 0, decal: None, spin_locked: true }, // Cant combine two watermelons, and it sits flat
+];
+const DEFAULT_SPAWNABLE: usize = 5; // the player is handed one of the first 5 of the 11 default tiers
+const FRUIT_DECAL_Z: f32 = 0.1; // relative to the parent fruit
+const OUTLINE_WIDTH: f32 = 2.0; // px of dark rim around each fruit
+const OUTLINE_COLOR: Color = Color::rgb(0.15, 0.15, 0.2);
+const OUTLINE_Z: f32 = -0.01; // relative to the parent fruit, behind it but still in front of the background
+const ROUNDED_BOX_CORNER: f32 = 0.4; // corner radius of the --rounded variant, as a fraction of the fruit radius
+
+// The merge chain, in order. Any number of tiers works; the last one is the top tier and never
+// merges further. A host can insert its own before adding SuikaPlugin to play a different chain.
+#[derive(Resource)]
+pub struct FruitTable {
+    defs: Vec<FruitDef>,
+    spawnable: usize, // the player is handed groups 0..spawnable
+    shape: CollisionShape, // of every fruit in this variant
+    upright_spawn: bool, // circles start at 0 rather than the classic 45 degree tilt of the icon
+}
+
+impl FruitTable {
+    // The spawnable range scales with the number of tiers, keeping the default 5-of-11 ratio
+    pub fn new(defs: Vec<FruitDef>) -> FruitTable {
+        assert!(!defs.is_empty());
+        let spawnable: usize = ((defs.len() * DEFAULT_SPAWNABLE + DEFAULT_FRUITS.len()/2) / DEFAULT_FRUITS.len()).max(1);
+        FruitTable { defs, spawnable, shape: CollisionShape::Circle, upright_spawn: false }
+    }
+    fn len(&self) -> usize {
+        return self.defs.len();
+    }
+    // Initial rotation of a spawned circle, and of the held fruit
+    fn spawn_angle(&self) -> f32 {
+        return if self.upright_spawn { 0.0 } else { FRAC_PI_4 };
+    }
+    fn get(&self, group: u8) -> &FruitDef {
+        return &self.defs[group as usize];
+    }
+    fn is_top(&self, group: u8) -> bool {
+        return (group as usize) + 1 >= self.defs.len();
+    }
+    fn color(&self, group: u8, alpha: f32) -> Color {
+        return Color::hsla(self.get(group).hue, 1.0, 0.6, alpha);
+    }
+    fn min_radius(&self) -> f32 {
+        return self.defs[0].radius;
+    }
+    fn max_radius(&self) -> f32 {
+        return self.defs[self.defs.len()-1].radius;
+    }
+    fn random_spawn_group(&self, rng: &mut GameRng) -> u8 {
+        return self.random_group_below(rng, self.spawnable);
+    }
+    fn random_group_below(&self, rng: &mut GameRng, spawnable: usize) -> u8 {
+        return rng.0.gen_range(0..spawnable.clamp(1, self.defs.len())) as u8;
+    }
+}
+
+impl Default for FruitTable {
+    fn default() -> Self {
+        FruitTable::new(DEFAULT_FRUITS.to_vec())
+    }
+}
+
+// Both shapes fill the same 2*radius square, so the walls treat them alike and only
+// fruit-on-fruit contacts differ
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum CollisionShape {
+    Circle,
+    RoundedBox { corner: f32 }, // axis-aligned square that never rotates, corner radius as a fraction of the fruit radius
+}
+
+#[derive(Component)]
+struct FruitIterator{
+    next_id: u32,
+    next_group: u8, // always 0 when the next drop is a bomb, bombs are the smallest size
+    next_is_bomb: bool,
+}
+
+// Cosmetic only: how cracked a fruit looks, 0.0 ..= 1.0. Raised by hard impacts, fades over time.
+#[derive(Component)]
+struct Damage {
+    amount: f32,
+}
+
+// Seconds since a fruit the player dropped landed, None while it's still falling. Removed once
+// it's older than the perfect drop window, so only fresh drops carry it.
+#[derive(Component)]
+struct DropAge(Option<f32>);
+
+// Seconds before a fruit made by a merge can merge again, see PhysicsConfig::merge_cooldown.
+// Removed once it runs out.
+#[derive(Component)]
+struct MergeCooldown(f32);
+
+#[derive(Component)]
+struct CrackDecal;
+
+// Anything drawn as part of a fruit: outline, decals, mesh, id label. Always a child of the fruit,
+// so every fruit is despawned with despawn_recursive to take its parts with it.
+#[derive(Component)]
+struct FruitPart;
+
+// Dark rim behind a fruit so light fruits stand out from a light background. A child sprite
+// of the fruit's own shape, so it turns and scales with it.
+#[derive(Component)]
+struct Outline;
+
+// Draw fruits between their last two physics steps, by how far the clock has run past the
+// last step, rather than snapping to each step. Smoother whenever the frame rate isn't a
+// multiple of the physics rate, at the cost of showing the pile up to one step in the past.
+#[derive(Resource)]
+struct RenderInterpolation {
+    enabled: bool,
+}
+
+impl Default for RenderInterpolation {
+    fn default() -> Self {
+        RenderInterpolation { enabled: true }
+    }
+}
+
+#[derive(Resource)]
+struct FruitOutlines {
+    enabled: bool,
+}
+
+impl Default for FruitOutlines {
+    fn default() -> Self {
+        FruitOutlines { enabled: true }
+    }
+}
+
+// A fruit that, on touching another fruit, clears every fruit of that group for points.
+// It takes part in collisions like any fruit but never merges. Off unless run with --bombs, and
+// left out of the board snapshot, so bots and the estimate only see the pile.
+#[derive(Component)]
+struct Bomb {
+    fuse: Timer,
+}
+
+#[derive(Component)]
+struct Fruit {
+    id: u32,
+    group: u8, // index into the FruitTable
+    pos: Vec2,
+    pos_last: Vec2,
+    // vel: Vec2,
+    acc: Vec2, // px/s^2 accumulated by the force systems this step, cleared by physics_update
+    a_pos: f32,
+    a_pos_last: f32,
+    // a_vel: f32,
+    a_acc: f32,
+    radius: f32,
+    shape: CollisionShape,
+    spin_locked: bool, // from its FruitDef, see integrate
+    color: Color,
+    impact: f32, // fastest impact this step in px/s, read and cleared by record_damage
+    impact_with: Option<u32>, // id of the fruit that impact was with, None for a wall
+}
+
+impl Fruit {
+    // Every shape is a rectangle core grown by a rounding radius: a circle has no core, a
+    // rounded box is a square core with circular corners
+    fn core_half_size(&self) -> Vec2 {
+        match self.shape {
+            CollisionShape::Circle => Vec2::ZERO,
+            CollisionShape::RoundedBox { corner } => Vec2::splat(self.radius * (1.0 - corner)),
+        }
+    }
+    fn rounding(&self) -> f32 {
+        match self.shape {
+            CollisionShape::Circle => self.radius,
+            CollisionShape::RoundedBox { corner } => self.radius * corner,
+        }
+    }
+    // sanitize_timestep keeps dt sane in the game, the floor covers direct callers
+    fn get_vel(&self, dt: f32) -> Vec2 {
+        return (self.pos - self.pos_last) / dt.max(f32::EPSILON);
+    }
+    fn set_vel(&mut self, dt: f32, new_velocity: Vec2){
+        self.pos_last = self.pos - (new_velocity * dt);
+    }
+    fn inc_vel(&mut self, dt: f32, inc_velocity: Vec2){
+        self.pos_last = self.pos_last - (inc_velocity * dt);
+    }
+    // Keeps the fastest impact this step and what it was with
+    fn hit(&mut self, speed: f32, with: Option<u32>) {
+        if speed > self.impact {
+            self.impact = speed;
+            self.impact_with = with;
+        }
+    }
+    fn get_a_vel(&self, dt: f32) -> f32 {
+        return (self.a_pos - self.a_pos_last) / dt.max(f32::EPSILON);
+    }
+    fn set_a_vel(&mut self, dt: f32, new_a_velocity: f32){
+        self.a_pos_last = self.a_pos - (new_a_velocity * dt);
+    }
+    fn inc_a_vel(&mut self, dt: f32, inc_a_velocity: f32){
+        self.pos_last = self.pos_last - (inc_a_velocity * dt);
+    }
+    fn kinematics(&self) -> Kinematics {
+        Kinematics {
+            pos: self.pos,
+            pos_last: self.pos_last,
+            acc: self.acc,
+            a_pos: self.a_pos,
+            a_pos_last: self.a_pos_last,
+            a_acc: self.a_acc,
+            spin_locked: self.spin_locked,
+        }
+    }
+    fn set_kinematics(&mut self, body: &Kinematics) {
+        self.pos = body.pos;
+        self.pos_last = body.pos_last;
+        self.acc = body.acc;
+        self.a_pos = body.a_pos;
+        self.a_pos_last = body.a_pos_last;
+        self.a_acc = body.a_acc;
+    }
+}
+
+// The part of a Fruit integrate reads and writes, the hot data of every physics step
+#[derive(Clone, Copy)]
+struct Kinematics {
+    pos: Vec2,
+    pos_last: Vec2,
+    acc: Vec2,
+    a_pos: f32,
+    a_pos_last: f32,
+    a_acc: f32,
+    spin_locked: bool,
+}
+
+#[derive(Component)]
+struct Player;
+
+// The floor's sprite, moved by move_floor_wall in rising floor mode
+#[derive(Component)]
+struct FloorWall;
+
+// Wall code from Rust Brick Breaker example
+enum WallLocation {
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+impl WallLocation {
+    fn position(&self, top_wall: f32) -> Vec2 {
+        match self {
+            WallLocation::Left => Vec2::new(LEFT_WALL, (top_wall+BOTTOM_WALL) / 2.0),
+            WallLocation::Right => Vec2::new(RIGHT_WALL, (top_wall+BOTTOM_WALL) / 2.0),
+            WallLocation::Bottom => Vec2::new(0., BOTTOM_WALL),
+            WallLocation::Top => Vec2::new(0., top_wall),
+        }
+    }
+
+    fn size(&self, top_wall: f32) -> Vec2 {
+        let arena_height = top_wall - BOTTOM_WALL;
+        let arena_width = RIGHT_WALL - LEFT_WALL;
+        // Make sure we haven't messed up our constants
+        assert!(arena_height > 0.0);
+        assert!(arena_width > 0.0);
+
+        match self {
+            WallLocation::Left | WallLocation::Right => {
+                Vec2::new(WALL_THICKNESS, arena_height + WALL_THICKNESS)
+            }
+            WallLocation::Bottom | WallLocation::Top => {
+                Vec2::new(arena_width + WALL_THICKNESS, WALL_THICKNESS)
+            }
+        }
+    }
+}
+#[derive(Bundle)]
+struct WallBundle {
+    // You can nest bundles inside of other bundles like this
+    // Allowing you to compose their functionality
+    sprite_bundle: SpriteBundle,
+}
+
+impl WallBundle {
+    // This "builder method" allows us to reuse logic across our wall entities,
+    // making our code easier to read and less prone to bugs when we change the logic
+    fn new(location: WallLocation, top_wall: f32) -> WallBundle {
+        WallBundle {
+            sprite_bundle: SpriteBundle {
+                transform: Transform {
+                    // We need to convert our Vec2 into a Vec3, by giving it a z-coordinate
+                    // This is used to determine the order of our sprites
+                    translation: location.position(top_wall).extend(0.0),
+                    // The z-scale of 2D objects must always be 1.0,
+                    // or their ordering will be affected in surprising ways.
+                    // See https://github.com/bevyengine/bevy/issues/4149
+                    scale: location.size(top_wall).extend(1.0),
+                    ..default()
+                },
+                sprite: Sprite {
+                    color: WALL_COLOR,
+                    ..default()
+                },
+                ..default()
+            },
+        }
+    }
+}
+
+// Static segment that fruits collide with, used for angled arena pieces
+#[derive(Component)]
+struct LineCollider {
+    start: Vec2,
+    end: Vec2,
+}
+
+impl LineCollider {
+    fn closest_point(&self, point: Vec2) -> Vec2 {
+        let segment: Vec2 = self.end - self.start;
+        let t: f32 = ((point - self.start).dot(segment) / segment.length_squared()).clamp(0.0, 1.0);
+        return self.start + segment * t;
+    }
+}
+
+#[derive(Bundle)]
+struct LineColliderBundle {
+    sprite_bundle: SpriteBundle,
+    collider: LineCollider,
+}
+
+impl LineColliderBundle {
+    fn new(start: Vec2, end: Vec2) -> LineColliderBundle {
+        LineColliderBundle {
+            sprite_bundle: SpriteBundle {
+                transform: LineColliderBundle::transform(start, end),
+                sprite: Sprite {
+                    color: WALL_COLOR,
+                    ..default()
+                },
+                ..default()
+            },
+            collider: LineCollider { start, end },
+        }
+    }
+
+    // Stretches the unit sprite along the segment
+    fn transform(start: Vec2, end: Vec2) -> Transform {
+        let segment: Vec2 = end - start;
+        return Transform {
+            translation: ((start + end) / 2.0).extend(0.0),
+            rotation: Quat::from_rotation_z(segment.y.atan2(segment.x)),
+            scale: Vec2::new(segment.length(), WALL_THICKNESS).extend(1.0),
+        };
+    }
+}
+
+// One of the two corner ramps, see GameSettings::gutters
+#[derive(Component)]
+struct Gutter;
+
+// The left and right gutter segments for a bottom wall centered at bottom_wall
+fn gutter_segments(bottom_wall: f32) -> [(Vec2, Vec2); 2] {
+    return [
+        (Vec2::new(LEFT_WALL, bottom_wall + GUTTER_HEIGHT), Vec2::new(LEFT_WALL + GUTTER_WIDTH, bottom_wall)),
+        (Vec2::new(RIGHT_WALL - GUTTER_WIDTH, bottom_wall), Vec2::new(RIGHT_WALL, bottom_wall + GUTTER_HEIGHT)),
+    ];
+}
+
+#[derive(Bundle)]
+struct FruitBundle {
+    sprite_bundle: SpriteBundle,
+    fruit: Fruit,
+}
+
+impl FruitBundle {
+    // Shared by every spawn site so dropped, merged and rained fruits stay identical
+    fn new(id: u32, group: u8, pos: Vec2, fruit_table: &FruitTable, texture: Handle<Image>) -> FruitBundle {
+        // boxes are drawn as plain untextured squares and start upright
+        let (texture, angle) = match fruit_table.shape {
+            CollisionShape::Circle => (texture, fruit_table.spawn_angle()),
+            CollisionShape::RoundedBox { .. } => (Handle::default(), 0.0),
+        };
+        FruitBundle {
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(2.0*fruit_table.get(group).radius)),
+                    color: fruit_table.color(group, 1.0),
+                    ..default()
+                },
+                texture,
+                transform: Transform { 
+                    translation: vec3(pos.x, pos.y, 0.0),
+                    rotation: Quat::from_rotation_z(angle), // 45 degrees for circles unless upright_spawn
+                    ..default()
+                },
+                ..default()
+            },
+            fruit: Fruit{
+                id,
+                group,
+                pos,
+                pos_last: pos,
+                // vel: Vec2::ZERO,
+                acc: Vec2::ZERO,
+                a_pos: angle,
+                a_pos_last: angle,
+                // a_vel: 0.0,
+                a_acc: 0.0,
+                color: Color::RED,
+                radius: fruit_table.get(group).radius,
+                shape: fruit_table.shape,
+                // boxes collide axis-aligned, so they stay upright to look the way they collide
+                spin_locked: fruit_table.get(group).spin_locked || fruit_table.shape != CollisionShape::Circle,
+                impact: 0.0,
+                impact_with: None,
+            },
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Scoreboard {
+    score: u32,
+}
+
+#[derive(Component)]
+struct ScoreboardText;
+
+// Highest group produced by a merge this run
+#[derive(Resource, Default)]
+struct BiggestFruit {
+    group: Option<u8>,
+}
+
+impl BiggestFruit {
+    // Returns true when `group` beats the current record
+    fn record(&mut self, group: u8) -> bool {
+        if self.group.map_or(true, |biggest| group > biggest) {
+            self.group = Some(group);
+            return true;
+        }
+        return false;
+    }
+}
+
+#[derive(Component)]
+struct BiggestFruitText;
+
+#[derive(Component)]
+struct DangerLine;
+
+#[derive(Component)]
+struct StressOverlay;
+
+#[derive(Component)]
+struct FruitSpawnTimer {
+    timer: Stopwatch,
+    // Seconds a Space press stays queued, so a tap a moment early still drops as soon as the
+    // cooldown ends. Set every frame by buffer_drop_press, so a tap that comes and goes between
+    // two FixedUpdate steps isn't missed either.
+    drop_buffer: f32,
+    last_x: Option<f32>, // where the player was when the last step looked at the drop, for the flick
+}
+
+impl FruitSpawnTimer {
+    // Cooldown already over, so the first drop of a run is immediate
+    fn ready() -> FruitSpawnTimer {
+        let mut timer = Stopwatch::new();
+        timer.set_elapsed(Duration::from_secs_f32(SPAWN_INTERVAL));
+        return FruitSpawnTimer { timer, drop_buffer: 0.0, last_x: None };
+    }
+}
+
+// Loaded from PHYSICS_CONFIG_PATH at startup when present, see config.rs
+#[derive(Resource, Clone, Deserialize)]
+#[serde(default)]
+struct PhysicsConfig {
+    gravity: f32,
+    // Gravity multiplier for the largest fruit, scaled linearly by radius down to 1.0 for the
+    // smallest. 1.0 keeps gravity uniform, > 1.0 makes big fruits fall faster
+    gravity_size_factor: f32,
+    // Largest positional correction applied to one colliding pair per step, so deep overlaps
+    // (after a big merge or a load) are pushed apart over several steps instead of exploding.
+    // Ordinary contacts overlap by far less, so they still resolve in a single step.
+    max_correction: f32,
+    wall_bounce: f32, // restitution off the walls and line colliders, 0.0 ..= 1.0
+    timestep: f32, // seconds per FixedUpdate step
+    // Walk the collision pairs backwards every other step. Each correction sees the ones made
+    // before it, so a fixed order slowly pushes piles toward the higher-indexed fruits.
+    relaxation_alternate: bool,
+    // Same-group pairs only merge when their relative speed is below this, so fruits ricocheting
+    // off each other bounce instead. Infinite merges on any touch.
+    merge_max_rel_speed: f32,
+    // Where a merged fruit appears: 0.0 at the midpoint of the pair, 1.0 resting where the lower
+    // fruit rested (same bottom), so a stacked merge doesn't grow down into the floor or a neighbor
+    merge_spawn_bias: f32,
+    // Gap between two same-group fruits within which the merge magnet pulls them together
+    magnet_range: f32,
+    // Terminal speed downward, px/s. Clamps only the fall, so sideways and upward speeds still
+    // go up to MAX_VEL.
+    max_fall_speed: f32,
+    // Fraction of its angular velocity a fruit loses every step, like drag, so a fruit spun up
+    // with nothing touching it slows to a stop. 0.0 ..= 1.0, 0.0 never slows.
+    angular_damping: f32,
+    // The same for linear velocity: air resistance, taking a little off every flight and fall.
+    // 0.0 ..= 1.0, 0.0 is frictionless.
+    air_damping: f32,
+    // Fraction of gravity added every 30 s of a run, see difficulty.rs. 0.0 keeps it constant.
+    gravity_ramp: f32,
+    // Sideways speed a nudge gives the pile, px/s, 0.0 disables it, and the seconds before the
+    // next one, see nudge.rs
+    nudge_speed: f32,
+    nudge_cooldown: f32,
+    // Seconds a fruit made by a merge has to wait before it can merge again, so a cascade plays
+    // out one step at a time. 0.0 lets it merge straight away, even in the same step.
+    merge_cooldown: f32,
+    // px/s the floor climbs during a run in rising floor mode, see raise_floor. 0.0 keeps it still.
+    floor_rise: f32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        PhysicsConfig {
+            gravity: GRAVITY,
+            gravity_size_factor: 1.0,
+            max_correction: MAX_CORRECTION,
+            wall_bounce: WALL_BOUNCE_CONST,
+            timestep: TIMESTEP,
+            relaxation_alternate: false,
+            merge_max_rel_speed: f32::INFINITY,
+            merge_spawn_bias: 0.0,
+            magnet_range: MAGNET_RANGE,
+            max_fall_speed: MAX_FALL_SPEED,
+            angular_damping: 0.0,
+            air_damping: 0.0,
+            gravity_ramp: 0.0,
+            nudge_speed: 0.0,
+            nudge_cooldown: NUDGE_COOLDOWN,
+            merge_cooldown: 0.0,
+            floor_rise: 0.0,
+        }
+    }
+}
+
+impl PhysicsConfig {
+    fn gravity_for_radius(&self, radius: f32, fruit_table: &FruitTable) -> f32 {
+        let radius_range: f32 = (fruit_table.max_radius() - fruit_table.min_radius()).max(f32::EPSILON);
+        let size: f32 = (radius - fruit_table.min_radius()) / radius_range;
+        return self.gravity * (1.0 + (self.gravity_size_factor - 1.0) * size);
+    }
+}
+
+#[derive(Resource)]
+struct ArenaConfig {
+    top_wall: f32,
+    danger_line_y: f32, // fruits resting above this height are about to overflow
+    bottom_wall: f32, // center of the bottom wall, starts each run at BOTTOM_WALL
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        ArenaConfig::with_top_wall(TOP_WALL)
+    }
+}
+
+impl ArenaConfig {
+    fn with_top_wall(top_wall: f32) -> ArenaConfig {
+        ArenaConfig {
+            top_wall,
+            danger_line_y: top_wall - DANGER_LINE_OFFSET,
+            bottom_wall: BOTTOM_WALL,
+        }
+    }
+
+    // Height fruits rest on
+    fn floor(&self) -> f32 {
+        return self.bottom_wall + WALL_THICKNESS/2.0;
+    }
+
+    // How fast the floor is moving right now, rising at floor_rise until it reaches the danger line
+    fn floor_speed(&self, floor_rise: f32) -> f32 {
+        if self.floor() >= self.danger_line_y {
+            return 0.0;
+        }
+        return floor_rise;
+    }
+}
+
+#[derive(Resource)]
+struct GameSettings {
+    refuse_blocked_drop: bool, // ignore Space while the column below the player is full
+    drop_mode: DropMode,
+    bomb_chance: f32, // 0.0 ..= 1.0 per drop, 0.0 disables bombs
+    spawn_height: SpawnHeight,
+    throw_mode: bool, // dropping while holding A/D throws the fruit sideways
+    scoring: ScoringMode,
+    // A merge within this many seconds of a dropped fruit landing is a perfect drop, worth
+    // perfect_drop_bonus more. 0.0 turns the bonus off.
+    perfect_drop_window: f32,
+    perfect_drop_bonus: u32,
+    flick: bool, // dropping while moving carries some of the player's speed into the fruit
+    save_on_exit: bool, // quitting mid-run keeps the board for the next launch, see autosave.rs
+    time_attack: bool, // runs last TIME_ATTACK_DURATION seconds, from the next run on, see win.rs
+    gutters: bool, // angled ramps in the bottom corners that roll fruits toward the center
+    keys: KeyLayout,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            refuse_blocked_drop: true,
+            drop_mode: DropMode::Free,
+            bomb_chance: 0.0,
+            spawn_height: SpawnHeight::Player,
+            throw_mode: false,
+            scoring: ScoringMode::Merges,
+            perfect_drop_window: 0.0,
+            perfect_drop_bonus: PERFECT_DROP_BONUS,
+            flick: false,
+            save_on_exit: true,
+            time_attack: false,
+            gutters: false,
+            keys: KeyLayout::Letters,
+        }
+    }
+}
+
+// How a run earns points
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ScoringMode {
+    Merges, // points only for merges
+    // merges, plus a trickle for every resting contact in the pile, so a dense, well packed pile
+    // earns while it sits there
+    Packing,
+}
+
+// Where a dropped fruit starts falling from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SpawnHeight {
+    Player, // at the held fruit, above the top wall
+    // just inside the top wall, falling less far so big fruits hit the pile softer. They start
+    // at SPAWN_ENTRY_SPEED, faster than DANGER_FALL_SPEED, so they still read as dropping in
+    // rather than as the pile poking over the danger line.
+    InsideTop,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+enum DropMode {
+    Free, // A/D move the player anywhere between the walls
+    FixedCenter, // classic: always drop from x = 0, only the timing is up to the player
+}
+
+// Keys that move the player and drop, picked on the settings screen. Shift with either move
+// key is a nudge, see nudge.rs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+enum KeyLayout {
+    Letters, // A / D to move, Space to drop
+    Arrows, // Left / Right to move, Down to drop
+}
+
+impl KeyLayout {
+    const ALL: [KeyLayout; 2] = [KeyLayout::Letters, KeyLayout::Arrows];
+
+    fn left_key(&self) -> KeyCode {
+        match self {
+            KeyLayout::Letters => KeyCode::A,
+            KeyLayout::Arrows => KeyCode::Left,
+        }
+    }
+    fn right_key(&self) -> KeyCode {
+        match self {
+            KeyLayout::Letters => KeyCode::D,
+            KeyLayout::Arrows => KeyCode::Right,
+        }
+    }
+    fn drop_key(&self) -> KeyCode {
+        match self {
+            KeyLayout::Letters => KeyCode::Space,
+            KeyLayout::Arrows => KeyCode::Down,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct GameRng(StdRng);
+
+// What GameRng was seeded with, saved with replays
+#[derive(Resource)]
+struct GameSeed(u64);
+
+// Set when the seed came from --seed or --replay, so nothing may swap it for another
+#[derive(Resource, Default)]
+struct SeedChosen(bool);
+
+fn seed_game(app: &mut App, seed: u64) {
+    app.insert_resource(GameSeed(seed))
+        .insert_resource(GameRng(StdRng::seed_from_u64(seed)));
+}
+
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum GameState {
+    #[default]
+    Playing,
+    GameOver, // the simulation has stopped, see gameover.rs
+    Won, // goal mode only, see win.rs
+}
+
+// Sent for every merge, including each step of a cascade
+#[derive(Event)]
+struct FruitMerged {
+    group: u8, // group of the fruit produced
+    pos: Vec2,
+    score: u32, // points awarded, including the cascade multiplier
+    cascade_depth: u32,
+}
+
+// A fruit hit another fruit or a wall at `speed` px/s this step, sent by record_damage for
+// impacts of at least COLLISION_EVENT_MIN_SPEED so resting contact stays quiet. Two fruits
+// whose fastest impact was each other send one event between them.
+#[derive(Event)]
+struct CollisionEvent {
+    speed: f32,
+}
+
+// A big fruit hit the floor fast, see apply_constraint
+#[derive(Event)]
+struct HardLanding {
+    id: u32,
+    pos: Vec2,
+    speed: f32, // px/s into the floor
+    radius: f32,
+}
+
+#[derive(Event)]
+struct FruitDropped {
+    group: u8, // 0 for a bomb, which has no group of its own
+    bomb: bool,
+    x: f32,
+    launch: f32, // px/s sideways it was thrown or flicked with
+}
+
+// Off for the no-merge chaos mode (--no-merge or the settings screen): fruits only collide,
+// so nothing scores
+#[derive(Resource)]
+struct MergeEnabled(bool);
+
+impl Default for MergeEnabled {
+    fn default() -> Self {
+        MergeEnabled(true)
+    }
+}
+
+fn merging_enabled(merge_enabled: Res<MergeEnabled>) -> bool {
+    return merge_enabled.0;
+}
+
+// Variant (--soft-walls): the side walls are springs a heavy pile can bulge into a little,
+// instead of hard constraints. The floor stays hard.
+#[derive(Resource, Default)]
+struct SoftWalls(bool);
+
+// Easy-mode assist (settings screen): nearby fruits of the same group drift toward each other
+#[derive(Resource, Default)]
+struct MergeMagnet(bool);
+
+fn merge_magnet_enabled(magnet: Res<MergeMagnet>) -> bool {
+    return magnet.0;
+}
+
+// Work done by the physics since the last frame, read and reset by profiling.rs
+#[derive(Resource, Default)]
+struct PhysicsCounters {
+    pairs_tested: u32, // fruit pairs checked by apply_collisions
+    merges: u32,
+}
+
+// Touching pairs at rest against each other in the last apply_collisions pass
+#[derive(Resource, Default)]
+struct RestingContacts(u32);
+
+// Merges that follow each other within COMBO_WINDOW form a combo
+#[derive(Resource, Default)]
+struct ComboTracker {
+    count: u32, // merges in the current combo
+    points: u32, // points scored during the current combo
+    since_last: f32, // seconds since the last merge
+}
+
+// Hidden profiling mode (F9 or --rain): drops a fruit at a random X every RAIN_INTERVAL,
+// ignoring the spawn cooldown, so the arena fills as fast as possible
+#[derive(Resource)]
+struct StressTest {
+    active: bool,
+    timer: Timer,
+}
+
+impl Default for StressTest {
+    fn default() -> Self {
+        StressTest {
+            active: false,
+            timer: Timer::from_seconds(RAIN_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+// Handles loaded once in setup and cloned wherever a fruit is spawned
+#[derive(Resource)]
+struct FruitAssets {
+    fruit_icon: Handle<Image>,
+    decals: Vec<Option<Handle<Image>>>, // indexed by group
+    crack: Handle<Image>,
+    skin: Vec<Handle<Image>>, // indexed by group, fruit_icon unless a skin pack is loaded (see skin.rs)
+}
+
+impl FruitAssets {
+    fn texture(&self, group: u8) -> Handle<Image> {
+        return self.skin.get(group as usize).unwrap_or(&self.fruit_icon).clone();
+    }
+}
+
+// Runs one of the windowless tools (--golden, --golden-update, --bench-physics) if asked for.
+// Returns false when none was, and the game should start.
+pub fn run_tool(args: &[String]) -> bool {
+    if args.iter().any(|arg| arg == "--golden") {
+        golden::run(false);
+        return true;
+    }
+    if args.iter().any(|arg| arg == "--golden-update") {
+        golden::run(true);
+        return true;
+    }
+    if args.iter().any(|arg| arg == "--bench-physics") {
+        bench::run();
+        return true;
+    }
+    return false;
+}
+
+// The game's window, for WindowPlugin. Starts with the saved vsync choice.
+pub fn primary_window() -> Window {
+    return Window {
+        title: WINDOW_TITLE.into(),
+        present_mode: settings::saved_graphics_settings().present_mode(),
+        ..default()
+    };
+}
+
+// The whole game, for an App that already has DefaultPlugins: the camera, the step rate from
+// physics.ron and the frame time diagnostics come with it. The window and the command line are
+// left to the host (see main.rs).
+pub struct SuikaPlugin;
+
+impl Plugin for SuikaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            update_sprites.run_if(in_state(GameState::Playing)), // the game over sequence animates them itself
+            update_scoreboard,
+            update_biggest_fruit_text,
+            update_danger_line,
+            draw_drop_shadow,
+            update_cracks,
+            update_outlines,
+        ))
+            .init_resource::<FruitOutlines>()
+            .init_resource::<RenderInterpolation>();
+        add_game(app);
+        let physics_config: PhysicsConfig = config::load_physics_config();
+        app.insert_resource(FixedTime::new_from_secs(physics_config.timestep))
+            .insert_resource(physics_config)
+            .add_systems(Startup, spawn_camera);
+        // the stress test overlay's FPS. A host may have added it already
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        app.add_plugins((ProfilingPlugin, GraphicsPlugin, DropRatePlugin, SkinPlugin, TimelinePlugin, SettlePlugin, TutorialPlugin, LeaderboardPlugin, NudgePlugin, AutosavePlugin, MusicPlugin))
+            .add_plugins((AchievementsPlugin, SoundPlugin, SandboxPlugin, ThemePlugin, HoverPlugin, CameraPlugin, EffectsPlugin, EstimatePlugin, PreviewPlugin, DebugDrawPlugin, GameOverPlugin, SharePlugin, RestartPlugin, WinPlugin, GravityWellPlugin))
+            .add_systems(Update, (
+                toggle_stress_test,
+                update_stress_overlay,
+            ))
+            // last, the saved settings it applies go into the resources the plugins above add
+            .add_plugins(SettingsPlugin);
+    }
+}
+
+// Command line flags, applied after SuikaPlugin so they win over the saved settings
+pub fn apply_args(app: &mut App, args: &[String]) {
+    if args.iter().any(|arg| arg == "--rain") {
+        app.world.resource_mut::<StressTest>().active = true;
+    }
+    if args.iter().any(|arg| arg == "--sandbox") {
+        app.world.resource_mut::<Sandbox>().active = true;
+    }
+    // flags that take a value: --seed <share code>, --win <count>, --record <file>, --replay <file>
+    let flag_value = |flag: &str| -> Option<String> {
+        let index: usize = args.iter().position(|arg| arg == flag)?;
+        return args.get(index + 1).cloned();
+    };
+    if let Some(code) = flag_value("--seed") {
+        match share::parse_share_code(&code) {
+            Some(seed) => {
+                seed_game(app, seed);
+                app.world.resource_mut::<SeedChosen>().0 = true;
+            }
+            None => warn!("Invalid share code {}, using a random seed", code),
+        }
+    }
+    if let Some(target) = flag_value("--win") {
+        match target.parse::<u32>() {
+            Ok(target) if target > 0 => app.world.resource_mut::<WinCondition>().target = Some(target),
+            _ => warn!("--win needs a number of top-tier fruits, got {}", target),
+        }
+    }
+    if let Some(path) = flag_value("--replay") {
+        let loaded = app.world.resource_mut::<ReplayPlayer>().load(&path);
+        match loaded {
+            Ok(seed) => {
+                seed_game(app, seed);
+                app.world.resource_mut::<SeedChosen>().0 = true;
+            }
+            Err(err) => warn!("Could not load replay {}: {}", path, err),
+        }
+    }
+    if let Some(path) = flag_value("--record") {
+        let seed: u64 = app.world.resource::<GameSeed>().0;
+        app.world.resource_mut::<ReplayRecorder>().start(path.into(), seed);
+    }
+    if args.iter().any(|arg| arg == "--low-spawn") {
+        app.world.resource_mut::<GameSettings>().spawn_height = SpawnHeight::InsideTop;
+    }
+    if args.iter().any(|arg| arg == "--no-merge") {
+        app.world.resource_mut::<MergeEnabled>().0 = false;
+    }
+    if args.iter().any(|arg| arg == "--relief") {
+        app.world.resource_mut::<DynamicDifficulty>().enabled = true;
+    }
+    if args.iter().any(|arg| arg == "--hard-mode") {
+        let mut difficulty = app.world.resource_mut::<DynamicDifficulty>();
+        difficulty.enabled = true;
+        difficulty.mode = DifficultyMode::Hard;
+    }
+    if args.iter().any(|arg| arg == "--gravity-ramp") {
+        app.world.resource_mut::<PhysicsConfig>().gravity_ramp = GRAVITY_RAMP;
+    }
+    if args.iter().any(|arg| arg == "--rounded") {
+        app.world.resource_mut::<FruitTable>().shape = CollisionShape::RoundedBox { corner: ROUNDED_BOX_CORNER };
+    }
+    if args.iter().any(|arg| arg == "--throw") {
+        app.world.resource_mut::<GameSettings>().throw_mode = true;
+    }
+    if args.iter().any(|arg| arg == "--packing") {
+        app.world.resource_mut::<GameSettings>().scoring = ScoringMode::Packing;
+    }
+    if args.iter().any(|arg| arg == "--time-attack") {
+        app.world.resource_mut::<GameSettings>().time_attack = true;
+    }
+    if args.iter().any(|arg| arg == "--bombs") {
+        app.world.resource_mut::<GameSettings>().bomb_chance = BOMB_CHANCE;
+    }
+    if args.iter().any(|arg| arg == "--nudge") {
+        app.world.resource_mut::<PhysicsConfig>().nudge_speed = NUDGE_SPEED;
+    }
+    if args.iter().any(|arg| arg == "--gutters") {
+        app.world.resource_mut::<GameSettings>().gutters = true;
+    }
+    if args.iter().any(|arg| arg == "--perfect-drops") {
+        app.world.resource_mut::<GameSettings>().perfect_drop_window = PERFECT_DROP_WINDOW;
+    }
+    if args.iter().any(|arg| arg == "--flick") {
+        app.world.resource_mut::<GameSettings>().flick = true;
+    }
+    if args.iter().any(|arg| arg == "--tall") {
+        app.insert_resource(ArenaConfig::with_top_wall(TALL_TOP_WALL));
+        app.world.resource_mut::<CameraFollow>().enabled = true;
+    }
+    if args.iter().any(|arg| arg == "--rising-floor") {
+        app.world.resource_mut::<PhysicsConfig>().floor_rise = FLOOR_RISE_SPEED;
+    }
+    if args.iter().any(|arg| arg == "--upright") {
+        app.world.resource_mut::<FruitTable>().upright_spawn = true;
+    }
+    if args.iter().any(|arg| arg == "--soft-walls") {
+        app.world.resource_mut::<SoftWalls>().0 = true;
+    }
+    if args.iter().any(|arg| arg == "--wells") {
+        app.world.resource_mut::<GravityWell>().enabled = true;
+    }
+    if args.iter().any(|arg| arg == "--reset-tutorial") {
+        app.world.resource_mut::<Achievements>().tutorial_seen = false;
+    }
+    if args.iter().any(|arg| arg == "--attract") {
+        app.world.resource_mut::<AttractMode>().active = true;
+    }
+}
+
+// Everything the simulation needs, without windowing or rendering, so it can also be
+// driven headlessly (see golden.rs)
+fn add_game(app: &mut App) {
+    seed_game(app, rand::random());
+    app.insert_resource(Scoreboard { score: 0 })
+        .init_resource::<GameSettings>()
+        .init_resource::<PhysicsConfig>()
+        .init_resource::<FruitTable>()
+        .init_resource::<ArenaConfig>()
+        .init_resource::<StressTest>()
+        .init_resource::<SeedChosen>()
+        .init_resource::<ComboTracker>()
+        .init_resource::<BiggestFruit>()
+        .init_resource::<PhysicsCounters>()
+        .init_resource::<RestingContacts>()
+        .init_resource::<MergeEnabled>()
+        .init_resource::<MergeMagnet>()
+        .init_resource::<SoftWalls>()
+        .init_resource::<DebugDraw>()
+        .init_resource::<Localization>()
+        .init_resource::<BoardSnapshot>()
+        .add_event::<FruitMerged>()
+        .add_event::<FruitDropped>()
+        .add_event::<HardLanding>()
+        .add_event::<CollisionEvent>()
+        .add_state::<GameState>()
+        .configure_set(FixedUpdate, Simulation.run_if(in_state(GameState::Playing)))
+        .add_plugins((AttractPlugin, StepPlugin, DifficultyPlugin, ReplayPlugin))
+        .add_systems(Startup, setup)
+        // every run, the first included, starts with the same cooldown whatever the last one left
+        .add_systems(OnEnter(GameState::Playing), (reset_spawn_timer, reset_floor))
+        // chained so the physics steps in the same order every run
+        .add_systems(FixedUpdate, (
+            input_handler, 
+            apply_merges.run_if(merging_enabled),
+            // merged fruits must exist, and consumed ones be gone, before a bomb looks for its group
+            apply_deferred,
+            detonate_bombs.run_if(merging_enabled), // a bomb is a merge too, it just falls in chaos mode
+            apply_gravity,
+            apply_collisions,
+            apply_constraint,
+            apply_line_colliders,
+            apply_torque,
+            physics_update,
+        ).chain().in_set(Simulation))
+        .add_systems(Update, buffer_drop_press.run_if(in_state(GameState::Playing)))
+        .add_systems(FixedUpdate, config::sanitize_timestep.before(Simulation))
+        .add_systems(FixedUpdate, raise_floor.before(apply_constraint).in_set(Simulation))
+        .add_systems(FixedUpdate, sync_gutters.after(raise_floor).before(apply_line_colliders))
+        .add_systems(Update, move_floor_wall)
+        .add_systems(FixedUpdate, rain_fruits.run_if(stress_test_active).before(input_handler).in_set(Simulation))
+        .add_systems(FixedUpdate, update_combo.after(apply_merges).in_set(Simulation))
+        .add_systems(FixedUpdate, trickle_packing_score.run_if(packing_scoring).after(apply_collisions).in_set(Simulation))
+        .add_systems(FixedUpdate, age_drops.after(apply_merges).in_set(Simulation))
+        .add_systems(FixedUpdate, cool_merges.after(apply_merges).in_set(Simulation))
+        .add_systems(FixedUpdate, record_damage.after(apply_line_colliders).before(physics_update).in_set(Simulation))
+        .add_systems(FixedUpdate, apply_merge_magnet.run_if(merge_magnet_enabled).run_if(merging_enabled)
+            .after(apply_gravity).before(apply_collisions).in_set(Simulation))
+        .add_systems(FixedUpdate, nudge_landing_neighbors.after(apply_constraint).before(physics_update).in_set(Simulation))
+        .add_systems(FixedUpdate, board::update_board_snapshot.after(physics_update).in_set(Simulation));
+    #[cfg(debug_assertions)]
+    app.add_systems(FixedUpdate, check_finite_positions.after(physics_update).in_set(Simulation))
+        .add_systems(Last, check_orphaned_parts)
+        .add_systems(Update, board::dump_board);
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    arena_config: Res<ArenaConfig>,
+    fruit_table: Res<FruitTable>,
+    localization: Res<Localization>,
+    mut rng: ResMut<GameRng>,
+){
+    let starting_group: u8 = fruit_table.random_spawn_group(&mut rng);
+    let fruit_icon: Handle<Image> = asset_server.load("fruit_icon.png");
+    commands.insert_resource(FruitAssets {
+        fruit_icon: fruit_icon.clone(),
+        decals: fruit_table.defs
+            .iter()
+            .map(|def| def.decal.map(|path| asset_server.load(path)))
+            .collect(),
+        crack: asset_server.load("fruit_crack.png"),
+        skin: vec![fruit_icon.clone(); fruit_table.len()],
+    });
+    commands.spawn((
+        SpriteBundle{
+            transform: Transform { 
+                translation: vec3(0.0, arena_config.top_wall + PLAYER_ABOVE_TOP_WALL, 0.0),
+                rotation: Quat::from_rotation_z(fruit_table.spawn_angle()),
+                ..default()
+                // rotation: (), scale: () 
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(2.0*fruit_table.get(starting_group).radius)),
+                color: fruit_table.color(starting_group, 1.0),
+                ..default()
+            },
+            texture: fruit_icon.clone(),
+            ..default()
+        },
+        Player,
+        FruitIterator{
+            next_id: 0,
+            next_group: starting_group,
+            next_is_bomb: false,
+        },
+        FruitSpawnTimer::ready(),
+    ));
+
+    commands.spawn(WallBundle::new(WallLocation::Left, arena_config.top_wall));
+    commands.spawn(WallBundle::new(WallLocation::Right, arena_config.top_wall));
+    commands.spawn((WallBundle::new(WallLocation::Bottom, arena_config.top_wall), FloorWall));
+    commands.spawn(WallBundle::new(WallLocation::Top, arena_config.top_wall));
+
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform {
+                translation: vec3(0.0, arena_config.danger_line_y, 0.0),
+                scale: vec3(RIGHT_WALL - LEFT_WALL - WALL_THICKNESS, DANGER_LINE_THICKNESS, 1.0),
+                ..default()
+            },
+            sprite: Sprite {
+                color: DANGER_LINE_COLOR,
+                ..default()
+            },
+            ..default()
+        },
+        DangerLine,
+    ));
+
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                localization.get("score"),
+                TextStyle {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font_size: SCOREBOARD_FONT_SIZE,
+                color: SCORE_COLOR,
+                ..default()
+            }),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: SCOREBOARD_TEXT_PADDING,
+            left: SCOREBOARD_TEXT_PADDING,
+            ..default()
+        }),
+        ScoreboardText,
+    ));
+
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                localization.get("biggest"),
+                TextStyle {
+                    font_size: OVERLAY_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font_size: OVERLAY_FONT_SIZE,
+                color: SCORE_COLOR,
+                ..default()
+            }),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: BIGGEST_FRUIT_TEXT_TOP,
+            left: SCOREBOARD_TEXT_PADDING,
+            ..default()
+        }),
+        BiggestFruitText,
+    ));
+
+    let mut stress_overlay = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: OVERLAY_FONT_SIZE,
+            color: TEXT_COLOR,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: SCOREBOARD_TEXT_PADDING,
+        right: SCOREBOARD_TEXT_PADDING,
+        ..default()
+    });
+    stress_overlay.visibility = Visibility::Hidden;
+    commands.spawn((stress_overlay, StressOverlay));
+
+}
+
+// Bevy has no icon field on Window, so the icon is set directly on the winit window.
+// If the icon file can't be read the platform default is left in place.
+pub fn set_window_icon(
+    primary_query: Query<Entity, With<PrimaryWindow>>,
+    winit_windows: NonSend<WinitWindows>,
+){
+    let Ok(primary_entity) = primary_query.get_single() else {
+        return;
+    };
+    let Some(primary) = winit_windows.get_window(primary_entity) else {
+        return;
+    };
+
+    let icon_image = match image::open(WINDOW_ICON_PATH) {
+        Ok(icon_image) => icon_image.into_rgba8(),
+        Err(err) => {
+            warn!("Could not load window icon {}: {}", WINDOW_ICON_PATH, err);
+            return;
+        }
+    };
+    let (width, height) = icon_image.dimensions();
+    match Icon::from_rgba(icon_image.into_raw(), width, height) {
+        Ok(icon) => primary.set_window_icon(Some(icon)),
+        Err(err) => warn!("Invalid window icon {}: {}", WINDOW_ICON_PATH, err),
+    }
+}
+
+// Spawns a fruit and, if its group has one, the decal child that rotates along with it
+fn spawn_fruit_bundle(
+    commands: &mut Commands,
+    fruit_assets: &FruitAssets,
+    bundle: FruitBundle,
+) -> Entity {
+    let group: usize = bundle.fruit.group as usize;
+    let radius: f32 = bundle.fruit.radius;
+    let texture: Handle<Image> = bundle.sprite_bundle.texture.clone();
+    let mut fruit_entity = commands.spawn((bundle, Damage { amount: 0.0 }));
+    fruit_entity.with_children(|parent| {
+        // hidden until update_outlines has checked FruitOutlines
+        parent.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(2.0*(radius + OUTLINE_WIDTH))),
+                    color: OUTLINE_COLOR,
+                    ..default()
+                },
+                texture,
+                transform: Transform::from_xyz(0.0, 0.0, OUTLINE_Z),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Outline,
+            FruitPart,
+        ));
+        if let Some(decal) = &fruit_assets.decals[group] {
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(2.0*radius)),
+                        ..default()
+                    },
+                    texture: decal.clone(),
+                    transform: Transform::from_xyz(0.0, 0.0, FRUIT_DECAL_Z),
+                    ..default()
+                },
+                FruitPart,
+            ));
+        }
+        parent.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(2.0*radius)),
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                    ..default()
+                },
+                texture: fruit_assets.crack.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, CRACK_Z),
+                ..default()
+            },
+            CrackDecal,
+            FruitPart,
+        ));
+    });
+    return fruit_entity.id();
+}
+
+fn spawn_fruit(
+    mut commands: Commands,
+    fruit_iterator: &mut Mut<'_, FruitIterator>,
+    player_translation: Vec3,
+    fruit_assets: &FruitAssets,
+    fruit_table: &FruitTable,
+    rng: &mut GameRng,
+    settings: &GameSettings,
+    top_wall: f32,
+    throw_speed: f32, // px/s sideways, 0.0 unless thrown or flicked
+    spawnable: usize,
+    dt: f32,
+){
+    let radius: f32 = fruit_table.get(fruit_iterator.next_group).radius;
+    let mut pos: Vec2 = player_translation.truncate();
+    if settings.spawn_height == SpawnHeight::InsideTop {
+        // kept clear of the wall so column_blocked doesn't count it as overflowing
+        pos.y = pos.y.min(top_wall - WALL_THICKNESS/2.0 - radius - SPAWN_CLEARANCE);
+    }
+    let mut bundle: FruitBundle = FruitBundle::new(
+        fruit_iterator.next_id,
+        fruit_iterator.next_group,
+        pos,
+        fruit_table,
+        fruit_assets.texture(fruit_iterator.next_group),
+    );
+    let entry_speed: f32 = if settings.spawn_height == SpawnHeight::InsideTop { SPAWN_ENTRY_SPEED } else { 0.0 };
+    bundle.fruit.set_vel(dt, Vec2::new(throw_speed, -entry_speed));
+    if fruit_iterator.next_is_bomb {
+        spawn_bomb(&mut commands, bundle);
+    } else {
+        let fruit_entity: Entity = spawn_fruit_bundle(&mut commands, fruit_assets, bundle);
+        if settings.perfect_drop_window > 0.0 {
+            commands.entity(fruit_entity).insert(DropAge(None));
+        }
+    }
+    fruit_iterator.next_id += 1;
+    // only roll for a bomb when they're enabled, so the group sequence is unchanged otherwise
+    fruit_iterator.next_is_bomb = settings.bomb_chance > 0.0 && rng.0.gen::<f32>() < settings.bomb_chance;
+    fruit_iterator.next_group = if fruit_iterator.next_is_bomb { 0 } else { fruit_table.random_group_below(rng, spawnable) };
+}
+
+fn spawn_bomb(
+    commands: &mut Commands,
+    mut bundle: FruitBundle,
+){
+    bundle.sprite_bundle.sprite.color = BOMB_COLOR;
+    commands.spawn((
+        bundle,
+        Bomb {
+            fuse: Timer::from_seconds(BOMB_FUSE, TimerMode::Once),
+        },
+    ));
+}
+
+fn held_color(fruit_iterator: &FruitIterator, fruit_table: &FruitTable) -> Color {
+    if fruit_iterator.next_is_bomb {
+        return BOMB_COLOR;
+    }
+    return fruit_table.color(fruit_iterator.next_group, 1.0);
+}
+
+// True if a fruit in the column of width 2*radius centered on x already reaches the top wall,
+// meaning anything dropped there would immediately overflow
+fn column_blocked<'a>(
+    fruits: impl Iterator<Item = &'a Fruit>,
+    x: f32,
+    radius: f32,
+    top_wall: f32,
+) -> bool {
+    let top_limit: f32 = top_wall - WALL_THICKNESS/2.0;
+    for fruit in fruits {
+        if (fruit.pos.x - x).abs() < (fruit.radius + radius) && (fruit.pos.y + fruit.radius) >= top_limit {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Space is read in Update, which sees every frame's presses, rather than in FixedUpdate, where
+// just_pressed is gone on frames that run no step and counted twice on frames that run two
+fn buffer_drop_press(
+    input: Res<Input<KeyCode>>,
+    settings: Res<GameSettings>,
+    step_mode: Res<StepMode>,
+    mut timer_query: Query<&mut FruitSpawnTimer, With<Player>>,
+){
+    // a press while paused shouldn't drop the moment the game resumes
+    if step_mode.paused || !input.just_pressed(settings.keys.drop_key()) {
+        return;
+    }
+    for mut spawn_timer in timer_query.iter_mut() {
+        spawn_timer.drop_buffer = DROP_BUFFER_TIME;
+    }
+}
+
+fn input_handler(
+    input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    time_step: Res<FixedTime>,
+    settings: Res<GameSettings>,
+    arena_config: Res<ArenaConfig>,
+    difficulty: Res<DynamicDifficulty>,
+    mut rng: ResMut<GameRng>,
+    mut query: Query<(&mut Transform, &mut FruitIterator, &mut Sprite, &mut FruitSpawnTimer), With<Player>>,
+    fruit_query: Query<&Fruit, Without<Bomb>>, // a falling bomb doesn't block a column
+    mut commands: Commands,
+    fruit_assets: Res<FruitAssets>,
+    fruit_table: Res<FruitTable>,
+    mut dropped_events: EventWriter<FruitDropped>,
+    mut attract: ResMut<AttractMode>,
+    mut replay: ResMut<ReplayPlayer>,
+    mut throw_charge: Local<f32>, // px/s, signed
+){
+    let (mut player_transform, mut fruit_iterator, mut sprite, mut spawn_timer) = query.single_mut();
+    
+    spawn_timer.timer.tick(time_step.period);
+    spawn_timer.drop_buffer = (spawn_timer.drop_buffer - time_step.period.as_secs_f32()).max(0.0);
+
+    // Radius of the fruit the player sprite is showing. Refreshed right after a drop advances
+    // next_group so the wall clamp below always matches what is on screen this frame.
+    let mut held_radius: f32 = fruit_table.get(fruit_iterator.next_group).radius;
+
+    if settings.drop_mode == DropMode::FixedCenter {
+        player_transform.translation.x = 0.0;
+    }
+    // how far the player really went since the last step, attract mode, nudges and the walls included
+    let moved: f32 = spawn_timer.last_x.map_or(0.0, |last_x| player_transform.translation.x - last_x);
+    spawn_timer.last_x = Some(player_transform.translation.x);
+
+    let mut direction: f32 = 0.0;
+    let ready: bool = spawn_timer.timer.elapsed() > Duration::from_secs_f32(difficulty.spawn_interval());
+    if ready {
+        // Shift+A/D is a nudge, not a move
+        if settings.drop_mode == DropMode::Free && !replay.active && !input.any_pressed(NUDGE_MODIFIERS) {
+            if input.pressed(settings.keys.left_key()){
+                direction -= 1.0;
+            }
+            if input.pressed(settings.keys.right_key()){
+                direction += 1.0;
+            }
+        }
+        // letting go of A/D drops the charge, so only a throw made while moving goes sideways
+        *throw_charge = if settings.throw_mode && direction != 0.0 {
+            (*throw_charge + direction * THROW_CHARGE_RATE * time_step.period.as_secs_f32()).clamp(-THROW_MAX_SPEED, THROW_MAX_SPEED)
+        } else {
+            0.0
+        };
+        let blocked: bool = column_blocked(
+            fruit_query.iter(),
+            player_transform.translation.x,
+            held_radius,
+            arena_config.top_wall,
+        );
+        sprite.color = held_color(&fruit_iterator, &fruit_table);
+        if blocked && (time.elapsed_seconds() * BLOCKED_FLASH_RATE).fract() < 0.5 {
+            sprite.color = Color::RED;
+        }
+        // a replay puts the player in place itself and ignores the keyboard
+        let drop_pressed: bool = ((input.pressed(settings.keys.drop_key()) || spawn_timer.drop_buffer > 0.0) && !replay.active)
+            || attract.drop_requested || replay.drop_due;
+        if drop_pressed && !(blocked && settings.refuse_blocked_drop) {
+            attract.drop_requested = false;
+            replay.drop_due = false;
+            let flick: f32 = if settings.flick {
+                flick_speed(moved, time_step.period.as_secs_f32())
+            } else {
+                0.0
+            };
+            // a replay has no keys held to throw or flick with, so it uses the recorded launch
+            let launch: f32 = if replay.active {
+                replay.drop_launch
+            } else {
+                (*throw_charge + flick).clamp(-THROW_MAX_SPEED, THROW_MAX_SPEED)
+            };
+            dropped_events.send(FruitDropped {
+                group: fruit_iterator.next_group,
+                bomb: fruit_iterator.next_is_bomb,
+                x: player_transform.translation.x,
+                launch,
+            });
+            spawn_fruit(commands, &mut fruit_iterator, player_transform.translation, &fruit_assets, &fruit_table, &mut rng, &settings, arena_config.top_wall, launch, difficulty.spawnable(&fruit_table), time_step.period.as_secs_f32());
+            held_radius = fruit_table.get(fruit_iterator.next_group).radius;
+            sprite.custom_size = Some(Vec2::splat(2.0*held_radius));
+            sprite.color = fruit_table.color(fruit_iterator.next_group, 0.0);
+            spawn_timer.timer.reset();
+            spawn_timer.drop_buffer = 0.0;
+        } else if blocked {
+            attract.drop_requested = false; // attract mode picks another column
+        }
+
+    }
+
+    let new_x: f32 = player_transform.translation.x + direction * PLAYER_SPEED * time_step.period.as_secs_f32();
+    player_transform.translation.x = clamp_player_x(new_x, held_radius);
+}
+
+// Sideways speed a flicked fruit leaves with after the player moved `moved` px in the last
+// step: FLICK_FRACTION of the player's velocity, at most FLICK_MAX_SPEED
+fn flick_speed(moved: f32, dt: f32) -> f32 {
+    return (moved / dt.max(f32::EPSILON) * FLICK_FRACTION).clamp(-FLICK_MAX_SPEED, FLICK_MAX_SPEED);
+}
+
+// Keeps a held fruit of the given radius entirely inside the side walls
+fn clamp_player_x(x: f32, radius: f32) -> f32 {
+    return x.clamp(
+        LEFT_WALL + WALL_THICKNESS/2.0 + radius,
+        RIGHT_WALL - WALL_THICKNESS/2.0 - radius,
+    );
+}
+
+fn packing_scoring(settings: Res<GameSettings>) -> bool {
+    return settings.scoring == ScoringMode::Packing;
+}
+
+// Points build up a fraction at a time and are added as they make a whole one
+fn trickle_packing_score(
+    time_step: Res<FixedTime>,
+    resting_contacts: Res<RestingContacts>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut owed: Local<f32>,
+){
+    *owed += resting_contacts.0 as f32 * PACKING_POINTS_PER_CONTACT * time_step.period.as_secs_f32();
+    let whole: f32 = owed.floor();
+    scoreboard.score += whole as u32;
+    *owed -= whole;
+}
+
+fn reset_spawn_timer(mut timer_query: Query<&mut FruitSpawnTimer, With<Player>>) {
+    for mut spawn_timer in timer_query.iter_mut() {
+        *spawn_timer = FruitSpawnTimer::ready();
+    }
+}
+
+// Rising floor mode: the floor climbs floor_rise px/s until it reaches the danger line, and the
+// pile on it is pushed up by apply_constraint until it overflows
+fn raise_floor(
+    time_step: Res<FixedTime>,
+    physics_config: Res<PhysicsConfig>,
+    mut arena_config: ResMut<ArenaConfig>,
+){
+    let speed: f32 = arena_config.floor_speed(physics_config.floor_rise);
+    if speed == 0.0 {
+        return;
+    }
+    let dt = time_step.period.as_secs_f32();
+    let top: f32 = arena_config.danger_line_y - WALL_THICKNESS/2.0;
+    arena_config.bottom_wall = (arena_config.bottom_wall + speed * dt).min(top);
+}
+
+fn reset_floor(mut arena_config: ResMut<ArenaConfig>) {
+    arena_config.bottom_wall = BOTTOM_WALL;
+}
+
+// Adds or removes the gutters when the setting changes, and keeps them on the floor as it rises.
+// Not part of Simulation, so a change made on the settings screen shows up while paused.
+fn sync_gutters(
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    arena_config: Res<ArenaConfig>,
+    mut gutter_query: Query<(Entity, &mut LineCollider, &mut Transform), With<Gutter>>,
+){
+    if !settings.is_changed() && !arena_config.is_changed() {
+        return;
+    }
+    if !settings.gutters {
+        for (entity, _, _) in gutter_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    if gutter_query.is_empty() {
+        for (start, end) in gutter_segments(arena_config.bottom_wall) {
+            commands.spawn((LineColliderBundle::new(start, end), Gutter));
+        }
+        return;
+    }
+    // the two are alike, so either entity can take either side
+    for ((_, mut collider, mut transform), (start, end)) in gutter_query.iter_mut().zip(gutter_segments(arena_config.bottom_wall)) {
+        *collider = LineCollider { start, end };
+        *transform = LineColliderBundle::transform(start, end);
+    }
+}
+
+fn move_floor_wall(
+    arena_config: Res<ArenaConfig>,
+    mut wall_query: Query<&mut Transform, With<FloorWall>>,
+){
+    if !arena_config.is_changed() {
+        return;
+    }
+    for mut transform in wall_query.iter_mut() {
+        transform.translation.y = arena_config.bottom_wall;
+    }
+}
+
+// True once a dropped fruit touches the floor or any other fruit
+fn has_landed<'a>(fruit: &Fruit, others: impl Iterator<Item = &'a Fruit>, floor: f32) -> bool {
+    if fruit.pos.y - fruit.radius <= floor + LANDING_SLOP {
+        return true;
+    }
+    return others
+        .filter(|other| other.id != fruit.id)
+        .any(|other| (other.pos - fruit.pos).length() < other.radius + fruit.radius + LANDING_SLOP);
+}
+
+// The perfect drop window starts when the fruit lands, so a drop from high up isn't penalized
+// for its fall
+fn age_drops(
+    time_step: Res<FixedTime>,
+    settings: Res<GameSettings>,
+    arena_config: Res<ArenaConfig>,
+    mut commands: Commands,
+    mut drop_query: Query<(Entity, &Fruit, &mut DropAge)>,
+    fruit_query: Query<&Fruit>,
+){
+    let floor: f32 = arena_config.floor();
+    for (entity, fruit, mut age) in drop_query.iter_mut() {
+        age.0 = match age.0 {
+            Some(seconds) => Some(seconds + time_step.period.as_secs_f32()),
+            None if has_landed(fruit, fruit_query.iter(), floor) => Some(0.0),
+            None => None,
+        };
+        if age.0.is_some_and(|seconds| seconds >= settings.perfect_drop_window) {
+            commands.entity(entity).remove::<DropAge>();
+        }
+    }
+}
+
+fn cool_merges(
+    time_step: Res<FixedTime>,
+    mut commands: Commands,
+    mut cooldown_query: Query<(Entity, &mut MergeCooldown)>,
+){
+    for (entity, mut cooldown) in cooldown_query.iter_mut() {
+        cooldown.0 -= time_step.period.as_secs_f32();
+        if cooldown.0 <= 0.0 {
+            commands.entity(entity).remove::<MergeCooldown>();
+        }
+    }
+}
+
+fn update_combo(
+    time_step: Res<FixedTime>,
+    mut combo: ResMut<ComboTracker>,
+    mut merged_events: EventReader<FruitMerged>,
+){
+    combo.since_last += time_step.period.as_secs_f32();
+    if combo.since_last > COMBO_WINDOW {
+        combo.count = 0;
+        combo.points = 0;
+    }
+    for merged in merged_events.iter() {
+        combo.count += 1;
+        combo.points += merged.score;
+        combo.since_last = 0.0;
+    }
+}
+
+// Gravity is accumulated as an acceleration in px/s^2 and only turned into a displacement by
+// physics_update, which owns the timestep, so it needs no dt here
+fn apply_gravity(
+    physics_config: Res<PhysicsConfig>,
+    fruit_table: Res<FruitTable>,
+    mut fruit_query: Query<&mut Fruit>,    
+){
+    let mut fruits: Vec<_> = fruit_query.iter_mut().collect();
+    for i in 0..fruits.len() {
+        fruits[i].acc.y -= physics_config.gravity_for_radius(fruits[i].radius, &fruit_table);
+    }
+}
+
+// Pulls each pair of same-group fruits within magnet_range of touching toward each other. The
+// pull fades to nothing at the edge of the range and is a small fraction of gravity at contact,
+// so it only tips a near miss into a merge and never lifts a fruit off the pile.
+fn apply_merge_magnet(
+    time_step: Res<FixedTime>,
+    physics_config: Res<PhysicsConfig>,
+    fruit_table: Res<FruitTable>,
+    mut fruit_query: Query<&mut Fruit, Without<Bomb>>,
+){
+    let dt = time_step.period.as_secs_f32();
+    if physics_config.magnet_range <= 0.0 {
+        return;
+    }
+    let pull: f32 = physics_config.gravity * MAGNET_GRAVITY_FRACTION;
+    let mut pairs = fruit_query.iter_combinations_mut();
+    while let Some([mut a, mut b]) = pairs.fetch_next() {
+        // the top tier has nothing to merge into
+        if a.group != b.group || fruit_table.is_top(a.group) {
+            continue;
+        }
+        let offset: Vec2 = b.pos - a.pos;
+        let distance: f32 = offset.length();
+        let gap: f32 = distance - a.radius - b.radius;
+        if gap <= 0.0 || gap >= physics_config.magnet_range || distance < f32::EPSILON {
+            continue;
+        }
+        let falloff: f32 = 1.0 - gap / physics_config.magnet_range;
+        let direction: Vec2 = offset / distance;
+        a.inc_vel(dt, direction * pull * falloff * dt);
+        b.inc_vel(dt, -direction * pull * falloff * dt);
+    }
+}
+
+// Working copy of a fruit while merges for one step are resolved
+struct MergeCandidate {
+    entity: Option<Entity>, // None for fruits produced by a merge during this step
+    id: u32,
+    group: u8,
+    pos: Vec2,
+    vel: Vec2,
+    radius: f32,
+    consumed: bool,
+    fresh: bool, // dropped and still falling, or landed within the perfect drop window
+    cooling: bool, // made by a merge too recently to merge again
+}
+
+// Broad phase for apply_merges: the pairs (i, j), i < j and j >= new_start, of same-group fruits
+// close enough that they might touch, in nested loop order. Each group gets its own grid, sized
+// to its fruits, so a pile of one size never has to look at another.
+fn merge_pairs(candidates: &[MergeCandidate], new_start: usize) -> Vec<(usize, usize)> {
+    let mut groups: HashMap<u8, Vec<usize>> = HashMap::new();
+    for (index, candidate) in candidates.iter().enumerate() {
+        groups.entry(candidate.group).or_default().push(index);
+    }
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for members in groups.values() {
+        let grid: SpatialGrid = SpatialGrid::new(members.iter().map(|index| (candidates[*index].pos, candidates[*index].radius)));
+        // members are in candidate order, so the new ones are at the end
+        let from: usize = members.partition_point(|index| *index < new_start);
+        pairs.extend(grid.pairs(from).into_iter().map(|(i, j)| (members[i], members[j])));
+    }
+    pairs.sort_unstable();
+    return pairs;
+}
+
+fn apply_merges(
+    time_step: Res<FixedTime>,
+    fruit_query: Query<(Entity, &Fruit, Option<&DropAge>, Option<&MergeCooldown>), Without<Bomb>>,
+    mut iterator_query: Query<(&mut Transform, &mut FruitIterator), With<Player>>,
+    mut commands: Commands,
+    fruit_assets: Res<FruitAssets>,
+    fruit_table: Res<FruitTable>,
+    physics_config: Res<PhysicsConfig>,
+    settings: Res<GameSettings>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut biggest_fruit: ResMut<BiggestFruit>,
+    mut counters: ResMut<PhysicsCounters>,
+    mut merged_events: EventWriter<FruitMerged>,
+){
+    let dt = time_step.period.as_secs_f32();
+    let (_, mut fruit_iterator) = iterator_query.single_mut();
+
+    let mut candidates: Vec<MergeCandidate> = fruit_query
+        .iter()
+        .map(|(entity, fruit, drop_age, cooldown)| MergeCandidate {
+            entity: Some(entity),
+            id: fruit.id,
+            group: fruit.group,
+            pos: fruit.pos,
+            vel: fruit.get_vel(dt),
+            radius: fruit.radius,
+            consumed: false,
+            // still falling means it's landing on this merge
+            fresh: settings.perfect_drop_window > 0.0
+                && drop_age.is_some_and(|age| age.0.map_or(true, |seconds| seconds < settings.perfect_drop_window)),
+            cooling: cooldown.is_some(),
+        })
+        .collect();
+
+    let mut r_ij: Vec2;
+    let mut cm_ij: Vec2;
+    let mut vm_ij: Vec2;
+    let mut min_dist: f32;
+    let mut group: u8;
+    let mut merge_score: u32;
+
+    // A merged fruit can land on another fruit of its new group, so keep making passes until
+    // nothing merges. Each pass only tests pairs involving a fruit made in the previous pass, and
+    // is one level deeper in the cascade, multiplying the score of the merges it finds.
+    // Every merge removes a fruit and raises a group, so the depth cap is never really hit.
+    // With a merge cooldown the fruits made in a pass can't merge, so the cascade waits for a later step.
+    let max_cascade_depth: u32 = fruit_table.len() as u32;
+    let mut cascade_depth: u32 = 1;
+    let mut new_start: usize = 0;
+    loop {
+        let n: usize = candidates.len();
+        let mut merged_this_pass: bool = false;
+        for (i, j) in merge_pairs(&candidates, new_start) {
+            if candidates[i].consumed || candidates[j].consumed || candidates[i].cooling || candidates[j].cooling {
+                continue;
+            }
+            group = candidates[i].group;
+            if fruit_table.is_top(group) { // Cant combine two watermelons
+                continue;
+            }
+            r_ij = candidates[j].pos - candidates[i].pos;
+            min_dist = candidates[j].radius + candidates[i].radius;
+            if r_ij.length() < min_dist // if collision
+                && (candidates[j].vel - candidates[i].vel).length() < physics_config.merge_max_rel_speed {
+                candidates[i].consumed = true;
+                candidates[j].consumed = true;
+                merge_score = fruit_table.get(group).score * cascade_depth;
+                if candidates[i].fresh || candidates[j].fresh {
+                    merge_score += settings.perfect_drop_bonus;
+                }
+                scoreboard.score += merge_score;
+
+                cm_ij = (candidates[j].pos + candidates[i].pos) / 2.0; // center of mass
+                if physics_config.merge_spawn_bias > 0.0 {
+                    let lower: &MergeCandidate = if candidates[i].pos.y <= candidates[j].pos.y { &candidates[i] } else { &candidates[j] };
+                    let resting: Vec2 = lower.pos + Vec2::new(0.0, fruit_table.get(group+1).radius - lower.radius);
+                    cm_ij = cm_ij.lerp(resting, physics_config.merge_spawn_bias);
+                }
+                vm_ij = (candidates[j].vel + candidates[i].vel) / 2.0; // average velocity
+                merged_events.send(FruitMerged {
+                    group: group+1,
+                    pos: cm_ij,
+                    score: merge_score,
+                    cascade_depth,
+                });
+
+                candidates.push(MergeCandidate {
+                    entity: None,
+                    id: fruit_iterator.next_id,
+                    group: group+1,
+                    pos: cm_ij,
+                    vel: vm_ij,
+                    radius: fruit_table.get(group+1).radius,
+                    consumed: false,
+                    fresh: false,
+                    cooling: physics_config.merge_cooldown > 0.0,
+                });
+                fruit_iterator.next_id += 1;
+                biggest_fruit.record(group+1);
+                counters.merges = counters.merges.saturating_add(1);
+                merged_this_pass = true;
+            }
+        }
+        if !merged_this_pass || cascade_depth >= max_cascade_depth {
+            break;
+        }
+        new_start = n;
+        cascade_depth += 1;
+    }
+
+    for candidate in candidates.iter() {
+        match (candidate.entity, candidate.consumed) {
+            (Some(entity), true) => {
+                commands.entity(entity).despawn_recursive();
+            }
+            (None, false) => {
+                let mut merged = FruitBundle::new(
+                    candidate.id,
+                    candidate.group,
+                    candidate.pos,
+                    &fruit_table,
+                    fruit_assets.texture(candidate.group),
+                );
+                merged.fruit.set_vel(dt, candidate.vel);
+                let merged_entity: Entity = spawn_fruit_bundle(&mut commands, &fruit_assets, merged);
+                if physics_config.merge_cooldown > 0.0 {
+                    commands.entity(merged_entity).insert(MergeCooldown(physics_config.merge_cooldown));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// A bomb touching a fruit removes it and every other fruit of its group, scoring each as if it
+// had merged. Top-tier fruits never merge and score nothing, so they're worth the best merge
+// score in the table instead. Each bomb goes off at most once, so one step can't chain.
+fn detonate_bombs(
+    time_step: Res<FixedTime>,
+    mut bomb_query: Query<(Entity, &Fruit, &mut Bomb)>,
+    fruit_query: Query<(Entity, &Fruit), Without<Bomb>>,
+    mut commands: Commands,
+    fruit_table: Res<FruitTable>,
+    mut scoreboard: ResMut<Scoreboard>,
+){
+    let mut cleared: Vec<u8> = Vec::new(); // groups already removed this step by another bomb
+    for (bomb_entity, bomb, mut bomb_state) in bomb_query.iter_mut() {
+        let contact = fruit_query
+            .iter()
+            .filter(|(_, fruit)| (fruit.pos - bomb.pos).length() < fruit.radius + bomb.radius + BOMB_CONTACT_SLOP)
+            .min_by(|(_, a), (_, b)| (a.pos - bomb.pos).length().total_cmp(&(b.pos - bomb.pos).length()));
+        let Some((_, target)) = contact else {
+            bomb_state.fuse.tick(time_step.period);
+            if bomb_state.fuse.finished() {
+                commands.entity(bomb_entity).despawn_recursive();
+            }
+            continue;
+        };
+
+        let group: u8 = target.group;
+        commands.entity(bomb_entity).despawn_recursive();
+        if cleared.contains(&group) {
+            continue;
+        }
+        cleared.push(group);
+        let points: u32 = if fruit_table.is_top(group) {
+            fruit_table.defs.iter().map(|def| def.score).max().unwrap_or(0)
+        } else {
+            fruit_table.get(group).score
+        };
+        for (entity, fruit) in fruit_query.iter() {
+            if fruit.group == group {
+                commands.entity(entity).despawn_recursive();
+                scoreboard.score += points;
+            }
+        }
+    }
+}
+
+// Separation of two fruits as (normal from a to b, distance, contact distance); they overlap
+// when distance < contact distance. The distance is between the closest points of the two
+// cores, which for two circles is just the distance between centers. Overlapping cores give a
+// negative distance along the axis that separates them fastest.
+fn shape_separation(a: &Fruit, b: &Fruit) -> (Vec2, f32, f32) {
+    let offset: Vec2 = b.pos - a.pos;
+    let contact: f32 = a.rounding() + b.rounding();
+    // gap between the cores along each axis, negative where they overlap
+    let gap: Vec2 = offset.abs() - (a.core_half_size() + b.core_half_size());
+    if gap.x > 0.0 || gap.y > 0.0 {
+        let closest: Vec2 = Vec2::new(gap.x.max(0.0) * offset.x.signum(), gap.y.max(0.0) * offset.y.signum());
+        let dist: f32 = closest.length();
+        return (closest / dist, dist, contact);
+    }
+    if gap.x > gap.y {
+        return (Vec2::new(offset.x.signum(), 0.0), gap.x, contact);
+    }
+    return (Vec2::new(0.0, offset.y.signum()), gap.y, contact);
+}
+
+fn apply_collisions(
+    time_step: Res<FixedTime>,
+    physics_config: Res<PhysicsConfig>,
+    mut counters: ResMut<PhysicsCounters>,
+    mut resting_contacts: ResMut<RestingContacts>,
+    mut backward: Local<bool>,
+    mut debug_draw: ResMut<DebugDraw>,
+    mut fruit_query: Query<&mut Fruit>,
+){
+    let mut fruits: Vec<_> = fruit_query.iter_mut().collect();
+    let dt = time_step.period.as_secs_f32();
+
+    if debug_draw.enabled {
+        debug_draw.contacts.clear();
+    }
+    if fruits.len() < 2{
+        resting_contacts.0 = 0;
+        return;
+    }
+    let reverse: bool = physics_config.relaxation_alternate && *backward;
+    *backward = !*backward;
+    let contacts: Option<&mut Vec<(Vec2, Vec2)>> = if debug_draw.enabled { Some(&mut debug_draw.contacts) } else { None };
+    resting_contacts.0 = resolve_collisions(&mut fruits, physics_config.max_correction, reverse, dt, &mut counters.pairs_tested, contacts);
+}
+
+// One pass over every pair, pushing overlapping fruits apart. Kept free of the ECS so
+// benches/collision.rs can time it on a plain Vec. Returns how many of the touching pairs were
+// at rest against each other.
+fn resolve_collisions<F: DerefMut<Target = Fruit>>(
+    fruits: &mut [F],
+    max_correction: f32,
+    reverse: bool, // walk the pairs back to front
+    dt: f32,
+    pairs_tested: &mut u32, // one more for every pair checked, see PhysicsCounters
+    mut contacts: Option<&mut Vec<(Vec2, Vec2)>>, // (point, normal) of each contact, for DebugDraw
+) -> u32 {
+    let mut r_ij: Vec2 = Vec2::ZERO;
+    let mut r_ij_mag: f32 = 0.0;
+    let mut r_ij_hat: Vec2 = Vec2::ZERO;
+    let mut v_ij: Vec2 = Vec2::ZERO;
+    let mut min_dist: f32 = 0.0;
+    let mut size_sum: f32 = 0.0;
+    let mut ratio_i: f32 = 0.0;
+    let mut ratio_j: f32 = 0.0;
+    let mut delta: f32 = 0.0;
+
+    let mut resting: u32 = 0;
+    let n: usize = fruits.len();
+    if n < 2{
+        return resting;
+    }
+    let mut i: usize;
+    let mut j: usize;
+    for a in 0..(n-1) {
+        i = if reverse { n - 2 - a } else { a };
+        for b in (i+1)..n {
+            j = if reverse { n + i - b } else { b }; // i+1..n, back to front
+            // saturating: nothing resets the counters in a headless run
+            *pairs_tested = pairs_tested.saturating_add(1);
+            if fruits[i].shape == CollisionShape::Circle && fruits[j].shape == CollisionShape::Circle {
+                r_ij = fruits[j].pos - fruits[i].pos;
+                r_ij_mag = r_ij.length();
+                min_dist = fruits[j].radius + fruits[i].radius;
+                // exactly on top of each other has no direction, push j to the right rather than divide by zero
+                r_ij_hat = if r_ij_mag > 0.0 { r_ij / r_ij_mag } else { Vec2::X };
+            } else {
+                (r_ij_hat, r_ij_mag, min_dist) = shape_separation(&fruits[i], &fruits[j]);
+            }
+            if r_ij_mag < min_dist{ // if collision
+                v_ij = fruits[i].get_vel(dt) - fruits[j].get_vel(dt);
+                let impact: f32 = v_ij.dot(r_ij_hat); // closing speed, positive when approaching
+                let (id_i, id_j) = (fruits[i].id, fruits[j].id);
+                fruits[i].hit(impact, Some(id_j));
+                fruits[j].hit(impact, Some(id_i));
+                if v_ij.length() < RESTING_CONTACT_SPEED {
+                    resting += 1;
+                }
+                if let Some(contacts) = contacts.as_deref_mut() {
+                    // midway between the two surfaces, pointing from i to j
+                    let contact: Vec2 = fruits[i].pos + r_ij_hat * (fruits[i].radius - (min_dist - r_ij_mag) / 2.0);
+                    contacts.push((contact, r_ij_hat));
+                }
+                size_sum = fruits[j].radius + fruits[i].radius;
+                ratio_i = fruits[i].radius / size_sum;
+                ratio_j = fruits[j].radius / size_sum;
+                delta =  0.5 * POS_RESPONSE_CONST * (r_ij_mag - min_dist);
+                delta = delta.max(-max_correction);
+
+                fruits[i].pos += r_ij_hat * (ratio_j * delta);
+                fruits[j].pos -= r_ij_hat * (ratio_i * delta);
+                fruits[i].inc_vel(dt, r_ij_hat * VEL_RESPONSE_CONST *(ratio_j * delta) / dt);
+                fruits[j].inc_vel(dt, - r_ij_hat * VEL_RESPONSE_CONST *(ratio_i * delta) / dt);
+
+                // fruits[i].vel += r_ij_hat * (ratio_j * delta) / dt;
+                // fruits[j].vel -= r_ij_hat * (ratio_i * delta) / dt;
+
+                // fruits[i].a_acc -= ROT_FRICTION_CONST * ratio_j *((fruits[i].vel - fruits[j].vel).perp_dot(r_ij_hat) + fruits[i].a_vel*fruits[i].radius - fruits[j].a_vel*fruits[j].radius);
+                // fruits[j].a_acc += ROT_FRICTION_CONST * ratio_i *((fruits[i].vel - fruits[j].vel).perp_dot(r_ij_hat) + fruits[i].a_vel*fruits[i].radius - fruits[j].a_vel*fruits[j].radius);
+
+                // fruits[i].a_acc -= ROT_FRICTION_CONST * ratio_j *((fruits[i].vel - fruits[j].vel).perp_dot(r_ij_hat) + fruits[i].a_vel*fruits[i].radius - fruits[j].a_vel*fruits[j].radius);
+                // fruits[j].a_acc += ROT_FRICTION_CONST * ratio_i *((fruits[i].vel - fruits[j].vel).perp_dot(r_ij_hat) + fruits[i].a_vel*fruits[i].radius - fruits[j].a_vel*fruits[j].radius);
+
+                // println!("{:?}, {:?}", fruits[i].a_acc, fruits[j].a_acc);
+            }
+        }
+    }
+    return resting;
+}
+
+// Both collision shapes reach exactly `radius` along each axis, so the walls need no shape check
+fn apply_constraint(
+    time_step: Res<FixedTime>,
+    physics_config: Res<PhysicsConfig>,
+    arena_config: Res<ArenaConfig>,
+    soft_walls: Res<SoftWalls>,
+    mut fruit_query: Query<&mut Fruit>, 
+    mut landing_events: EventWriter<HardLanding>,
+){
+    let dt = time_step.period.as_secs_f32();
+    let floor: f32 = arena_config.floor();
+    let floor_speed: f32 = arena_config.floor_speed(physics_config.floor_rise);
+    let mut fruits: Vec<_> = fruit_query.iter_mut().collect();
+    let mut vel: Vec2;
+    let mut a_vel: f32;
+    for i in 0..fruits.len() {
+        if (fruits[i].pos.y - fruits[i].radius) < floor {
+            vel = fruits[i].get_vel(dt);
+            a_vel = fruits[i].get_a_vel(dt);
+            if -vel.y > HARD_LANDING_SPEED && fruits[i].radius >= HARD_LANDING_MIN_RADIUS {
+                landing_events.send(HardLanding {
+                    id: fruits[i].id,
+                    pos: fruits[i].pos,
+                    speed: -vel.y,
+                    radius: fruits[i].radius,
+                });
+            }
+
+            fruits[i].hit(-vel.y, None);
+            fruits[i].pos.y = floor + fruits[i].radius;
+            // bounced off the floor as it moves, so a rising floor carries fruits up rather than
+            // leaving them to sink back through it
+            let bounce: f32 = floor_speed - (vel.y - floor_speed) * physics_config.wall_bounce;
+            fruits[i].set_vel(dt, Vec2{x: vel.x * LINEAR_FRICTION_CONST, y: bounce});
+            // fruits[i].vel.y = -fruits[i].vel.y * WALL_BOUNCE_CONST;
+            // fruits[i].vel.x = fruits[i].vel.x * LINEAR_FRICTION_CONST;
+            // fruits[i].a_acc += LINEAR_FRICTION_CONST * (-vel.x - a_vel*fruits[i].radius);
+        }
+        if soft_walls.0 {
+            let left_depth: f32 = (LEFT_WALL + WALL_THICKNESS/2.0) - (fruits[i].pos.x - fruits[i].radius);
+            let right_depth: f32 = (fruits[i].pos.x + fruits[i].radius) - (RIGHT_WALL - WALL_THICKNESS/2.0);
+            if left_depth > 0.0 {
+                push_out_of_soft_wall(&mut fruits[i], 1.0, left_depth, dt);
+            }
+            if right_depth > 0.0 {
+                push_out_of_soft_wall(&mut fruits[i], -1.0, right_depth, dt);
+            }
+            continue;
+        }
+        if (fruits[i].pos.x - fruits[i].radius) < (LEFT_WALL + WALL_THICKNESS/2.0){
+            vel = fruits[i].get_vel(dt);
+            a_vel = fruits[i].get_a_vel(dt);
+
+            fruits[i].hit(-vel.x, None);
+            fruits[i].pos.x = LEFT_WALL + WALL_THICKNESS/2.0 + fruits[i].radius;
+            fruits[i].set_vel(dt, Vec2{x: -vel.x * physics_config.wall_bounce, y: vel.y * LINEAR_FRICTION_CONST});
+            // fruits[i].vel.x = -fruits[i].vel.x * WALL_BOUNCE_CONST;
+            // fruits[i].vel.y = fruits[i].vel.y * LINEAR_FRICTION_CONST;
+            // fruits[i].a_acc += LINEAR_FRICTION_CONST * (vel.y - a_vel*fruits[i].radius);
+        }
+        if (fruits[i].pos.x + fruits[i].radius) > (RIGHT_WALL - WALL_THICKNESS/2.0){
+            vel = fruits[i].get_vel(dt);
+            a_vel = fruits[i].get_a_vel(dt);
+
+            fruits[i].hit(vel.x, None);
+            fruits[i].pos.x = RIGHT_WALL - WALL_THICKNESS/2.0 - fruits[i].radius;
+            fruits[i].set_vel(dt, Vec2{x: -vel.x * physics_config.wall_bounce, y: vel.y * LINEAR_FRICTION_CONST});
+            // fruits[i].vel.x = -fruits[i].vel.x * WALL_BOUNCE_CONST;
+            // fruits[i].vel.y = fruits[i].vel.y * LINEAR_FRICTION_CONST;
+            // fruits[i].a_acc += LINEAR_FRICTION_CONST * (-vel.y - a_vel*fruits[i].radius);
+        }
+    }
+
+}
+
+// Springs a fruit back out of a soft wall, `normal` being the x direction out of it. Past
+// SOFT_WALL_MAX_DEPTH it's moved out like a hard wall would, keeping its velocity, so a heavy
+// pile can't push a fruit through.
+fn push_out_of_soft_wall(fruit: &mut Fruit, normal: f32, depth: f32, dt: f32) {
+    let into_wall: f32 = (-fruit.get_vel(dt).x * normal).max(0.0);
+    fruit.hit(into_wall, None);
+    let push: f32 = SOFT_WALL_STIFFNESS * depth + SOFT_WALL_DAMPING * into_wall;
+    fruit.inc_vel(dt, Vec2::new(normal * push * dt, 0.0));
+    if depth > SOFT_WALL_MAX_DEPTH {
+        let correction: f32 = normal * (depth - SOFT_WALL_MAX_DEPTH);
+        fruit.pos.x += correction;
+        fruit.pos_last.x += correction;
+    }
+}
+
+// A hard landing gives resting fruits nearby a small downward kick, which collisions and the
+// floor then settle. Moving fruits are left alone so falling stacks aren't thrown about.
+fn nudge_landing_neighbors(
+    time_step: Res<FixedTime>,
+    mut landing_events: EventReader<HardLanding>,
+    mut fruit_query: Query<&mut Fruit>,
+){
+    let dt = time_step.period.as_secs_f32();
+    for landing in landing_events.iter() {
+        for mut fruit in fruit_query.iter_mut() {
+            if fruit.id == landing.id || fruit.get_vel(dt).length() > LANDING_NUDGE_SPEED {
+                continue;
+            }
+            let gap: f32 = ((fruit.pos - landing.pos).length() - fruit.radius - landing.radius).max(0.0);
+            if gap < LANDING_NUDGE_RANGE {
+                let falloff: f32 = 1.0 - gap / LANDING_NUDGE_RANGE;
+                fruit.inc_vel(dt, Vec2::new(0.0, -LANDING_NUDGE_KICK * falloff));
+            }
+        }
+    }
+}
+
+// Debug builds only. A NaN or infinite position makes a fruit vanish without any other sign,
+// so report each broken fruit once, as soon as a step produces it.
+#[cfg(debug_assertions)]
+fn check_finite_positions(
+    fruit_query: Query<&Fruit>,
+    mut reported: Local<Vec<u32>>,
+){
+    for fruit in fruit_query.iter() {
+        if (fruit.pos.is_finite() && fruit.pos_last.is_finite()) || reported.contains(&fruit.id) {
+            continue;
+        }
+        error!("Fruit {} (group {}) has a non-finite position {:?}, last {:?}", fruit.id, fruit.group, fruit.pos, fruit.pos_last);
+        reported.push(fruit.id);
+    }
+}
+
+// Debug builds only. A fruit despawned without despawn_recursive leaves its parts behind, still
+// drawn where the fruit last was, so report each one left without a fruit to belong to.
+#[cfg(debug_assertions)]
+fn check_orphaned_parts(
+    part_query: Query<(Entity, Option<&Parent>), With<FruitPart>>,
+    fruit_query: Query<(), With<Fruit>>,
+    mut reported: Local<Vec<Entity>>,
+){
+    for (part, parent) in part_query.iter() {
+        let orphaned: bool = parent.map_or(true, |parent| !fruit_query.contains(parent.get()));
+        if !orphaned || reported.contains(&part) {
+            continue;
+        }
+        error!("Fruit part {:?} was left behind by a despawned fruit", part);
+        reported.push(part);
+    }
+}
+
+// Pushes fruits out of any LineCollider and reflects the normal velocity like the walls do.
+// Slow normal impacts are absorbed instead of bounced so fruits settle on a slope and roll
+// along it rather than jittering.
+fn apply_line_colliders(
+    time_step: Res<FixedTime>,
+    physics_config: Res<PhysicsConfig>,
+    collider_query: Query<&LineCollider>,
+    mut fruit_query: Query<&mut Fruit>,
+){
+    let dt = time_step.period.as_secs_f32();
+    let colliders: Vec<&LineCollider> = collider_query.iter().collect();
+    if colliders.is_empty() {
+        return;
+    }
+
+    let mut offset: Vec2;
+    let mut dist: f32;
+    let mut min_dist: f32;
+    let mut normal: Vec2;
+    let mut vel: Vec2;
+    let mut vel_n: f32;
+    let mut vel_t: Vec2;
+    for mut fruit in fruit_query.iter_mut() {
+        for collider in colliders.iter() {
+            let closest: Vec2 = collider.closest_point(fruit.pos);
+            offset = fruit.pos - closest;
+            dist = offset.length();
+            min_dist = fruit.radius + WALL_THICKNESS/2.0;
+            if dist < min_dist && dist > 0.0 {
+                normal = offset / dist;
+                vel = fruit.get_vel(dt);
+                vel_n = vel.dot(normal);
+                vel_t = vel - normal * vel_n;
+                if vel_n < 0.0 {
+                    vel_n = if -vel_n > LINE_REST_SPEED { -vel_n * physics_config.wall_bounce } else { 0.0 };
+                }
+
+                fruit.pos = closest + normal * min_dist;
+                fruit.set_vel(dt, vel_t * LINEAR_FRICTION_CONST + normal * vel_n);
+            }
+        }
+    }
+}
+
+// Rolling. A fruit resting off-center on another one rolls about the contact point: for a solid
+// disk that's an angular acceleration of 2/3 * g * sin(theta) / r, theta being how far the
+// contact normal leans from vertical. Only contacts that hold the fruit up count, a fruit
+// pressed against another from the side isn't resting on it.
+// Where a fruit touches the floor or the fruit under it, grip trades slip between the spin and
+// the slide the way friction would on a solid disk, a third of it from the slide and two thirds
+// from the spin, so a spinning fruit sets off rolling and a sliding one starts to turn.
+fn apply_torque(
+    time_step: Res<FixedTime>,
+    physics_config: Res<PhysicsConfig>,
+    fruit_table: Res<FruitTable>,
+    arena_config: Res<ArenaConfig>,
+    mut fruit_query: Query<&mut Fruit>,
+){
+    let dt = time_step.period.as_secs_f32();
+    let mut fruits: Vec<_> = fruit_query.iter_mut().collect();
+    roll_fruits(&mut fruits, dt, &physics_config, &fruit_table, arena_config.floor());
+}
+
+// The body of apply_torque, on any slice of fruits so tests can run it on a plain Vec
+fn roll_fruits<F: DerefMut<Target = Fruit>>(
+    fruits: &mut [F],
+    dt: f32,
+    physics_config: &PhysicsConfig,
+    fruit_table: &FruitTable,
+    floor: f32,
+){
+    let mut r_ij: Vec2;
+    let mut normal: Vec2;
+    let mut upper: usize;
+    let mut lower: usize;
+    let mut a_vel: f32;
+
+    for i in 0..fruits.len() {
+        for j in (i+1)..fruits.len() {
+            if fruits[i].shape != CollisionShape::Circle || fruits[j].shape != CollisionShape::Circle {
+                continue; // boxes stack flat
+            }
+            r_ij = fruits[j].pos - fruits[i].pos;
+            if r_ij.length() > fruits[i].radius + fruits[j].radius + ROLL_CONTACT_SLOP {
+                continue;
+            }
+            // the fruit on top rolls, normal points from the supporting fruit up to it
+            (upper, lower, normal) = if r_ij.y > 0.0 { (j, i, r_ij.normalize()) } else { (i, j, -r_ij.normalize()) };
+            if normal.y < ROLL_MIN_SUPPORT {
+                continue;
+            }
+            let gravity: f32 = physics_config.gravity_for_radius(fruits[upper].radius, fruit_table);
+            let radius: f32 = fruits[upper].radius;
+            fruits[upper].a_acc -= 2.0 / 3.0 * gravity * normal.x / radius;
+            let ground: Vec2 = fruits[lower].get_vel(dt);
+            grip(&mut *fruits[upper], normal, ground, dt);
+        }
+    }
+
+    for fruit in fruits.iter_mut() {
+        if (fruit.pos.y - fruit.radius) <= (floor + ROLL_CONTACT_SLOP) {
+            grip(&mut **fruit, Vec2::Y, Vec2::ZERO, dt);
+        }
+        a_vel = fruit.get_a_vel(dt);
+        if a_vel.abs() > MAX_A_VEL {
+            fruit.set_a_vel(dt, a_vel.signum() * MAX_A_VEL);
+        }
+    }
+}
+
+// Closes ROLL_GRIP of the slip between fruit and a surface with the given normal moving at
+// ground. Slip is zero when the fruit rolls without sliding, along the floor that's
+// vel.x == -a_vel * radius.
+fn grip(fruit: &mut Fruit, normal: Vec2, ground: Vec2, dt: f32){
+    let tangent: Vec2 = Vec2::new(normal.y, -normal.x);
+    let vel: Vec2 = fruit.get_vel(dt);
+    let a_vel: f32 = fruit.get_a_vel(dt);
+    let slip: f32 = (vel - ground).dot(tangent) + a_vel * fruit.radius;
+    let closed: f32 = slip * ROLL_GRIP;
+    fruit.set_vel(dt, vel - tangent * closed / 3.0);
+    fruit.set_a_vel(dt, a_vel - closed * 2.0 / 3.0 / fruit.radius);
+}
+
+// Turns this step's impacts into crack damage and CollisionEvents. Never touches the physics
+// state besides clearing the impact.
+fn record_damage(
+    mut fruit_query: Query<(&mut Fruit, Option<&mut Damage>)>,
+    mut collision_events: EventWriter<CollisionEvent>,
+){
+    let impacts: HashMap<u32, Option<u32>> = fruit_query
+        .iter()
+        .map(|(fruit, _)| (fruit.id, fruit.impact_with))
+        .collect();
+    for (mut fruit, damage) in fruit_query.iter_mut() {
+        // the pair's other fruit sends it when that was its fastest impact too and its id is lower
+        let heard_from_other: bool = fruit.impact_with.is_some_and(|other| {
+            other < fruit.id && impacts.get(&other) == Some(&Some(fruit.id))
+        });
+        if fruit.impact >= COLLISION_EVENT_MIN_SPEED && !heard_from_other {
+            collision_events.send(CollisionEvent { speed: fruit.impact });
+        }
+        if let Some(mut damage) = damage {
+            let crack: f32 = (fruit.impact - CRACK_IMPACT_SPEED) / (CRACK_FULL_SPEED - CRACK_IMPACT_SPEED);
+            if crack > damage.amount {
+                damage.amount = crack.min(1.0);
+            }
+        }
+        fruit.impact = 0.0;
+        fruit.impact_with = None;
+    }
+}
+
+// Verlet Integration
+// x(t+dt) = x(t) + (x(t) - x(t-dt)) + a*dt^2, where a is the acceleration accumulated in
+// acc/a_acc this step. This is the only place accelerations are scaled by the timestep.
+fn integrate(body: &mut Kinematics, dt: f32, physics_config: &PhysicsConfig) {
+    let mut vel: Vec2 = (body.pos - body.pos_last) / dt.max(f32::EPSILON);
+    if vel.length() >= MAX_VEL{
+        body.pos_last = body.pos - (vel.normalize() * MAX_VEL * dt);
+        vel = vel.normalize() * MAX_VEL;
+    }
+    if vel.y < -physics_config.max_fall_speed {
+        body.pos_last.y = body.pos.y + physics_config.max_fall_speed * dt;
+    }
+
+    let displacement: Vec2 = (body.pos - body.pos_last) * (1.0 - physics_config.air_damping);
+    let a_displacement: f32 = (body.a_pos - body.a_pos_last) * (1.0 - physics_config.angular_damping);
+
+    body.pos_last = body.pos;
+    body.a_pos_last = body.a_pos;
+
+    body.pos = body.pos + displacement + body.acc * dt * dt;
+    // a locked fruit drops whatever spin it was given, so it never starts turning
+    if !body.spin_locked {
+        body.a_pos = body.a_pos + a_displacement + body.a_acc * dt * dt;
+    }
+
+    body.acc = Vec2::ZERO;
+    body.a_acc = 0.0;
+}
+
+// Copies every fruit's Kinematics out into one packed buffer, integrates that, and copies the
+// result back, so the integration loop itself never touches the rest of Fruit
+fn physics_update(
+    time_step: Res<FixedTime>,
+    physics_config: Res<PhysicsConfig>,
+    mut bodies: Local<Vec<Kinematics>>, // kept between steps so it's only allocated once
+    mut fruit_query: Query<&mut Fruit>, 
+){
+    let dt = time_step.period.as_secs_f32();
+    bodies.clear();
+    bodies.extend(fruit_query.iter().map(|fruit| fruit.kinematics()));
+    for body in bodies.iter_mut() {
+        integrate(body, dt, &physics_config);
+    }
+    // nothing is spawned or despawned in between, so both passes see the fruits in the same order
+    for (mut fruit, body) in fruit_query.iter_mut().zip(bodies.iter()) {
+        fruit.set_kinematics(body);
+    }
+}
+
+// Euler Integration
+// fn physics_update(
+//     time_step: Res<FixedTime>,
+//     mut fruit_query: Query<&mut Fruit>, 
+// ){
+//     let dt = time_step.period.as_secs_f32();
+//     for mut fruit_i in fruit_query.iter_mut(){
+//         fruit_i.vel.x += dt * fruit_i.acc.x;
+//         fruit_i.vel.y += dt * fruit_i.acc.y;
+//         fruit_i.a_vel += dt * fruit_i.a_acc;
+//         fruit_i.pos.x += dt * fruit_i.vel.x;
+//         fruit_i.pos.y += dt * fruit_i.vel.y;
+//         fruit_i.a_pos += dt * fruit_i.a_vel;
+
+//         fruit_i.acc.x = 0.0;
+//         fruit_i.acc.y = 0.0;
+//         fruit_i.a_acc = 0.0;
+//     }
+
+// }
+
+fn update_sprites(
+    time_step: Res<FixedTime>,
+    interpolation: Res<RenderInterpolation>,
+    step_mode: Res<StepMode>,
+    mut query: Query<(&mut Transform, &Fruit)>,
+){
+    // 0.0 right after a step, approaching 1.0 just before the next. The accumulator keeps
+    // filling while the simulation is paused, so a frozen board is drawn where it stopped.
+    let alpha: f32 = if interpolation.enabled && !step_mode.paused {
+        (time_step.accumulated().as_secs_f32() / time_step.period.as_secs_f32()).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    for (mut transform, fruit) in query.iter_mut(){
+        let pos: Vec2 = fruit.pos_last.lerp(fruit.pos, alpha);
+        transform.translation.x = pos.x;
+        transform.translation.y = pos.y;
+        transform.rotation = Quat::from_rotation_z(fruit.a_pos_last + (fruit.a_pos - fruit.a_pos_last) * alpha);
+    }
+}
+
+// Height the bottom of a fruit of `radius` dropped straight down at `x` would come to rest at:
+// on top of the highest fruit it would hit, or on the floor
+fn landing_height<'a>(fruits: impl Iterator<Item = &'a Fruit>, floor: f32, x: f32, radius: f32) -> f32 {
+    let mut landing: f32 = floor;
+    for fruit in fruits {
+        let reach: f32 = fruit.radius + radius;
+        let dx: f32 = fruit.pos.x - x;
+        if dx.abs() < reach {
+            // center height where the two circles would just touch
+            let center: f32 = fruit.pos.y + (reach*reach - dx*dx).sqrt();
+            landing = landing.max(center - radius);
+        }
+    }
+    return landing;
+}
+
+// A faint ellipse where the held fruit would land, redrawn every frame as the player moves
+fn draw_drop_shadow(
+    mut gizmos: Gizmos,
+    fruit_table: Res<FruitTable>,
+    arena_config: Res<ArenaConfig>,
+    player_query: Query<(&Transform, &FruitIterator, &Sprite), With<Player>>,
+    fruit_query: Query<&Fruit>,
+){
+    let (player_transform, fruit_iterator, sprite) = player_query.single();
+    if sprite.color.a() == 0.0 { // nothing held during the spawn cooldown
+        return;
+    }
+    let radius: f32 = fruit_table.get(fruit_iterator.next_group).radius;
+    let x: f32 = player_transform.translation.x;
+    let y: f32 = landing_height(fruit_query.iter(), arena_config.floor(), x, radius);
+    let half_size: Vec2 = Vec2::new(radius * DROP_SHADOW_WIDTH / 2.0, DROP_SHADOW_HEIGHT / 2.0);
+    gizmos.linestrip_2d(
+        (0..=DROP_SHADOW_SEGMENTS).map(|i| {
+            let angle: f32 = TAU * i as f32 / DROP_SHADOW_SEGMENTS as f32;
+            Vec2::new(x, y) + half_size * Vec2::new(angle.cos(), angle.sin())
+        }),
+        DROP_SHADOW_COLOR,
+    );
+}
+
+fn update_cracks(
+    time: Res<Time>,
+    mut fruit_query: Query<(&mut Damage, &Children)>,
+    mut crack_query: Query<&mut Sprite, With<CrackDecal>>,
+){
+    for (mut damage, children) in fruit_query.iter_mut() {
+        if damage.amount <= 0.0 {
+            continue;
+        }
+        damage.amount = (damage.amount - CRACK_DECAY * time.delta_seconds()).max(0.0);
+        for child in children.iter() {
+            if let Ok(mut sprite) = crack_query.get_mut(*child) {
+                sprite.color.set_a(damage.amount);
+            }
+        }
+    }
+}
+
+fn update_outlines(
+    outlines: Res<FruitOutlines>,
+    mut outline_query: Query<(&mut Visibility, Ref<Outline>)>,
+){
+    let visibility: Visibility = if outlines.enabled { Visibility::Inherited } else { Visibility::Hidden };
+    for (mut outline_visibility, outline) in outline_query.iter_mut() {
+        if outlines.is_changed() || outline.is_added() {
+            *outline_visibility = visibility;
+        }
+    }
+}
+
+// Settled above the danger line, rather than still dropping in
+fn over_danger_line(fruit: &Fruit, arena_config: &ArenaConfig, dt: f32) -> bool {
+    return (fruit.pos.y + fruit.radius) > arena_config.danger_line_y && fruit.get_vel(dt).y > -DANGER_FALL_SPEED;
+}
+
+// Turns the danger line red and pulses it while any fruit that has finished dropping in
+// pokes above it
+fn update_danger_line(
+    time: Res<Time>,
+    time_step: Res<FixedTime>,
+    arena_config: Res<ArenaConfig>,
+    fruit_query: Query<&Fruit>,
+    mut line_query: Query<(&mut Sprite, &mut Transform), With<DangerLine>>,
+){
+    let dt = time_step.period.as_secs_f32();
+    let (mut sprite, mut transform) = line_query.single_mut();
+    transform.translation.y = arena_config.danger_line_y;
+
+    let danger: bool = fruit_query.iter().any(|fruit| over_danger_line(fruit, &arena_config, dt));
+
+    if danger {
+        let pulse: f32 = 0.5 + 0.5 * (time.elapsed_seconds() * DANGER_PULSE_RATE * TAU).sin();
+        sprite.color = Color::rgba(1.0, 0.0, 0.0, 0.4 + 0.6 * pulse);
+    } else {
+        sprite.color = DANGER_LINE_COLOR;
+    }
+}
+
+fn update_scoreboard(
+    scoreboard: Res<Scoreboard>,
+    localization: Res<Localization>,
+     mut query: Query<&mut Text, With<ScoreboardText>>
+) {
+    let mut text = query.single_mut();
+    if localization.is_changed() {
+        text.sections[0].value = localization.get("score").to_string();
+    }
+    text.sections[1].value = scoreboard.score.to_string();
+}
+
+fn update_biggest_fruit_text(
+    biggest_fruit: Res<BiggestFruit>,
+    localization: Res<Localization>,
+    mut query: Query<&mut Text, With<BiggestFruitText>>,
+){
+    if !biggest_fruit.is_changed() && !localization.is_changed() {
+        return;
+    }
+    let mut text = query.single_mut();
+    text.sections[0].value = localization.get("biggest").to_string();
+    text.sections[1].value = match biggest_fruit.group {
+        Some(group) => localization.format("tier", &[&(group + 1)]),
+        None => "-".to_string(),
+    };
+}
+
+fn stress_test_active(stress_test: Res<StressTest>) -> bool {
+    return stress_test.active;
+}
+
+fn toggle_stress_test(
+    input: Res<Input<KeyCode>>,
+    mut stress_test: ResMut<StressTest>,
+) {
+    if input.just_pressed(STRESS_TEST_KEY) {
+        stress_test.active = !stress_test.active;
+    }
+}
+
+// Anywhere a fruit of the given radius fits between the side walls. One wider than the arena
+// goes in the middle, rather than asking gen_range for an empty range.
+fn random_rain_x(rng: &mut GameRng, radius: f32) -> f32 {
+    let center: f32 = (LEFT_WALL + RIGHT_WALL) / 2.0;
+    let reach: f32 = (RIGHT_WALL - LEFT_WALL - WALL_THICKNESS) / 2.0 - radius;
+    if reach <= 0.0 {
+        return center;
+    }
+    return center + rng.0.gen_range(-reach..reach);
+}
+
+fn rain_fruits(
+    time_step: Res<FixedTime>,
+    mut stress_test: ResMut<StressTest>,
+    mut rng: ResMut<GameRng>,
+    fruit_assets: Res<FruitAssets>,
+    fruit_table: Res<FruitTable>,
+    arena_config: Res<ArenaConfig>,
+    mut iterator_query: Query<&mut FruitIterator>,
+    mut commands: Commands,
+) {
+    stress_test.timer.tick(time_step.period);
+    let mut fruit_iterator = iterator_query.single_mut();
+    for _ in 0..stress_test.timer.times_finished_this_tick() {
+        let group: u8 = fruit_table.random_spawn_group(&mut rng);
+        let x: f32 = random_rain_x(&mut rng, fruit_table.get(group).radius);
+        spawn_fruit_bundle(&mut commands, &fruit_assets, FruitBundle::new(
+            fruit_iterator.next_id,
+            group,
+            Vec2::new(x, arena_config.top_wall),
+            &fruit_table,
+            fruit_assets.texture(group),
+        ));
+        fruit_iterator.next_id += 1;
+    }
+}
+
+// FPS, fruit count and drop rate, shown only while the stress test is running
+fn update_stress_overlay(
+    stress_test: Res<StressTest>,
+    diagnostics: Res<DiagnosticsStore>,
+    drop_rate: Res<DropRate>,
+    mut overlay_query: Query<(&mut Text, &mut Visibility), With<StressOverlay>>,
+) {
+    let (mut text, mut visibility) = overlay_query.single_mut();
+    if !stress_test.active {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+    let smoothed = |id: DiagnosticId| -> f64 {
+        return diagnostics.get(id).and_then(|diagnostic| diagnostic.smoothed()).unwrap_or(0.0);
+    };
+    text.sections[0].value = format!(
+        "FPS: {:.0}  Fruits: {}\nPairs: {:.0}  Merges/s: {:.1}\nDrops/min: {:.0}",
+        smoothed(FrameTimeDiagnosticsPlugin::FPS),
+        smoothed(profiling::ACTIVE_FRUITS),
+        smoothed(profiling::COLLISION_PAIRS),
+        smoothed(profiling::MERGES_PER_SECOND),
+        drop_rate.per_minute,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // integrate for a single Fruit, copied out and back the way physics_update does it
+    fn integrate_fruit(fruit: &mut Fruit, dt: f32, physics_config: &PhysicsConfig) {
+        let mut body: Kinematics = fruit.kinematics();
+        integrate(&mut body, dt, physics_config);
+        fruit.set_kinematics(&body);
+    }
+
+    // A default-table fruit at rest at pos
+    fn fruit(id: u32, group: u8, pos: Vec2) -> Fruit {
+        return FruitBundle::new(id, group, pos, &FruitTable::default(), Handle::default()).fruit;
+    }
+
+    // count tiers, growing by 10 px each, named and colored like the default chain
+    fn tiers(count: usize) -> Vec<FruitDef> {
+        return (0..count)
+            .map(|index| FruitDef { radius: 10.0 * (index + 1) as f32, ..DEFAULT_FRUITS[index % DEFAULT_FRUITS.len()].clone() })
+            .collect();
+    }
+
+    #[test]
+    fn fruit_tables_of_any_length_scale_their_spawnable_range() {
+        let mut rng: GameRng = GameRng(StdRng::seed_from_u64(1));
+        for (count, spawnable) in [(7, 3), (11, DEFAULT_SPAWNABLE), (15, 7)] {
+            let table: FruitTable = FruitTable::new(tiers(count));
+            assert_eq!(table.spawnable, spawnable);
+            assert!(table.is_top(count as u8 - 1) && !table.is_top(count as u8 - 2));
+            assert_eq!(table.max_radius(), 10.0 * count as f32);
+            assert!((0..100).all(|_| (table.random_spawn_group(&mut rng) as usize) < spawnable));
+        }
+    }
+
+    #[test]
+    fn rain_keeps_fruits_between_the_walls() {
+        let mut rng: GameRng = GameRng(StdRng::seed_from_u64(1));
+        for _ in 0..100 {
+            let x: f32 = random_rain_x(&mut rng, 50.0);
+            assert!(x - 50.0 >= LEFT_WALL && x + 50.0 <= RIGHT_WALL);
+        }
+        assert_eq!(random_rain_x(&mut rng, RIGHT_WALL - LEFT_WALL), (LEFT_WALL + RIGHT_WALL) / 2.0);
+    }
+
+    // Gap between the centers of two cherries 10 px apart after one collision pass
+    fn gap_after_collision(max_correction: f32) -> f32 {
+        let mut fruits: Vec<Fruit> = vec![fruit(0, 0, Vec2::ZERO), fruit(1, 0, Vec2::new(10.0, 0.0))];
+        {
+            let mut refs: Vec<&mut Fruit> = fruits.iter_mut().collect();
+            resolve_collisions(&mut refs, max_correction, false, 0.01, &mut 0, None);
+        }
+        return fruits[1].pos.x - fruits[0].pos.x;
+    }
+
+    #[test]
+    fn only_fruits_resting_off_center_on_another_roll() {
+        let physics_config: PhysicsConfig = PhysicsConfig::default();
+        let fruit_table: FruitTable = FruitTable::default();
+        // a cherry balanced just right of the top of another, and two more side by side
+        let mut fruits: Vec<Fruit> = vec![
+            fruit(0, 0, Vec2::ZERO),
+            fruit(1, 0, Vec2::new(5.0, 40.0)),
+            fruit(2, 0, Vec2::new(200.0, 0.0)),
+            fruit(3, 0, Vec2::new(240.0, 0.0)),
+        ];
+        {
+            let mut refs: Vec<&mut Fruit> = fruits.iter_mut().collect();
+            roll_fruits(&mut refs, 0.01, &physics_config, &fruit_table, -1000.0);
+        }
+        assert!(fruits[1].a_acc < 0.0, "the top cherry should start rolling off to the right");
+        assert_eq!(fruits[0].a_acc, 0.0);
+        assert_eq!(fruits[2].a_acc, 0.0);
+        assert_eq!(fruits[3].a_acc, 0.0);
+    }
+
+    #[test]
+    fn spin_on_the_floor_sets_a_fruit_rolling() {
+        let mut fruits: Vec<Fruit> = vec![fruit(0, 0, Vec2::new(0.0, 100.0))];
+        let floor: f32 = 100.0 - fruits[0].radius;
+        fruits[0].set_a_vel(0.01, -5.0);
+        {
+            let mut refs: Vec<&mut Fruit> = fruits.iter_mut().collect();
+            roll_fruits(&mut refs, 0.01, &PhysicsConfig::default(), &FruitTable::default(), floor);
+        }
+        // clockwise spin pushes it right, and pays for it out of the spin
+        assert!(fruits[0].get_vel(0.01).x > 0.0);
+        assert!(fruits[0].get_a_vel(0.01) > -5.0);
+    }
+
+    #[test]
+    fn max_correction_limits_each_pairs_push() {
+        // 30 px of overlap between two 20 px cherries, half of it resolved per pass
+        assert!((gap_after_collision(f32::INFINITY) - 25.0).abs() < 1e-4);
+        assert!((gap_after_collision(PhysicsConfig::default().max_correction) - (10.0 + MAX_CORRECTION)).abs() < 1e-4);
+        assert!((gap_after_collision(1.0) - 11.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn a_deep_overlap_opens_up_over_several_steps() {
+        let max_correction: f32 = PhysicsConfig::default().max_correction;
+        let radius: f32 = DEFAULT_FRUITS[9].radius;
+        // two melons almost on top of each other, 225 px deep
+        let mut fruits: Vec<Fruit> = vec![fruit(0, 9, Vec2::ZERO), fruit(1, 9, Vec2::new(5.0, 0.0))];
+        let mut gap: f32 = 5.0;
+        let mut limited_steps: u32 = 0;
+        for _ in 0..60 {
+            {
+                let mut refs: Vec<&mut Fruit> = fruits.iter_mut().collect();
+                resolve_collisions(&mut refs, max_correction, false, 1.0 / 60.0, &mut 0, None);
+            }
+            let opened: f32 = fruits[1].pos.x - fruits[0].pos.x - gap;
+            assert!(opened <= max_correction + 1e-3, "opened {} px in one step", opened);
+            if opened > max_correction - 1e-3 {
+                limited_steps += 1;
+            }
+            gap += opened;
+        }
+        assert!(limited_steps >= 10, "only {} steps were held to max_correction", limited_steps);
+        assert!((gap - 2.0 * radius).abs() < 0.1, "still {} px apart", gap);
+    }
+
+    #[test]
+    fn merge_pairs_finds_what_a_nested_loop_finds() {
+        let mut rng: StdRng = StdRng::seed_from_u64(7);
+        let candidates: Vec<MergeCandidate> = (0..120)
+            .map(|id| {
+                let group: u8 = rng.gen_range(0..3);
+                MergeCandidate {
+                    entity: None,
+                    id,
+                    group,
+                    pos: Vec2::new(rng.gen_range(LEFT_WALL..RIGHT_WALL), rng.gen_range(BOTTOM_WALL..TOP_WALL)),
+                    vel: Vec2::ZERO,
+                    radius: DEFAULT_FRUITS[group as usize].radius,
+                    consumed: false,
+                    fresh: false,
+                    cooling: false,
+                }
+            })
+            .collect();
+        let touching = |(i, j): &(usize, usize)| -> bool {
+            return (candidates[*j].pos - candidates[*i].pos).length() < candidates[*i].radius + candidates[*j].radius;
+        };
+        for new_start in [0, 60] {
+            let mut nested: Vec<(usize, usize)> = Vec::new();
+            for j in new_start..candidates.len() {
+                for i in 0..j {
+                    if candidates[i].group == candidates[j].group {
+                        nested.push((i, j));
+                    }
+                }
+            }
+            nested.sort_unstable();
+            nested.retain(touching);
+            let mut grid: Vec<(usize, usize)> = merge_pairs(&candidates, new_start);
+            assert!(grid.iter().all(|(i, j)| candidates[*i].group == candidates[*j].group));
+            grid.retain(touching);
+            assert!(!nested.is_empty());
+            assert_eq!(grid, nested);
+        }
+    }
+
+    #[test]
+    fn gravity_scales_from_the_smallest_fruit_to_the_largest() {
+        let fruit_table: FruitTable = FruitTable::default();
+        let physics_config: PhysicsConfig = PhysicsConfig { gravity_size_factor: 2.0, ..default() };
+        let middle: f32 = (fruit_table.min_radius() + fruit_table.max_radius()) / 2.0;
+        assert_eq!(physics_config.gravity_for_radius(fruit_table.min_radius(), &fruit_table), physics_config.gravity);
+        assert!((physics_config.gravity_for_radius(middle, &fruit_table) - 1.5 * physics_config.gravity).abs() < 1e-3);
+        assert!((physics_config.gravity_for_radius(fruit_table.max_radius(), &fruit_table) - 2.0 * physics_config.gravity).abs() < 1e-3);
+    }
+
+    // Everything apply_merges needs, with the default fruit table and a player
+    fn merge_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(FixedTime::new_from_secs(1.0 / 60.0));
+        world.insert_resource(PhysicsConfig::default());
+        world.insert_resource(GameSettings::default());
+        world.insert_resource(FruitTable::default());
+        world.insert_resource(FruitAssets {
+            fruit_icon: Handle::default(),
+            decals: vec![None; DEFAULT_FRUITS.len()],
+            crack: Handle::default(),
+            skin: Vec::new(),
+        });
+        world.insert_resource(Scoreboard { score: 0 });
+        world.init_resource::<BiggestFruit>();
+        world.init_resource::<PhysicsCounters>();
+        world.init_resource::<Events<FruitMerged>>();
+        world.spawn((Transform::default(), FruitIterator { next_id: 100, next_group: 0, next_is_bomb: false }, Player));
+        return world;
+    }
+
+    fn run_merges(world: &mut World) {
+        let mut schedule = Schedule::default();
+        schedule.add_systems(apply_merges);
+        schedule.run(world);
+    }
+
+    #[test]
+    fn merging_into_group_5_records_the_biggest_fruit() {
+        let mut world = merge_world();
+        let radius: f32 = DEFAULT_FRUITS[4].radius;
+        world.spawn(fruit(0, 4, Vec2::new(-radius + 1.0, 0.0)));
+        world.spawn(fruit(1, 4, Vec2::new(radius - 1.0, 0.0)));
+        run_merges(&mut world);
+        assert_eq!(world.resource::<BiggestFruit>().group, Some(5));
+        assert_eq!(world.resource::<Scoreboard>().score, DEFAULT_FRUITS[4].score);
+    }
+
+    // merge_world plus what input_handler reads, and the player entity
+    fn input_world() -> (World, Entity) {
+        let mut world = merge_world();
+        world.insert_resource(Time::default());
+        world.init_resource::<Input<KeyCode>>();
+        world.init_resource::<ArenaConfig>();
+        world.init_resource::<DynamicDifficulty>();
+        world.insert_resource(GameRng(StdRng::seed_from_u64(1)));
+        world.init_resource::<Events<FruitDropped>>();
+        world.init_resource::<AttractMode>();
+        world.init_resource::<ReplayPlayer>();
+        let player: Entity = world.query_filtered::<Entity, With<Player>>().single(&world);
+        world.entity_mut(player).insert((Sprite::default(), FruitSpawnTimer::ready()));
+        return (world, player);
+    }
+
+    fn run_input(world: &mut World) {
+        let mut schedule = Schedule::default();
+        schedule.add_systems(input_handler);
+        schedule.run(world);
+    }
+
+    #[test]
+    fn a_flicked_fruit_leaves_with_the_players_velocity() {
+        let (mut world, player) = input_world();
+        world.resource_mut::<GameSettings>().flick = true;
+        let dt: f32 = 1.0 / 60.0;
+
+        // 2 px in one step is 120 px/s, however the player got there
+        run_input(&mut world);
+        world.get_mut::<Transform>(player).unwrap().translation.x += 2.0;
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+        run_input(&mut world);
+
+        let vel: Vec2 = world.query::<&Fruit>().single(&world).get_vel(dt);
+        assert!((vel.x - 120.0 * FLICK_FRACTION).abs() < 1e-2, "flicked at {}", vel.x);
+        assert_eq!(flick_speed(100.0, dt), FLICK_MAX_SPEED);
+    }
+
+    #[test]
+    fn the_player_stops_where_the_held_fruit_touches_the_wall() {
+        let (mut world, player) = input_world();
+        world.get_mut::<FruitIterator>(player).unwrap().next_group = 4;
+        world.get_mut::<Transform>(player).unwrap().translation.x = RIGHT_WALL;
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::D);
+        run_input(&mut world);
+        let x: f32 = world.get::<Transform>(player).unwrap().translation.x;
+        assert_eq!(x, RIGHT_WALL - WALL_THICKNESS/2.0 - DEFAULT_FRUITS[4].radius);
+        assert_eq!(clamp_player_x(LEFT_WALL, 10.0), LEFT_WALL + WALL_THICKNESS/2.0 + 10.0);
+    }
+
+    #[test]
+    fn the_held_fruit_stays_clear_of_the_wall_whenever_it_drops() {
+        let cooldown_steps: usize = (SPAWN_INTERVAL * 60.0).round() as usize;
+        // held Space from each of these steps on, so the drops land early, mid and late in the cooldown
+        for press_step in [1, 2, cooldown_steps / 2, cooldown_steps - 1, cooldown_steps, cooldown_steps + 1] {
+            let (mut world, player) = input_world();
+            world.get_mut::<FruitIterator>(player).unwrap().next_group = 4;
+            world.get_mut::<Transform>(player).unwrap().translation.x = RIGHT_WALL;
+            world.resource_mut::<Input<KeyCode>>().press(KeyCode::D);
+            world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+            for step in 0..3 * cooldown_steps {
+                if step == 1 {
+                    world.resource_mut::<Input<KeyCode>>().release(KeyCode::Space);
+                }
+                if step == press_step {
+                    world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+                }
+                run_input(&mut world);
+                let x: f32 = world.get::<Transform>(player).unwrap().translation.x;
+                let held: u8 = world.get::<FruitIterator>(player).unwrap().next_group;
+                let radius: f32 = world.resource::<FruitTable>().get(held).radius;
+                assert!(
+                    x + radius <= RIGHT_WALL - WALL_THICKNESS/2.0 + 1e-3,
+                    "held group {} overlaps the wall at step {} after pressing at {}", held, step, press_step
+                );
+            }
+            assert!(world.query::<&Fruit>().iter(&world).count() > 1, "pressing at {} dropped too little", press_step);
+        }
+    }
+
+    #[test]
+    fn a_cascade_resolves_in_one_step_and_scores_by_depth() {
+        let mut world = merge_world();
+        let cherry: f32 = DEFAULT_FRUITS[0].radius;
+        let strawberry: f32 = DEFAULT_FRUITS[1].radius;
+        // the two cherries make a strawberry at the origin, touching the one already there
+        world.spawn(fruit(0, 0, Vec2::new(-cherry + 1.0, 0.0)));
+        world.spawn(fruit(1, 0, Vec2::new(cherry - 1.0, 0.0)));
+        world.spawn(fruit(2, 1, Vec2::new(2.0 * strawberry - 2.0, 0.0)));
+        run_merges(&mut world);
+        let groups: Vec<u8> = world.query::<&Fruit>().iter(&world).map(|fruit| fruit.group).collect();
+        assert_eq!(groups, vec![2]);
+        assert_eq!(world.resource::<Scoreboard>().score, DEFAULT_FRUITS[0].score + 2 * DEFAULT_FRUITS[1].score);
+    }
+
+    #[test]
+    fn a_short_table_merges_into_its_top_fruit_and_no_further() {
+        let mut world = merge_world();
+        let table: FruitTable = FruitTable::new(tiers(5));
+        let radius: f32 = table.get(3).radius;
+        world.spawn(FruitBundle::new(0, 3, Vec2::new(-radius + 1.0, 0.0), &table, Handle::default()).fruit);
+        world.spawn(FruitBundle::new(1, 3, Vec2::new(radius - 1.0, 0.0), &table, Handle::default()).fruit);
+        let top_radius: f32 = table.get(4).radius;
+        world.insert_resource(table);
+        run_merges(&mut world);
+        let groups: Vec<u8> = world.query::<&Fruit>().iter(&world).map(|fruit| fruit.group).collect();
+        assert_eq!(groups, vec![4]);
+        assert_eq!(world.resource::<BiggestFruit>().group, Some(4));
+
+        // two of the top fruit touching stay as they are
+        let top: Fruit = FruitBundle::new(2, 4, Vec2::new(2.0 * top_radius - 1.0, 0.0), world.resource::<FruitTable>(), Handle::default()).fruit;
+        world.spawn(top);
+        run_merges(&mut world);
+        let groups: Vec<u8> = world.query::<&Fruit>().iter(&world).map(|fruit| fruit.group).collect();
+        assert_eq!(groups, vec![4, 4]);
+    }
+
+    #[test]
+    fn fixed_center_drops_from_the_middle_whatever_the_keys() {
+        let (mut world, player) = input_world();
+        world.resource_mut::<GameSettings>().drop_mode = DropMode::FixedCenter;
+        world.get_mut::<Transform>(player).unwrap().translation.x = 100.0;
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::D);
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+        run_input(&mut world);
+        assert_eq!(world.get::<Transform>(player).unwrap().translation.x, 0.0);
+        assert_eq!(world.query::<&Fruit>().single(&world).pos.x, 0.0);
+    }
+
+    #[test]
+    fn free_mode_moves_with_the_keys_up_to_the_walls() {
+        let (mut world, player) = input_world();
+        assert!(world.resource::<GameSettings>().drop_mode == DropMode::Free);
+        let dt: f32 = world.resource::<FixedTime>().period.as_secs_f32();
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::D);
+        run_input(&mut world);
+        assert!((world.get::<Transform>(player).unwrap().translation.x - PLAYER_SPEED * dt).abs() < 1e-3);
+
+        // pushing into the left wall stops the held cherry against it
+        world.get_mut::<Transform>(player).unwrap().translation.x = LEFT_WALL;
+        let mut input = world.resource_mut::<Input<KeyCode>>();
+        input.release(KeyCode::D);
+        input.press(KeyCode::A);
+        run_input(&mut world);
+        run_input(&mut world);
+        assert_eq!(world.get::<Transform>(player).unwrap().translation.x, LEFT_WALL + WALL_THICKNESS/2.0 + DEFAULT_FRUITS[0].radius);
+    }
+
+    #[test]
+    fn the_arrow_layout_moves_and_drops_with_the_arrows() {
+        let (mut world, player) = input_world();
+        world.resource_mut::<GameSettings>().keys = KeyLayout::Arrows;
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::D);
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+        run_input(&mut world);
+        assert_eq!(world.get::<Transform>(player).unwrap().translation.x, 0.0);
+        assert_eq!(world.query::<&Fruit>().iter(&world).count(), 0);
+
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Right);
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Down);
+        run_input(&mut world);
+        assert!(world.get::<Transform>(player).unwrap().translation.x > 0.0);
+        assert_eq!(world.query::<&Fruit>().iter(&world).count(), 1);
+    }
+
+    #[test]
+    fn rounded_boxes_never_rotate() {
+        let mut table: FruitTable = FruitTable::default();
+        table.shape = CollisionShape::RoundedBox { corner: ROUNDED_BOX_CORNER };
+        let mut spun: Fruit = FruitBundle::new(0, 0, Vec2::ZERO, &table, Handle::default()).fruit;
+        spun.a_acc = 100.0;
+        let mut world = World::new();
+        world.insert_resource(FixedTime::new_from_secs(0.01));
+        world.insert_resource(PhysicsConfig::default());
+        let entity: Entity = world.spawn(spun).id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(physics_update);
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Fruit>(entity).unwrap().a_pos, 0.0);
+    }
+
+    #[test]
+    fn integrate_carries_velocity_and_applies_acceleration() {
+        let dt: f32 = 0.01;
+        let mut moving: Fruit = fruit(0, 0, Vec2::new(0.0, 100.0));
+        moving.pos_last = Vec2::new(-1.0, 100.0); // 100 px/s to the right
+        moving.acc = Vec2::new(0.0, -1000.0);
+        integrate_fruit(&mut moving, dt, &PhysicsConfig::default());
+        assert_eq!(moving.pos_last, Vec2::new(0.0, 100.0));
+        assert!((moving.pos - Vec2::new(1.0, 99.9)).length() < 1e-4);
+        // accelerations only count for the step they were added in
+        assert_eq!(moving.acc, Vec2::ZERO);
+    }
+
+    #[test]
+    fn a_perched_fruit_rolls_off_its_perch() {
+        let mut app: App = settle::headless_game(None);
+        app.world.run_schedule(Startup);
+        let fruit_table: FruitTable = FruitTable::default();
+        let floor: f32 = app.world.resource::<ArenaConfig>().floor();
+        let base: f32 = fruit_table.get(3).radius;
+        let cherry: f32 = fruit_table.get(0).radius;
+        // just right of the top of the base, where it could balance for a while without torque
+        let perch: Vec2 = Vec2::new(2.0, floor + 2.0 * base + cherry);
+        app.world.spawn(FruitBundle::new(0, 3, Vec2::new(0.0, floor + base), &fruit_table, Handle::default()));
+        let perched: Entity = app.world.spawn(FruitBundle::new(1, 0, perch, &fruit_table, Handle::default())).id();
+        settle::settle_board(&mut app.world, 180);
+        let pos: Vec2 = app.world.get::<Fruit>(perched).unwrap().pos;
+        assert!(pos.x > perch.x + cherry && pos.y < perch.y - cherry, "still perched at {:?}", pos);
+    }
+
+    // x of three overlapping cherries in a row after one collision pass
+    fn row_after_collision(reverse: bool) -> Vec<f32> {
+        let mut fruits: Vec<Fruit> = (0..3).map(|id| fruit(id, 0, Vec2::new(15.0 * (id as f32 - 1.0), 0.0))).collect();
+        {
+            let mut refs: Vec<&mut Fruit> = fruits.iter_mut().collect();
+            resolve_collisions(&mut refs, f32::INFINITY, reverse, 0.01, &mut 0, None);
+        }
+        return fruits.iter().map(|fruit| fruit.pos.x).collect();
+    }
+
+    #[test]
+    fn walking_the_pairs_backwards_mirrors_the_bias() {
+        let forward: Vec<f32> = row_after_collision(false);
+        let backward: Vec<f32> = row_after_collision(true);
+        assert_ne!(forward, backward);
+        for (x, mirrored) in forward.iter().zip(backward.iter().rev()) {
+            assert!((x + mirrored).abs() < 1e-4, "{:?} is not the mirror of {:?}", forward, backward);
+        }
+    }
+
+    // How far the middle of a symmetric, slightly overlapping pyramid of grapes drifts sideways
+    // while it settles
+    fn pile_drift(relaxation_alternate: bool) -> f32 {
+        let mut app: App = settle::headless_game(None);
+        app.world.run_schedule(Startup);
+        app.insert_resource(MergeEnabled(false));
+        app.world.resource_mut::<PhysicsConfig>().relaxation_alternate = relaxation_alternate;
+        let fruit_table: FruitTable = FruitTable::default();
+        let radius: f32 = fruit_table.get(2).radius;
+        let floor: f32 = app.world.resource::<ArenaConfig>().floor();
+        let spacing: f32 = 2.0 * radius - 2.0;
+        let mut id: u32 = 0;
+        for (row, count) in [7, 6, 5, 4].into_iter().enumerate() {
+            let y: f32 = floor + radius + row as f32 * spacing * 0.87;
+            for column in 0..count {
+                let x: f32 = (column as f32 - (count - 1) as f32 / 2.0) * spacing;
+                app.world.spawn(FruitBundle::new(id, 2, Vec2::new(x, y), &fruit_table, Handle::default()));
+                id += 1;
+            }
+        }
+        settle::settle_board(&mut app.world, 300);
+        let xs: Vec<f32> = app.world.query::<&Fruit>().iter(&app.world).map(|fruit| fruit.pos.x).collect();
+        return (xs.iter().sum::<f32>() / xs.len() as f32).abs();
+    }
+
+    #[test]
+    fn alternating_the_pair_order_keeps_a_symmetric_pile_centered() {
+        let one_way: f32 = pile_drift(false);
+        let alternating: f32 = pile_drift(true);
+        assert!(alternating < one_way, "alternating drifted {} px, one way {} px", alternating, one_way);
+    }
+
+    // Fruits left and the score after two touching cherries spend a few steps in the headless game
+    fn after_touching_cherries(merge_enabled: bool) -> (usize, u32) {
+        let mut app: App = settle::headless_game(None);
+        app.world.run_schedule(Startup);
+        app.insert_resource(MergeEnabled(merge_enabled));
+        let fruit_table: FruitTable = FruitTable::default();
+        let cherry: f32 = fruit_table.get(0).radius;
+        app.world.spawn(FruitBundle::new(0, 0, Vec2::new(-cherry + 1.0, 0.0), &fruit_table, Handle::default()));
+        app.world.spawn(FruitBundle::new(1, 0, Vec2::new(cherry - 1.0, 0.0), &fruit_table, Handle::default()));
+        settle::settle_board(&mut app.world, 10);
+        let count: usize = app.world.query::<&Fruit>().iter(&app.world).count();
+        return (count, app.world.resource::<Scoreboard>().score);
+    }
+
+    #[test]
+    fn no_merge_mode_leaves_the_fruits_and_the_score_alone() {
+        assert_eq!(after_touching_cherries(false), (2, 0));
+        // the same steps with merging on do merge them
+        assert_eq!(after_touching_cherries(true), (1, DEFAULT_FRUITS[0].score));
+    }
+
+    // Whether two touching cherries closing at `speed` px/s merge with merge_max_rel_speed 100
+    fn merges_at_speed(speed: f32) -> bool {
+        let mut world = merge_world();
+        world.resource_mut::<PhysicsConfig>().merge_max_rel_speed = 100.0;
+        let cherry: f32 = DEFAULT_FRUITS[0].radius;
+        let mut moving: Fruit = fruit(0, 0, Vec2::new(-cherry + 1.0, 0.0));
+        moving.set_vel(1.0 / 60.0, Vec2::new(speed, 0.0));
+        world.spawn(moving);
+        world.spawn(fruit(1, 0, Vec2::new(cherry - 1.0, 0.0)));
+        run_merges(&mut world);
+        return world.query::<&Fruit>().iter(&world).count() == 1;
+    }
+
+    #[test]
+    fn ricocheting_pairs_bounce_instead_of_merging() {
+        assert!(merges_at_speed(50.0));
+        assert!(!merges_at_speed(500.0));
+    }
+
+    #[test]
+    fn a_fully_biased_merge_keeps_the_lower_fruits_bottom() {
+        let mut world = merge_world();
+        world.resource_mut::<PhysicsConfig>().merge_spawn_bias = 1.0;
+        let cherry: f32 = DEFAULT_FRUITS[0].radius;
+        world.spawn(fruit(0, 0, Vec2::new(0.0, cherry)));
+        world.spawn(fruit(1, 0, Vec2::new(0.0, 3.0 * cherry - 2.0)));
+        run_merges(&mut world);
+        let merged: Vec2 = world.query::<&Fruit>().single(&world).pos;
+        assert!((merged - Vec2::new(0.0, DEFAULT_FRUITS[1].radius)).length() < 1e-4, "merged at {}", merged);
+    }
+
+    // Where update_sprites draws a fruit that moved from x = 0 to x = 10 in the last step, half
+    // a step later
+    fn drawn_x(paused: bool) -> f32 {
+        let mut world = World::new();
+        let mut time_step: FixedTime = FixedTime::new_from_secs(1.0 / 60.0);
+        time_step.tick(Duration::from_secs_f32(0.5 / 60.0));
+        world.insert_resource(time_step);
+        world.insert_resource(RenderInterpolation { enabled: true });
+        let mut step_mode: StepMode = StepMode::default();
+        step_mode.paused = paused;
+        world.insert_resource(step_mode);
+        let mut moved: Fruit = fruit(0, 0, Vec2::new(10.0, 0.0));
+        moved.pos_last = Vec2::ZERO;
+        let entity: Entity = world.spawn((Transform::default(), moved)).id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_sprites);
+        schedule.run(&mut world);
+        return world.get::<Transform>(entity).unwrap().translation.x;
+    }
+
+    #[test]
+    fn a_paused_board_is_drawn_where_it_stopped() {
+        assert!((drawn_x(false) - 5.0).abs() < 1e-3);
+        assert_eq!(drawn_x(true), 10.0);
+    }
+
+    // The drop buffer a Space tap leaves on the player after one Update, paused or not
+    fn buffered_after_tap(paused: bool) -> f32 {
+        let mut world = World::new();
+        let mut input: Input<KeyCode> = Input::default();
+        input.press(KeyCode::Space);
+        world.insert_resource(input);
+        let mut step_mode: StepMode = StepMode::default();
+        step_mode.paused = paused;
+        world.insert_resource(step_mode);
+        let player: Entity = world.spawn((Player, FruitSpawnTimer::ready())).id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(buffer_drop_press);
+        schedule.run(&mut world);
+        return world.get::<FruitSpawnTimer>(player).unwrap().drop_buffer;
+    }
+
+    #[test]
+    fn space_tap_is_buffered_for_the_next_step() {
+        assert_eq!(buffered_after_tap(false), DROP_BUFFER_TIME);
+        assert_eq!(buffered_after_tap(true), 0.0);
+    }
+
+    #[test]
+    fn a_press_just_before_the_cooldown_ends_drops_once_ready() {
+        let (mut world, player) = input_world();
+        world.insert_resource(StepMode::default());
+        let dt: f32 = world.resource::<FixedTime>().period.as_secs_f32();
+        let interval: f32 = world.resource::<DynamicDifficulty>().spawn_interval();
+        // ready on the second step, well inside DROP_BUFFER_TIME
+        world.get_mut::<FruitSpawnTimer>(player).unwrap().timer.set_elapsed(Duration::from_secs_f32(interval - 1.5 * dt));
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+        let mut schedule = Schedule::default();
+        schedule.add_systems((buffer_drop_press, input_handler).chain());
+        schedule.run(&mut world);
+        assert_eq!(world.query::<&Fruit>().iter(&world).count(), 0);
+
+        // let go before it's ready, so only the buffer remembers the press
+        let mut input = world.resource_mut::<Input<KeyCode>>();
+        input.release(KeyCode::Space);
+        input.clear();
+        schedule.run(&mut world);
+        assert_eq!(world.query::<&Fruit>().iter(&world).count(), 1);
+    }
+
+    #[test]
+    fn coincident_fruits_are_pushed_apart_sideways() {
+        let mut fruits: Vec<Fruit> = vec![fruit(0, 0, Vec2::ZERO), fruit(1, 0, Vec2::ZERO)];
+        {
+            let mut refs: Vec<&mut Fruit> = fruits.iter_mut().collect();
+            resolve_collisions(&mut refs, f32::INFINITY, false, 0.01, &mut 0, None);
+        }
+        assert!(fruits.iter().all(|fruit| fruit.pos.is_finite() && fruit.pos_last.is_finite()));
+        assert!(fruits[1].pos.x > fruits[0].pos.x);
+        assert_eq!(fruits[1].pos.y, fruits[0].pos.y);
+    }
+
+    #[test]
+    fn two_fruits_hitting_each_other_send_one_collision_event() {
+        let dt: f32 = 0.01;
+        let mut fruits: Vec<Fruit> = vec![fruit(0, 0, Vec2::ZERO), fruit(1, 0, Vec2::new(5.0, 0.0))];
+        fruits[0].set_vel(dt, Vec2::new(300.0, 0.0));
+        fruits[1].set_vel(dt, Vec2::new(-300.0, 0.0));
+        {
+            let mut refs: Vec<&mut Fruit> = fruits.iter_mut().collect();
+            resolve_collisions(&mut refs, f32::INFINITY, false, dt, &mut 0, None);
+        }
+        let mut walled: Fruit = fruit(2, 0, Vec2::new(200.0, 0.0));
+        walled.hit(400.0, None);
+        let mut world: World = World::new();
+        world.init_resource::<Events<CollisionEvent>>();
+        for fruit in fruits {
+            world.spawn(fruit);
+        }
+        world.spawn(walled);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(record_damage);
+        schedule.run(&mut world);
+
+        let mut speeds: Vec<f32> = world.resource_mut::<Events<CollisionEvent>>().drain().map(|hit| hit.speed).collect();
+        speeds.sort_by(f32::total_cmp);
+        assert_eq!(speeds, vec![400.0, 600.0]);
+    }
+
+    #[test]
+    fn every_pair_is_counted_as_tested() {
+        let mut fruits: Vec<Fruit> = (0..10).map(|id| fruit(id, 0, Vec2::new(id as f32 * 200.0, 0.0))).collect();
+        fruits.push(fruit(10, 0, Vec2::new(30.0, 0.0)));
+        let mut pairs_tested: u32 = 0;
+        {
+            let mut refs: Vec<&mut Fruit> = fruits.iter_mut().collect();
+            resolve_collisions(&mut refs, f32::INFINITY, false, 0.01, &mut pairs_tested, None);
+        }
+        assert_eq!(pairs_tested, 55);
+    }
+
+    #[test]
+    fn dropped_fruit_lands_on_the_floor_or_a_fruit() {
+        let floor: f32 = 0.0;
+        let falling: Fruit = fruit(0, 0, Vec2::new(0.0, 200.0));
+        // the query it's checked against includes the fruit itself
+        assert!(!has_landed(&falling, [fruit(0, 0, Vec2::new(0.0, 200.0))].iter(), floor));
+        let on_floor: Fruit = fruit(0, 0, Vec2::new(0.0, falling.radius));
+        assert!(has_landed(&on_floor, std::iter::empty(), floor));
+        let below: Fruit = fruit(1, 2, Vec2::new(0.0, 200.0 - falling.radius - 35.0));
+        assert!(has_landed(&falling, [below].iter(), floor));
+    }
+
+    #[test]
+    fn a_rising_floor_carries_its_fruit_up_to_the_danger_line() {
+        let mut world: World = World::new();
+        world.insert_resource(FixedTime::new_from_secs(1.0 / 60.0));
+        world.insert_resource(PhysicsConfig { floor_rise: 100.0, ..default() });
+        world.init_resource::<ArenaConfig>();
+        world.init_resource::<SoftWalls>();
+        world.init_resource::<Events<HardLanding>>();
+        let start: f32 = world.resource::<ArenaConfig>().floor();
+        let resting: Fruit = fruit(0, 0, Vec2::ZERO);
+        let radius: f32 = resting.radius;
+        let entity: Entity = world.spawn(resting).id();
+        world.get_mut::<Fruit>(entity).unwrap().pos = Vec2::new(0.0, start + radius);
+        world.get_mut::<Fruit>(entity).unwrap().pos_last = Vec2::new(0.0, start + radius);
+        let mut fall = Schedule::default();
+        fall.add_systems(physics_update);
+        let mut constrain = Schedule::default();
+        constrain.add_systems((raise_floor, apply_constraint).chain());
+
+        // long enough for the floor to climb all the way, 535 px at 100 px/s
+        for _ in 0..8 * 60 {
+            world.get_mut::<Fruit>(entity).unwrap().acc.y = -GRAVITY;
+            fall.run(&mut world);
+            constrain.run(&mut world);
+            let floor: f32 = world.resource::<ArenaConfig>().floor();
+            let bottom: f32 = world.get::<Fruit>(entity).unwrap().pos.y - radius;
+            assert!(bottom >= floor - 1e-3, "fruit bottom {} below the floor at {}", bottom, floor);
+        }
+        let arena_config = world.resource::<ArenaConfig>();
+        assert!((arena_config.floor() - arena_config.danger_line_y).abs() < 1e-3);
+        assert_eq!(arena_config.floor_speed(100.0), 0.0);
+        let bottom: f32 = world.get::<Fruit>(entity).unwrap().pos.y - radius;
+        assert!(bottom - arena_config.danger_line_y < 2.0, "thrown up to {}", bottom);
+
+        let mut reset = Schedule::default();
+        reset.add_systems(reset_floor);
+        reset.run(&mut world);
+        assert_eq!(world.resource::<ArenaConfig>().bottom_wall, BOTTOM_WALL);
+    }
+
+    // Score for merging a dropped cherry into the one it landed next to, `steps` steps after it landed
+    fn score_for_merge_after(steps: u32) -> u32 {
+        let mut world = merge_world();
+        world.init_resource::<ArenaConfig>();
+        world.resource_mut::<GameSettings>().perfect_drop_window = PERFECT_DROP_WINDOW;
+        let cherry: f32 = DEFAULT_FRUITS[0].radius;
+        world.spawn((fruit(0, 0, Vec2::new(-cherry + 1.0, 0.0)), DropAge(Some(0.0))));
+        world.spawn(fruit(1, 0, Vec2::new(cherry - 1.0, 0.0)));
+        let mut aging = Schedule::default();
+        aging.add_systems(age_drops);
+        for _ in 0..steps {
+            aging.run(&mut world);
+        }
+        run_merges(&mut world);
+        return world.resource::<Scoreboard>().score;
+    }
+
+    #[test]
+    fn only_a_merge_within_the_window_is_a_perfect_drop() {
+        let after_window: u32 = (PERFECT_DROP_WINDOW * 60.0).ceil() as u32 + 1;
+        assert_eq!(score_for_merge_after(1), DEFAULT_FRUITS[0].score + PERFECT_DROP_BONUS);
+        assert_eq!(score_for_merge_after(after_window), DEFAULT_FRUITS[0].score);
+    }
+
+    #[test]
+    fn velocity_helpers_stay_finite_as_dt_goes_to_zero() {
+        for dt in [0.0, 1e-30, f32::MIN_POSITIVE] {
+            let mut moving: Fruit = fruit(0, 0, Vec2::new(10.0, 0.0));
+            assert!(moving.get_vel(dt).is_finite());
+            moving.set_vel(dt, Vec2::new(100.0, -50.0));
+            assert!(moving.get_vel(dt).is_finite());
+            moving.inc_vel(dt, Vec2::new(0.0, 1e6));
+            assert!(moving.get_vel(dt).is_finite());
+            assert!(moving.pos.is_finite() && moving.pos_last.is_finite());
+            moving.set_a_vel(dt, 5.0);
+            assert!(moving.get_a_vel(dt).is_finite());
+        }
+    }
+
+    #[test]
+    fn only_the_fall_is_held_to_max_fall_speed() {
+        let physics_config: PhysicsConfig = PhysicsConfig { max_fall_speed: 300.0, air_damping: 0.0, ..default() };
+        let dt: f32 = 0.01;
+        let mut falling: Fruit = fruit(0, 0, Vec2::ZERO);
+        falling.set_vel(dt, Vec2::new(0.0, -600.0));
+        integrate_fruit(&mut falling, dt, &physics_config);
+        assert!((falling.get_vel(dt).y + 300.0).abs() < 1e-2);
+        let mut sliding: Fruit = fruit(1, 0, Vec2::ZERO);
+        sliding.set_vel(dt, Vec2::new(600.0, 0.0));
+        integrate_fruit(&mut sliding, dt, &physics_config);
+        assert!((sliding.get_vel(dt).x - 600.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn a_long_fall_levels_off_at_max_fall_speed() {
+        let physics_config: PhysicsConfig = PhysicsConfig { max_fall_speed: 300.0, air_damping: 0.0, ..default() };
+        let dt: f32 = 1.0 / 60.0;
+        let mut falling: Fruit = fruit(0, 0, Vec2::new(0.0, 1000.0));
+        let mut speeds: Vec<f32> = Vec::new();
+        for _ in 0..120 {
+            falling.acc.y = -physics_config.gravity;
+            integrate_fruit(&mut falling, dt, &physics_config);
+            speeds.push(-falling.get_vel(dt).y);
+        }
+        // each step starts from max_fall_speed and gains one step of gravity, where gravity alone
+        // would have reached 4000 px/s by the end
+        let cap: f32 = physics_config.max_fall_speed + physics_config.gravity * dt;
+        assert!(speeds.windows(2).all(|pair| pair[1] >= pair[0] - 0.1));
+        assert!(speeds.iter().all(|speed| *speed <= cap + 0.1));
+        assert!(speeds[30..].iter().all(|speed| (speed - cap).abs() < 0.1), "{:?}", &speeds[30..]);
+    }
+
+    #[test]
+    fn angular_damping_takes_its_share_of_the_spin_each_step() {
+        let physics_config: PhysicsConfig = PhysicsConfig { angular_damping: 0.25, ..default() };
+        let dt: f32 = 0.01;
+        let mut spinning: Fruit = fruit(0, 0, Vec2::ZERO);
+        spinning.set_a_vel(dt, 8.0);
+        integrate_fruit(&mut spinning, dt, &physics_config);
+        assert!((spinning.get_a_vel(dt) - 6.0).abs() < 1e-3);
+        assert_eq!(spinning.get_vel(dt), Vec2::ZERO);
+    }
+
+    #[test]
+    fn air_damping_slows_movement_but_not_spin() {
+        let physics_config: PhysicsConfig = PhysicsConfig { air_damping: 0.1, ..default() };
+        let dt: f32 = 0.01;
+        let mut moving: Fruit = fruit(0, 0, Vec2::ZERO);
+        moving.set_vel(dt, Vec2::new(100.0, -50.0));
+        moving.set_a_vel(dt, 4.0);
+        integrate_fruit(&mut moving, dt, &physics_config);
+        assert!((moving.get_vel(dt) - Vec2::new(90.0, -45.0)).length() < 1e-2);
+        assert!((moving.get_a_vel(dt) - 4.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn a_new_run_can_drop_straight_away() {
+        let (mut world, player) = input_world();
+        let mut spawn_timer = world.get_mut::<FruitSpawnTimer>(player).unwrap();
+        spawn_timer.timer.reset(); // the last run had just dropped
+        spawn_timer.drop_buffer = DROP_BUFFER_TIME;
+        let mut schedule = Schedule::default();
+        schedule.add_systems(reset_spawn_timer);
+        schedule.run(&mut world);
+        assert_eq!(world.get::<FruitSpawnTimer>(player).unwrap().drop_buffer, 0.0);
+
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+        run_input(&mut world);
+        assert_eq!(world.query::<&Fruit>().iter(&world).count(), 1);
+    }
+
+    #[test]
+    fn a_merge_cooldown_holds_the_cascade_back_until_it_runs_out() {
+        let mut world = merge_world();
+        world.resource_mut::<PhysicsConfig>().merge_cooldown = 0.09;
+        let cherry: f32 = DEFAULT_FRUITS[0].radius;
+        let strawberry: f32 = DEFAULT_FRUITS[1].radius;
+        world.spawn(fruit(0, 0, Vec2::new(-cherry + 1.0, 0.0)));
+        world.spawn(fruit(1, 0, Vec2::new(cherry - 1.0, 0.0)));
+        world.spawn(fruit(2, 1, Vec2::new(2.0 * strawberry - 2.0, 0.0)));
+        run_merges(&mut world);
+        assert_eq!(world.query::<&Fruit>().iter(&world).count(), 2);
+        assert_eq!(world.query::<&MergeCooldown>().iter(&world).count(), 1);
+
+        let mut cool = Schedule::default();
+        cool.add_systems(cool_merges);
+        for _ in 0..5 {
+            cool.run(&mut world);
+        }
+        assert_eq!(world.query::<&MergeCooldown>().iter(&world).count(), 1);
+        cool.run(&mut world);
+        assert_eq!(world.query::<&MergeCooldown>().iter(&world).count(), 0);
+        run_merges(&mut world);
+        let groups: Vec<u8> = world.query::<&Fruit>().iter(&world).map(|fruit| fruit.group).collect();
+        assert_eq!(groups, vec![2]);
+    }
+
+    #[test]
+    fn a_merged_away_fruit_takes_its_parts_with_it() {
+        let mut world = merge_world();
+        let cherry: f32 = DEFAULT_FRUITS[0].radius;
+        let strawberry: f32 = DEFAULT_FRUITS[1].radius;
+        world.spawn(fruit(0, 0, Vec2::new(-cherry + 1.0, 0.0)));
+        world.spawn(fruit(1, 0, Vec2::new(cherry - 1.0, 0.0)));
+        run_merges(&mut world);
+        let parts: usize = world.query_filtered::<(), With<FruitPart>>().iter(&world).count();
+        assert!(parts > 0);
+
+        // the strawberry that just got its parts merges away in turn
+        world.spawn(fruit(2, 1, Vec2::new(2.0 * strawberry - 2.0, 0.0)));
+        run_merges(&mut world);
+        let parents: Vec<Entity> = world.query_filtered::<&Parent, With<FruitPart>>().iter(&world).map(|parent| parent.get()).collect();
+        assert_eq!(parents.len(), parts);
+        assert!(parents.iter().all(|parent| world.get::<Fruit>(*parent).is_some_and(|fruit| fruit.group == 2)));
+    }
+
+    #[test]
+    fn a_spin_locked_fruit_ignores_torque() {
+        let physics_config: PhysicsConfig = PhysicsConfig::default();
+        let top: u8 = DEFAULT_FRUITS.len() as u8 - 1;
+        let mut watermelon: Fruit = fruit(0, top, Vec2::ZERO);
+        let mut cherry: Fruit = fruit(1, 0, Vec2::ZERO);
+        let angles: (f32, f32) = (watermelon.a_pos, cherry.a_pos);
+        for spinning in [&mut watermelon, &mut cherry] {
+            spinning.set_a_vel(0.01, 3.0);
+            spinning.a_acc = 50.0;
+            integrate_fruit(spinning, 0.01, &physics_config);
+        }
+        assert_eq!(watermelon.a_pos, angles.0);
+        assert!(cherry.a_pos > angles.1);
+    }
+}
@@ -0,0 +1,139 @@
+// UI text. Every string shown to the player is looked up by key in Localization, which is
+// loaded from assets/lang/<language>.ron on top of the built-in English, so a missing or
+// partial translation falls back to English rather than leaving a blank label.
+// `{}` in a string is replaced by the arguments of Localization::format, in order.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const LANG_DIR: &str = "assets/lang";
+pub const DEFAULT_LANGUAGE: &str = "en";
+pub const LANGUAGES: [&str; 2] = ["en", "es"]; // offered by the settings screen
+
+const ENGLISH: [(&str, &str); 75] = [
+    ("score", "Score: "),
+    ("biggest", "Biggest: "),
+    ("tier", "tier {}"),
+    ("fruit_tooltip", "{} ({} pts)"),
+    ("achievement_unlocked", "Achievement unlocked: {}"),
+    ("new_best_fruit", "New best fruit: tier {}"),
+    ("merge_estimate", "Pending merges: ~{}"),
+    ("final_score", "Game over! Final score: {}"),
+    ("you_win", "You win! Time: {}"),
+    ("time_up", "Time's up! Final score: {}"),
+    ("time_left", "Time left: {}"),
+    ("share_code", "Share code: {} (C to copy, E to play another) "),
+    ("share_copied", "copied"),
+    ("share_copy_failed", "no clipboard available"),
+    ("share_enter", "code to play: {}_ (Enter to start, Esc to cancel)"),
+    ("share_invalid", "{} is not a valid code"),
+    ("restart_new_seed", "New seed (R)"),
+    ("restart_retry_seed", "Retry seed (T)"),
+    ("name_prompt", "New high score! Type your name:"),
+    ("name_confirm", "Enter to save, Esc to skip"),
+    ("leaderboard_button", "High scores (L)"),
+    ("leaderboard_title", "High scores (click or L to close)"),
+    ("leaderboard_time_attack_title", "Time attack high scores (click or L to close)"),
+    ("leaderboard_empty", "No scores yet"),
+    ("achievement_first_watermelon", "First Watermelon"),
+    ("achievement_hundred_point_combo", "100-Point Combo"),
+    ("achievement_thousand_fruits", "1000 Total Fruits"),
+    ("tutorial_merge", "Drop a fruit onto one just like it and they merge into the next size up"),
+    ("tutorial_dismiss", "Press any key to start"),
+    ("settings_title", "Settings (F10 to close)"),
+    ("setting_volume", "Volume"),
+    ("setting_difficulty", "Dynamic difficulty"),
+    ("setting_cooldown_curve", "Drop cooldown"),
+    ("setting_merge_magnet", "Merge magnet"),
+    ("setting_theme", "Theme"),
+    ("setting_skin", "Fruit skin"),
+    ("setting_merge_preview", "Merge preview"),
+    ("setting_outlines", "Fruit outlines"),
+    ("setting_interpolation", "Smooth motion"),
+    ("setting_msaa", "Anti-aliasing"),
+    ("setting_vsync", "Vsync"),
+    ("setting_render_mode", "Fruit drawing"),
+    ("setting_drop_mode", "Drop mode"),
+    ("setting_keys", "Controls"),
+    ("setting_throw_mode", "Throw mode"),
+    ("setting_flick", "Flick drops"),
+    ("setting_mode", "Mode (next run)"),
+    ("mode_endless", "Endless"),
+    ("mode_time_attack", "Time attack"),
+    ("setting_perfect_drops", "Perfect drop bonus"),
+    ("setting_merging", "Merging"),
+    ("setting_gutters", "Corner gutters"),
+    ("setting_language", "Language"),
+    ("setting_auto_pause", "Pause on alt-tab"),
+    ("setting_restart_confirm", "Restart (R)"),
+    ("setting_save_on_exit", "Save run on exit"),
+    ("on", "On"),
+    ("off", "Off"),
+    ("difficulty_relief", "Relief"),
+    ("difficulty_hard", "Hard"),
+    ("skin_default", "Default"),
+    ("cooldown_constant", "Constant"),
+    ("cooldown_lengthen", "Slower near top"),
+    ("cooldown_shorten", "Faster near top"),
+    ("drop_free", "Free"),
+    ("drop_center", "Center"),
+    ("keys_letters", "A / D, Space"),
+    ("keys_arrows", "Arrows, Down"),
+    ("render_sprite", "Sprites"),
+    ("render_mesh", "Meshes"),
+    ("merging_off", "Off (chaos)"),
+    ("auto_pause_resume", "On, resume"),
+    ("restart_hold", "Hold"),
+    ("restart_double_press", "Press twice"),
+    ("language_name", "English"),
+];
+
+#[derive(Resource)]
+pub struct Localization {
+    pub language: String,
+    strings: HashMap<String, String>,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Localization::load(DEFAULT_LANGUAGE)
+    }
+}
+
+impl Localization {
+    pub fn load(language: &str) -> Localization {
+        let mut strings: HashMap<String, String> = ENGLISH
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        if language != DEFAULT_LANGUAGE {
+            let path = Path::new(LANG_DIR).join(format!("{}.ron", language));
+            let translated: Result<HashMap<String, String>, String> = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|contents| ron::from_str(&contents).map_err(|err| err.to_string()));
+            match translated {
+                Ok(translated) => strings.extend(translated),
+                Err(err) => warn!("Could not load {}: {}, using English", path.display(), err),
+            }
+        }
+        Localization {
+            language: language.to_string(),
+            strings,
+        }
+    }
+
+    // Unknown keys show up as the key itself, which is easy to spot
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        return self.strings.get(key).map(|value| value.as_str()).unwrap_or(key);
+    }
+
+    pub fn format(&self, key: &str, args: &[&dyn std::fmt::Display]) -> String {
+        let mut text: String = self.get(key).to_string();
+        for arg in args {
+            text = text.replacen("{}", &arg.to_string(), 1);
+        }
+        return text;
+    }
+}
@@ -0,0 +1,146 @@
+// Background music: a calm track, and an intense one that fades in while the pile nears the
+// danger line. The tracks live in assets/music/ and either can be missing; with only one it just
+// plays throughout, with none there's no music. Both loop from startup on their own sink, and the
+// crossfade only moves the two volumes, so switching never restarts a track.
+
+use bevy::audio::{AudioSinkPlayback, Volume};
+use bevy::prelude::*;
+use std::path::Path;
+
+use crate::audio::MasterVolume;
+use crate::skin::ASSETS_DIR;
+use crate::{ArenaConfig, Fruit, GameState};
+
+const MUSIC_DIR: &str = "music"; // under ASSETS_DIR
+const CALM_TRACK: &str = "calm.ogg";
+const INTENSE_TRACK: &str = "intense.ogg";
+const MUSIC_VOLUME: f32 = 0.4; // relative to MasterVolume, under the sound effects
+const CROSSFADE_TIME: f32 = 2.5; // seconds for a full switch from one track to the other
+// Pile height, 0.0 at the floor and 1.0 at the danger line, that brings the intense track in,
+// and the lower one that lets it go again, so a pile right at the edge doesn't flip back and forth
+const INTENSE_FULLNESS: f32 = 0.6;
+const CALM_FULLNESS: f32 = 0.45;
+const SETTLED_SPEED: f32 = 50.0; // px/s, faster fruits are still falling and don't count toward the pile
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MusicTrack {
+    Calm,
+    Intense,
+}
+
+impl MusicTrack {
+    const ALL: [MusicTrack; 2] = [MusicTrack::Calm, MusicTrack::Intense];
+
+    fn file(&self) -> &'static str {
+        match self {
+            MusicTrack::Calm => CALM_TRACK,
+            MusicTrack::Intense => INTENSE_TRACK,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct MusicState {
+    pub target: MusicTrack, // the track being faded to
+    available: Vec<MusicTrack>, // tracks with a file, found on startup
+    mix: f32, // 0.0 all calm, 1.0 all intense
+}
+
+impl Default for MusicState {
+    fn default() -> Self {
+        MusicState {
+            target: MusicTrack::Calm,
+            available: Vec::new(),
+            mix: 0.0,
+        }
+    }
+}
+
+#[derive(Component)]
+struct MusicSink(MusicTrack);
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicState>()
+            .add_systems(Startup, start_music)
+            .add_systems(Update, (choose_track, crossfade).chain());
+    }
+}
+
+fn start_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut music: ResMut<MusicState>,
+){
+    for track in MusicTrack::ALL {
+        let path: String = format!("{}/{}", MUSIC_DIR, track.file());
+        if !Path::new(ASSETS_DIR).join(&path).is_file() {
+            continue;
+        }
+        music.available.push(track);
+        commands.spawn((
+            AudioBundle {
+                source: asset_server.load(path),
+                // silent until crossfade sets its share
+                settings: PlaybackSettings::LOOP.with_volume(Volume::new_relative(0.0)),
+            },
+            MusicSink(track),
+        ));
+    }
+    if music.available.is_empty() {
+        info!("No music in {}/{}, playing without", ASSETS_DIR, MUSIC_DIR);
+    }
+}
+
+// Intense while a run's settled pile is high, calm otherwise, menus and end screens included
+fn choose_track(
+    time_step: Res<FixedTime>,
+    game_state: Res<State<GameState>>,
+    arena_config: Res<ArenaConfig>,
+    fruit_query: Query<&Fruit>,
+    mut music: ResMut<MusicState>,
+){
+    if *game_state.get() != GameState::Playing {
+        music.target = MusicTrack::Calm;
+        return;
+    }
+    let dt = time_step.period.as_secs_f32();
+    let floor: f32 = arena_config.floor();
+    let top: f32 = fruit_query
+        .iter()
+        .filter(|fruit| fruit.get_vel(dt).length() < SETTLED_SPEED)
+        .map(|fruit| fruit.pos.y + fruit.radius)
+        .fold(floor, f32::max);
+    let fullness: f32 = ((top - floor) / (arena_config.danger_line_y - floor).max(1.0)).clamp(0.0, 1.0);
+    if fullness >= INTENSE_FULLNESS {
+        music.target = MusicTrack::Intense;
+    } else if fullness <= CALM_FULLNESS {
+        music.target = MusicTrack::Calm;
+    }
+}
+
+fn crossfade(
+    time: Res<Time>,
+    master_volume: Res<MasterVolume>,
+    mut music: ResMut<MusicState>,
+    sink_query: Query<(&MusicSink, &AudioSink)>,
+){
+    let goal: f32 = match music.target {
+        MusicTrack::Calm => 0.0,
+        MusicTrack::Intense => 1.0,
+    };
+    let step: f32 = time.delta_seconds() / CROSSFADE_TIME;
+    music.mix += (goal - music.mix).clamp(-step, step);
+    // a lone track has nothing to fade to
+    let alone: bool = music.available.len() == 1;
+    for (music_sink, sink) in sink_query.iter() {
+        let share: f32 = match (alone, music_sink.0) {
+            (true, _) => 1.0,
+            (false, MusicTrack::Calm) => 1.0 - music.mix,
+            (false, MusicTrack::Intense) => music.mix,
+        };
+        sink.set_volume(MUSIC_VOLUME * master_volume.0 * share);
+    }
+}
@@ -0,0 +1,128 @@
+// Nudge (Shift with a move key, Shift+A / Shift+D by default): an emergency shove of the whole pile
+// sideways, for straightening a lopsided stack. Every fruit gets up to nudge_speed (physics config)
+// more speed that way, but never more than that in total, so a fruit already moving that way isn't
+// flung into the wall. Then it's unavailable for nudge_cooldown seconds of play. Off unless run
+// with --nudge, as nudge_speed defaults to 0.0. The player ignores the move keys while Shift is
+// held, so a nudge doesn't also move the held fruit. A replay plays back its own nudges and ignores
+// the keys.
+
+use bevy::prelude::*;
+
+use crate::replay::{Nudged, ReplayPlayer};
+use crate::step::Simulation;
+use crate::{apply_collisions, apply_gravity, Fruit, GameSettings, GameState, PhysicsConfig};
+
+pub const NUDGE_MODIFIERS: [KeyCode; 2] = [KeyCode::ShiftLeft, KeyCode::ShiftRight];
+
+#[derive(Resource, Default)]
+struct Nudge {
+    requested: Option<f32>, // direction, -1.0 or 1.0, applied on the next step
+    cooldown: f32, // seconds left
+}
+
+pub struct NudgePlugin;
+
+impl Plugin for NudgePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Nudge>()
+            .add_systems(Update, request_nudge)
+            .add_systems(FixedUpdate, apply_nudge.after(apply_gravity).before(apply_collisions).in_set(Simulation))
+            .add_systems(OnEnter(GameState::Playing), reset_nudge);
+    }
+}
+
+fn request_nudge(
+    keys: Res<Input<KeyCode>>,
+    settings: Res<GameSettings>,
+    physics_config: Res<PhysicsConfig>,
+    replay: Res<ReplayPlayer>,
+    mut nudge: ResMut<Nudge>,
+){
+    if physics_config.nudge_speed <= 0.0 || nudge.cooldown > 0.0 || replay.active || !keys.any_pressed(NUDGE_MODIFIERS) {
+        return;
+    }
+    if keys.just_pressed(settings.keys.left_key()) {
+        nudge.requested = Some(-1.0);
+    } else if keys.just_pressed(settings.keys.right_key()) {
+        nudge.requested = Some(1.0);
+    }
+}
+
+fn apply_nudge(
+    time_step: Res<FixedTime>,
+    physics_config: Res<PhysicsConfig>,
+    mut nudge: ResMut<Nudge>,
+    mut replay: ResMut<ReplayPlayer>,
+    mut nudged_events: EventWriter<Nudged>,
+    mut fruit_query: Query<&mut Fruit>,
+){
+    let dt = time_step.period.as_secs_f32();
+    nudge.cooldown = (nudge.cooldown - dt).max(0.0);
+    let Some(direction) = nudge.requested.take().or_else(|| replay.nudge_due.take()) else {
+        return;
+    };
+    for mut fruit in fruit_query.iter_mut() {
+        // speed already going the nudge's way counts toward it
+        let along: f32 = fruit.get_vel(dt).x * direction;
+        let added: f32 = (physics_config.nudge_speed - along).clamp(0.0, physics_config.nudge_speed);
+        fruit.inc_vel(dt, Vec2::new(added * direction, 0.0));
+    }
+    nudge.cooldown = physics_config.nudge_cooldown;
+    nudged_events.send(Nudged { direction });
+}
+
+fn reset_nudge(mut nudge: ResMut<Nudge>) {
+    *nudge = Nudge::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FruitBundle, FruitTable};
+
+    // Nudges on, one fruit at rest and Shift+D just pressed
+    fn nudge_world() -> (World, Entity) {
+        let mut world: World = World::new();
+        let mut keys: Input<KeyCode> = Input::default();
+        keys.press(KeyCode::ShiftLeft);
+        keys.press(KeyCode::D);
+        world.insert_resource(keys);
+        world.init_resource::<GameSettings>();
+        world.insert_resource(PhysicsConfig { nudge_speed: 100.0, ..default() });
+        world.insert_resource(FixedTime::new_from_secs(0.01));
+        world.init_resource::<Nudge>();
+        world.init_resource::<ReplayPlayer>();
+        world.init_resource::<Events<Nudged>>();
+        let fruit: Entity = world.spawn(FruitBundle::new(0, 0, Vec2::ZERO, &FruitTable::default(), Handle::default()).fruit).id();
+        return (world, fruit);
+    }
+
+    // The fruit's sideways speed after a frame and a step, and the nudges sent for recording
+    fn step(world: &mut World, fruit: Entity) -> (f32, Vec<f32>) {
+        let mut schedule = Schedule::default();
+        schedule.add_systems((request_nudge, apply_nudge).chain());
+        schedule.run(world);
+        let speed: f32 = world.get::<Fruit>(fruit).unwrap().get_vel(0.01).x;
+        let nudges: Vec<f32> = world.resource_mut::<Events<Nudged>>().drain().map(|nudged| nudged.direction).collect();
+        return (speed, nudges);
+    }
+
+    #[test]
+    fn a_replay_nudges_only_when_its_recording_did() {
+        let (mut world, fruit) = nudge_world();
+        let (speed, nudges) = step(&mut world, fruit);
+        assert!((speed - 100.0).abs() < 1e-2);
+        assert_eq!(nudges, vec![1.0]);
+
+        let (mut world, fruit) = nudge_world();
+        world.resource_mut::<ReplayPlayer>().active = true;
+        let (speed, nudges) = step(&mut world, fruit);
+        assert_eq!(speed, 0.0);
+        assert!(nudges.is_empty());
+
+        world.resource_mut::<ReplayPlayer>().nudge_due = Some(-1.0);
+        let (speed, nudges) = step(&mut world, fruit);
+        assert!((speed + 100.0).abs() < 1e-2);
+        assert_eq!(nudges, vec![-1.0]);
+    }
+}
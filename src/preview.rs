@@ -0,0 +1,98 @@
+// Merge preview, a tutorial aid (F6): when two same-group fruits are about to touch, a
+// translucent ghost of the fruit they'll merge into is shown at their midpoint.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::{Bomb, Fruit, FruitAssets, FruitTable};
+
+const PREVIEW_KEY: KeyCode = KeyCode::F6;
+const GHOST_RANGE: f32 = 15.0; // gap between two fruits below which their merge is previewed
+const GHOST_ALPHA: f32 = 0.35;
+const GHOST_Z: f32 = 0.5; // above fruits and their decals
+
+#[derive(Resource)]
+pub struct MergePreview {
+    pub enabled: bool,
+}
+
+impl Default for MergePreview {
+    fn default() -> Self {
+        MergePreview { enabled: true }
+    }
+}
+
+// Preview of the merge of the two fruits with these ids, lower id first
+#[derive(Component)]
+struct Ghost {
+    pair: (u32, u32),
+}
+
+pub struct PreviewPlugin;
+
+impl Plugin for PreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MergePreview>()
+            .add_systems(Update, (toggle_preview, update_ghosts).chain());
+    }
+}
+
+fn toggle_preview(
+    input: Res<Input<KeyCode>>,
+    mut preview: ResMut<MergePreview>,
+){
+    if input.just_pressed(PREVIEW_KEY) {
+        preview.enabled = !preview.enabled;
+    }
+}
+
+// Ghosts are matched to pairs by fruit id, so one follows its pair around, and disappears
+// as soon as the pair merges (the fruits are gone) or drifts apart
+fn update_ghosts(
+    mut commands: Commands,
+    preview: Res<MergePreview>,
+    fruit_table: Res<FruitTable>,
+    fruit_assets: Res<FruitAssets>,
+    fruit_query: Query<&Fruit, Without<Bomb>>,
+    mut ghost_query: Query<(Entity, &Ghost, &mut Transform)>,
+){
+    // (result group, midpoint) of every near-merge pair
+    let mut near: HashMap<(u32, u32), (u8, Vec2)> = HashMap::new();
+    if preview.enabled {
+        let fruits: Vec<&Fruit> = fruit_query.iter().collect();
+        for i in 0..fruits.len() {
+            for j in (i+1)..fruits.len() {
+                let (a, b) = (fruits[i], fruits[j]);
+                if a.group != b.group || fruit_table.is_top(a.group) {
+                    continue;
+                }
+                if (b.pos - a.pos).length() - a.radius - b.radius < GHOST_RANGE {
+                    near.insert((a.id.min(b.id), a.id.max(b.id)), (a.group + 1, (a.pos + b.pos) / 2.0));
+                }
+            }
+        }
+    }
+
+    for (entity, ghost, mut transform) in ghost_query.iter_mut() {
+        match near.remove(&ghost.pair) {
+            Some((_, midpoint)) => transform.translation = midpoint.extend(GHOST_Z),
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+    // whatever is left has no ghost yet
+    for (pair, (group, midpoint)) in near {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(2.0*fruit_table.get(group).radius)),
+                    color: fruit_table.color(group, GHOST_ALPHA),
+                    ..default()
+                },
+                texture: fruit_assets.texture(group),
+                transform: Transform::from_translation(midpoint.extend(GHOST_Z)),
+                ..default()
+            },
+            Ghost { pair },
+        ));
+    }
+}
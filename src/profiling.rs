@@ -0,0 +1,47 @@
+// Custom diagnostics for correlating frame drops with the board, shown in the stress overlay
+// next to the FPS. The physics systems only bump PhysicsCounters; they're turned into
+// measurements once per frame here.
+
+use bevy::diagnostic::{Diagnostic, DiagnosticId, Diagnostics, DiagnosticsStore};
+use bevy::prelude::*;
+
+use crate::{Fruit, PhysicsCounters};
+
+pub const COLLISION_PAIRS: DiagnosticId = DiagnosticId::from_u128(0x6f0c1c3e8d2a4b7f9e51a2c4d3b6e801);
+pub const MERGES_PER_SECOND: DiagnosticId = DiagnosticId::from_u128(0x6f0c1c3e8d2a4b7f9e51a2c4d3b6e802);
+pub const ACTIVE_FRUITS: DiagnosticId = DiagnosticId::from_u128(0x6f0c1c3e8d2a4b7f9e51a2c4d3b6e803);
+
+const DIAGNOSTIC_HISTORY: usize = 20; // frames averaged by smoothed()
+
+pub struct ProfilingPlugin;
+
+impl Plugin for ProfilingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, register_diagnostics)
+            .add_systems(Update, record_diagnostics);
+    }
+}
+
+fn register_diagnostics(mut diagnostics: ResMut<DiagnosticsStore>) {
+    diagnostics.add(Diagnostic::new(COLLISION_PAIRS, "collision_pairs_tested", DIAGNOSTIC_HISTORY));
+    diagnostics.add(Diagnostic::new(MERGES_PER_SECOND, "merges_per_second", DIAGNOSTIC_HISTORY));
+    diagnostics.add(Diagnostic::new(ACTIVE_FRUITS, "active_fruits", DIAGNOSTIC_HISTORY));
+}
+
+// Counts cover every FixedUpdate step since the previous frame
+fn record_diagnostics(
+    time: Res<Time>,
+    fruit_query: Query<(), With<Fruit>>,
+    mut counters: ResMut<PhysicsCounters>,
+    mut diagnostics: Diagnostics,
+){
+    let pairs: f64 = counters.pairs_tested as f64;
+    let merges: f64 = counters.merges as f64;
+    let delta: f64 = time.delta_seconds_f64();
+    diagnostics.add_measurement(COLLISION_PAIRS, || pairs);
+    if delta > 0.0 {
+        diagnostics.add_measurement(MERGES_PER_SECOND, || merges / delta);
+    }
+    diagnostics.add_measurement(ACTIVE_FRUITS, || fruit_query.iter().count() as f64);
+    *counters = PhysicsCounters::default();
+}
@@ -0,0 +1,336 @@
+// Replays. `--record <file>` saves the RNG seed, every drop (simulation step, x and the
+// sideways speed of a throw or flick), every nudge and every gravity well opened as the game is
+// played; `--replay <file>` reseeds the RNG
+// and re-injects them at the same steps, reproducing the run exactly as long as the
+// settings match. Live input is ignored while a replay is playing.
+//
+// File format, one entry per line:
+//   seed <u64>
+//   drop <step> <x> <launch>
+//   nudge <step> <direction>
+//   well <step> <x> <y>
+// A drop without a launch, from before throws were recorded, falls straight down.
+
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::step::Simulation;
+use crate::{apply_collisions, input_handler, FruitDropped, Player, Scoreboard};
+
+// Simulation steps run so far, the clock replays are timed by
+#[derive(Resource, Default)]
+pub struct StepCounter(pub u64);
+
+// The pile was nudged, sent on the step it happens
+#[derive(Event)]
+pub struct Nudged {
+    pub direction: f32, // -1.0 or 1.0
+}
+
+// A gravity well opened at `pos`, sent on the step it opens
+#[derive(Event)]
+pub struct WellOpened {
+    pub pos: Vec2,
+}
+
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    path: Option<PathBuf>, // None while not recording
+    contents: String,
+}
+
+impl ReplayRecorder {
+    pub fn start(&mut self, path: PathBuf, seed: u64) {
+        self.path = Some(path);
+        self.contents = format!("seed {}\n", seed);
+    }
+
+    pub fn recording(&self) -> bool {
+        return self.path.is_some();
+    }
+
+    // What was recorded stays on disk, it's written as it goes
+    pub fn stop(&mut self) {
+        self.path = None;
+        self.contents.clear();
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ReplayPlayer {
+    pub active: bool,
+    pub drop_due: bool, // consumed by input_handler, like a held Space
+    pub drop_launch: f32, // px/s sideways for the due drop
+    pub nudge_due: Option<f32>, // direction, consumed by apply_nudge like Shift with a move key
+    pub well_due: Option<Vec2>, // consumed by the gravity well toy, like a click
+    drops: VecDeque<(u64, f32, f32)>, // (step, x, launch), in order
+    nudges: VecDeque<(u64, f32)>, // (step, direction), in order
+    wells: VecDeque<(u64, Vec2)>, // (step, pos), in order
+}
+
+impl ReplayPlayer {
+    // Returns the seed the recording was made with
+    pub fn load(&mut self, path: &str) -> Result<u64, String> {
+        let contents: String = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut seed: Option<u64> = None;
+        self.drops.clear();
+        self.nudges.clear();
+        self.wells.clear();
+        for (number, line) in contents.lines().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let parsed: Option<()> = match fields.as_slice() {
+                ["seed", value] => value.parse().ok().map(|value| seed = Some(value)),
+                ["drop", step, x] => step.parse().ok().zip(x.parse().ok())
+                    .map(|(step, x)| self.drops.push_back((step, x, 0.0))),
+                ["drop", step, x, launch] => step.parse().ok().zip(x.parse().ok()).zip(launch.parse().ok())
+                    .map(|((step, x), launch)| self.drops.push_back((step, x, launch))),
+                ["nudge", step, direction] => step.parse().ok().zip(direction.parse().ok())
+                    .map(|(step, direction)| self.nudges.push_back((step, direction))),
+                ["well", step, x, y] => step.parse().ok().zip(x.parse().ok()).zip(y.parse().ok())
+                    .map(|((step, x), y)| self.wells.push_back((step, Vec2::new(x, y)))),
+                [] => Some(()),
+                _ => None,
+            };
+            if parsed.is_none() {
+                return Err(format!("line {}: could not read \"{}\"", number + 1, line));
+            }
+        }
+        self.active = true;
+        return seed.ok_or("no seed".to_string());
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+        self.drop_due = false;
+        self.drop_launch = 0.0;
+        self.nudge_due = None;
+        self.well_due = None;
+        self.drops.clear();
+        self.nudges.clear();
+        self.wells.clear();
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StepCounter>()
+            .init_resource::<ReplayRecorder>()
+            .init_resource::<ReplayPlayer>()
+            .add_event::<Nudged>()
+            .add_event::<WellOpened>()
+            .add_systems(FixedUpdate, (
+                count_step,
+                play_replay,
+            ).chain().before(input_handler).in_set(Simulation))
+            // nudges and wells happen before the collisions, so by then the step's actions all have
+            .add_systems(FixedUpdate, record_actions.after(apply_collisions).in_set(Simulation));
+    }
+}
+
+fn count_step(mut step_counter: ResMut<StepCounter>) {
+    step_counter.0 += 1;
+}
+
+fn play_replay(
+    step_counter: Res<StepCounter>,
+    scoreboard: Res<Scoreboard>,
+    mut replay: ResMut<ReplayPlayer>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+){
+    if !replay.active {
+        return;
+    }
+    if let Some(&(step, x, launch)) = replay.drops.front() {
+        if step == step_counter.0 {
+            player_query.single_mut().translation.x = x;
+            replay.drop_due = true;
+            replay.drop_launch = launch;
+            replay.drops.pop_front();
+        }
+    }
+    if let Some(&(step, direction)) = replay.nudges.front() {
+        if step == step_counter.0 {
+            replay.nudge_due = Some(direction);
+            replay.nudges.pop_front();
+        }
+    }
+    if let Some(&(step, pos)) = replay.wells.front() {
+        if step == step_counter.0 {
+            replay.well_due = Some(pos);
+            replay.wells.pop_front();
+        }
+    }
+    let played: bool = replay.drops.is_empty() && replay.nudges.is_empty() && replay.wells.is_empty();
+    if played && !replay.drop_due && replay.nudge_due.is_none() && replay.well_due.is_none() {
+        info!("Replay finished at step {} with a score of {}", step_counter.0, scoreboard.score);
+        replay.active = false;
+    }
+}
+
+fn record_actions(
+    step_counter: Res<StepCounter>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut dropped_events: EventReader<FruitDropped>,
+    mut nudged_events: EventReader<Nudged>,
+    mut opened_events: EventReader<WellOpened>,
+){
+    let Some(path) = recorder.path.clone() else {
+        dropped_events.clear();
+        nudged_events.clear();
+        opened_events.clear();
+        return;
+    };
+    let mut changed: bool = false;
+    for dropped in dropped_events.iter() {
+        recorder.contents += &format!("drop {} {} {}\n", step_counter.0, dropped.x, dropped.launch);
+        changed = true;
+    }
+    for nudged in nudged_events.iter() {
+        recorder.contents += &format!("nudge {} {}\n", step_counter.0, nudged.direction);
+        changed = true;
+    }
+    for opened in opened_events.iter() {
+        recorder.contents += &format!("well {} {} {}\n", step_counter.0, opened.pos.x, opened.pos.y);
+        changed = true;
+    }
+    // rewritten on every entry, so a crash or a closed window still leaves a usable replay
+    if changed {
+        if let Err(err) = fs::write(&path, &recorder.contents) {
+            warn!("Could not write replay {}: {}", path.display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BoardSnapshot;
+    use crate::settle::{headless_game, settle_board};
+
+    // Every fruit's id, group and position, and the score
+    fn outcome(app: &mut App) -> (Vec<(u32, u8, Vec2)>, u32) {
+        let fruits = BoardSnapshot::from_world(&mut app.world)
+            .fruits
+            .iter()
+            .map(|fruit| (fruit.id, fruit.group, fruit.pos))
+            .collect();
+        return (fruits, app.world.resource::<Scoreboard>().score);
+    }
+
+    #[test]
+    fn a_recorded_run_replays_to_the_same_score_and_board() {
+        let path: PathBuf = std::env::temp_dir().join("alpha_suika_replay_run_test.txt");
+        let mut recorded: App = headless_game(Some(9));
+        recorded.world.resource_mut::<ReplayRecorder>().start(path.clone(), 9);
+        recorded.world.run_schedule(Startup);
+        for x in [0.0, -150.0, 150.0, 0.0, -60.0, 60.0, 0.0, -150.0, 150.0, 0.0] {
+            let mut player_query = recorded.world.query_filtered::<&mut Transform, With<Player>>();
+            player_query.single_mut(&mut recorded.world).translation.x = x;
+            recorded.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+            recorded.world.run_schedule(FixedUpdate);
+            recorded.world.resource_mut::<Input<KeyCode>>().release(KeyCode::Space);
+            settle_board(&mut recorded.world, 40);
+        }
+        settle_board(&mut recorded.world, 300);
+        recorded.world.resource_mut::<ReplayRecorder>().stop();
+        let steps: u64 = recorded.world.resource::<StepCounter>().0;
+
+        // a different seed to start with, so only the replay's own seed can make it match
+        let mut replayed: App = headless_game(Some(1));
+        let seed: Result<u64, String> = replayed.world.resource_mut::<ReplayPlayer>().load(path.to_str().unwrap());
+        fs::remove_file(&path).ok();
+        assert_eq!(seed, Ok(9));
+        assert_eq!(replayed.world.resource::<ReplayPlayer>().drops.len(), 10);
+        seed_game(&mut replayed, 9);
+        replayed.world.run_schedule(Startup);
+        settle_board(&mut replayed.world, steps as usize);
+
+        let (recorded_fruits, recorded_score) = outcome(&mut recorded);
+        let (replayed_fruits, replayed_score) = outcome(&mut replayed);
+        assert_eq!(replayed_score, recorded_score);
+        assert_eq!(replayed_fruits, recorded_fruits);
+    }
+
+    #[test]
+    fn a_recording_plays_back_the_same_drops() {
+        let path: PathBuf = std::env::temp_dir().join("alpha_suika_replay_test.txt");
+        let mut world: World = World::new();
+        world.insert_resource(StepCounter(12));
+        world.init_resource::<ReplayRecorder>();
+        world.resource_mut::<ReplayRecorder>().start(path.clone(), 42);
+        world.init_resource::<Events<FruitDropped>>();
+        world.resource_mut::<Events<FruitDropped>>().send(FruitDropped { group: 1, bomb: false, x: -35.5, launch: 80.0 });
+        world.init_resource::<Events<Nudged>>();
+        world.init_resource::<Events<WellOpened>>();
+        let mut record = Schedule::default();
+        record.add_systems(record_actions);
+        record.run(&mut world);
+
+        world.insert_resource(StepCounter(11));
+        world.insert_resource(Scoreboard { score: 0 });
+        world.init_resource::<ReplayPlayer>();
+        let seed: Result<u64, String> = world.resource_mut::<ReplayPlayer>().load(path.to_str().unwrap());
+        fs::remove_file(&path).ok();
+        assert_eq!(seed, Ok(42));
+        let player: Entity = world.spawn((Transform::default(), Player)).id();
+        let mut play = Schedule::default();
+        play.add_systems((count_step, play_replay).chain());
+
+        play.run(&mut world);
+        let replay = world.resource::<ReplayPlayer>();
+        assert!(replay.drop_due && replay.drop_launch == 80.0);
+        assert_eq!(world.get::<Transform>(player).unwrap().translation.x, -35.5);
+    }
+
+    #[test]
+    fn drops_recorded_before_launches_fall_straight() {
+        let path: PathBuf = std::env::temp_dir().join("alpha_suika_old_replay_test.txt");
+        fs::write(&path, "seed 7\ndrop 3 10\n").unwrap();
+        let mut replay: ReplayPlayer = ReplayPlayer::default();
+        let seed: Result<u64, String> = replay.load(path.to_str().unwrap());
+        fs::remove_file(&path).ok();
+        assert_eq!(seed, Ok(7));
+        assert_eq!(replay.drops.front(), Some(&(3, 10.0, 0.0)));
+    }
+
+    #[test]
+    fn recorded_nudges_and_wells_happen_again_on_their_step() {
+        let path: PathBuf = std::env::temp_dir().join("alpha_suika_well_replay_test.txt");
+        let mut world: World = World::new();
+        world.insert_resource(StepCounter(12));
+        world.init_resource::<ReplayRecorder>();
+        world.resource_mut::<ReplayRecorder>().start(path.clone(), 5);
+        world.init_resource::<Events<FruitDropped>>();
+        world.init_resource::<Events<Nudged>>();
+        world.resource_mut::<Events<Nudged>>().send(Nudged { direction: -1.0 });
+        world.init_resource::<Events<WellOpened>>();
+        world.resource_mut::<Events<WellOpened>>().send(WellOpened { pos: Vec2::new(-30.0, 40.5) });
+        let mut record = Schedule::default();
+        record.add_systems(record_actions);
+        record.run(&mut world);
+
+        world.insert_resource(StepCounter(10));
+        world.insert_resource(Scoreboard { score: 0 });
+        world.init_resource::<ReplayPlayer>();
+        let seed: Result<u64, String> = world.resource_mut::<ReplayPlayer>().load(path.to_str().unwrap());
+        fs::remove_file(&path).ok();
+        assert_eq!(seed, Ok(5));
+        world.spawn((Transform::default(), Player));
+        let mut play = Schedule::default();
+        play.add_systems((count_step, play_replay).chain());
+
+        play.run(&mut world);
+        let replay = world.resource::<ReplayPlayer>();
+        assert_eq!((replay.nudge_due, replay.well_due), (None, None));
+        play.run(&mut world);
+        let replay = world.resource::<ReplayPlayer>();
+        assert_eq!(replay.nudge_due, Some(-1.0));
+        assert_eq!(replay.well_due, Some(Vec2::new(-30.0, 40.5)));
+        // still playing until the nudge and the well have happened
+        assert!(replay.active);
+    }
+}
@@ -0,0 +1,297 @@
+// Quick restart (R). On the game over and win screens it's immediate. During play it needs confirming,
+// by holding R for RESTART_HOLD_TIME or pressing it twice within RESTART_DOUBLE_PRESS_TIME, so a
+// stray key press can't throw away a good run. A restart starts a fresh seed.
+// Retry (T, on the game over and win screens only) starts the run's own seed again instead, so
+// the same fruits come in the same order for a fair rematch. The game over screen has a button
+// for each.
+// Every run starts through OnEnter(Playing), where each module resets its own per-run state. A
+// restart during play doesn't change the state, so it runs that schedule itself. A restart also
+// ends any replay being recorded or played, which only covers the run it started with.
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::replay::{ReplayPlayer, ReplayRecorder};
+use crate::win::{RunClock, WinCondition};
+use crate::{
+    held_color, BiggestFruit, ComboTracker, Fruit, FruitIterator, FruitTable, GameRng, GameSeed,
+    GameState, Player, Scoreboard,
+};
+
+const RESTART_KEY: KeyCode = KeyCode::R;
+const RETRY_KEY: KeyCode = KeyCode::T;
+const RESTART_HOLD_TIME: f32 = 1.0; // seconds
+const RESTART_DOUBLE_PRESS_TIME: f32 = 0.5; // seconds between the two presses
+
+// How a restart during play is confirmed
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum RestartConfirm {
+    Hold,
+    DoublePress,
+    Off, // restart on the first press
+}
+
+#[derive(Resource)]
+pub struct RestartConfig {
+    pub confirm: RestartConfirm,
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        RestartConfig {
+            confirm: RestartConfirm::Hold,
+        }
+    }
+}
+
+// Which seed a restart starts from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RestartSeed {
+    New, // fresh from entropy
+    Retry, // the seed of the run that just ended
+    Code(u64), // a share code typed on the game over screen (see share.rs)
+}
+
+#[derive(Event)]
+pub struct RestartRequested(pub RestartSeed);
+
+// Restarts when clicked
+#[derive(Component)]
+pub struct RestartButton(pub RestartSeed);
+
+#[derive(Default)]
+struct RestartInput {
+    held: f32, // seconds R has been held
+    hold_fired: bool, // this hold already restarted, it has to be let go before it can again
+    since_press: Option<f32>, // seconds since the last press, None once it can't count as the first of two
+}
+
+pub struct RestartPlugin;
+
+impl Plugin for RestartPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RestartConfig>()
+            .add_event::<RestartRequested>()
+            .add_systems(Update, (request_restart, press_restart_buttons, restart_game).chain());
+    }
+}
+
+fn restart_confirmed(input: &Input<KeyCode>, confirm: RestartConfirm, state: &mut RestartInput, dt: f32) -> bool {
+    let pressed: bool = input.just_pressed(RESTART_KEY);
+    state.held = if input.pressed(RESTART_KEY) { state.held + dt } else { 0.0 };
+    match confirm {
+        RestartConfirm::Off => return pressed,
+        RestartConfirm::Hold => {
+            // only once per hold, not every frame after the threshold
+            if state.held == 0.0 {
+                state.hold_fired = false;
+            }
+            if state.hold_fired || state.held < RESTART_HOLD_TIME {
+                return false;
+            }
+            state.hold_fired = true;
+            return true;
+        }
+        RestartConfirm::DoublePress => {
+            state.since_press = state.since_press.map(|since| since + dt).filter(|since| *since <= RESTART_DOUBLE_PRESS_TIME);
+            if !pressed {
+                return false;
+            }
+            if state.since_press.is_some() {
+                state.since_press = None;
+                return true;
+            }
+            state.since_press = Some(0.0);
+            return false;
+        }
+    }
+}
+
+fn request_restart(
+    time: Res<Time>,
+    input: Res<Input<KeyCode>>,
+    config: Res<RestartConfig>,
+    game_state: Res<State<GameState>>,
+    mut input_state: Local<RestartInput>,
+    mut restart_events: EventWriter<RestartRequested>,
+){
+    let playing: bool = *game_state.get() == GameState::Playing;
+    let confirm: RestartConfirm = if playing { config.confirm } else { RestartConfirm::Off };
+    if restart_confirmed(&input, confirm, &mut input_state, time.delta_seconds()) {
+        restart_events.send(RestartRequested(RestartSeed::New));
+    }
+    // a retry throws away the run too, so it's only offered once the run is over
+    if !playing && input.just_pressed(RETRY_KEY) {
+        restart_events.send(RestartRequested(RestartSeed::Retry));
+    }
+}
+
+fn press_restart_buttons(
+    button_query: Query<(&Interaction, &RestartButton), Changed<Interaction>>,
+    mut restart_events: EventWriter<RestartRequested>,
+){
+    for (interaction, button) in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            restart_events.send(RestartRequested(button.0));
+        }
+    }
+}
+
+fn restart_game(
+    mut commands: Commands,
+    fruit_table: Res<FruitTable>,
+    seed: Res<GameSeed>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut biggest_fruit: ResMut<BiggestFruit>,
+    mut combo: ResMut<ComboTracker>,
+    mut win: ResMut<WinCondition>,
+    mut clock: ResMut<RunClock>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut replay: ResMut<ReplayPlayer>,
+    game_state: Res<State<GameState>>,
+    mut restart_events: EventReader<RestartRequested>,
+    fruit_query: Query<Entity, With<Fruit>>,
+    mut player_query: Query<(&mut FruitIterator, &mut Sprite), With<Player>>,
+){
+    // a key and a button in the same frame, the last one wins
+    let Some(RestartRequested(restart_seed)) = restart_events.iter().last() else {
+        return;
+    };
+
+    for entity in fruit_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    scoreboard.score = 0;
+    *biggest_fruit = BiggestFruit::default();
+    *combo = ComboTracker::default();
+    win.made = 0;
+    clock.0 = Duration::ZERO;
+    recorder.stop();
+    replay.stop();
+
+    let seed: u64 = match restart_seed {
+        RestartSeed::New => rand::random(),
+        RestartSeed::Retry => seed.0,
+        RestartSeed::Code(code) => *code,
+    };
+    let mut rng: GameRng = GameRng(StdRng::seed_from_u64(seed));
+    for (mut fruit_iterator, mut sprite) in player_query.iter_mut() {
+        fruit_iterator.next_is_bomb = false;
+        fruit_iterator.next_group = fruit_table.random_spawn_group(&mut rng);
+        sprite.custom_size = Some(Vec2::splat(2.0*fruit_table.get(fruit_iterator.next_group).radius));
+        sprite.color = held_color(&fruit_iterator, &fruit_table);
+    }
+    commands.insert_resource(GameSeed(seed));
+    commands.insert_resource(rng);
+    if *game_state.get() == GameState::Playing {
+        // setting the state it's already in is a no-op, so the per-run resets wouldn't run
+        commands.add(|world: &mut World| {
+            let _ = world.try_run_schedule(OnEnter(GameState::Playing));
+        });
+    } else {
+        next_state.set(GameState::Playing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A finished run made with seed 42, after restart_game handles a restart from `restart_seed`
+    fn restarted(restart_seed: RestartSeed) -> World {
+        let mut world: World = World::new();
+        world.insert_resource(FruitTable::default());
+        world.insert_resource(GameSeed(42));
+        world.insert_resource(State::new(GameState::GameOver));
+        world.init_resource::<NextState<GameState>>();
+        world.insert_resource(Scoreboard { score: 1234 });
+        world.init_resource::<BiggestFruit>();
+        world.init_resource::<ComboTracker>();
+        world.init_resource::<WinCondition>();
+        world.init_resource::<RunClock>();
+        world.init_resource::<ReplayRecorder>();
+        world.init_resource::<ReplayPlayer>();
+        world.init_resource::<Events<RestartRequested>>();
+        world.spawn((Player, FruitIterator { next_id: 7, next_group: 0, next_is_bomb: true }, Sprite::default()));
+        world.resource_mut::<Events<RestartRequested>>().send(RestartRequested(restart_seed));
+        let mut schedule = Schedule::default();
+        schedule.add_systems(restart_game);
+        schedule.run(&mut world);
+        return world;
+    }
+
+    // The next spawn groups the restarted run's GameRng hands out
+    fn next_groups(world: &mut World) -> Vec<u8> {
+        let fruit_table: FruitTable = FruitTable::default();
+        let mut rng = world.resource_mut::<GameRng>();
+        return (0..5).map(|_| fruit_table.random_spawn_group(&mut rng)).collect();
+    }
+
+    #[test]
+    fn retry_replays_the_seed_and_restart_picks_a_new_one() {
+        let mut world: World = restarted(RestartSeed::Retry);
+        assert_eq!(world.resource::<GameSeed>().0, 42);
+        assert_eq!(world.resource::<Scoreboard>().score, 0);
+        assert_eq!(world.resource::<NextState<GameState>>().0, Some(GameState::Playing));
+
+        let fruit_table: FruitTable = FruitTable::default();
+        let mut expected_rng: GameRng = GameRng(StdRng::seed_from_u64(42));
+        let first_group: u8 = fruit_table.random_spawn_group(&mut expected_rng);
+        let fruit_iterator = world.query::<&FruitIterator>().single(&world);
+        assert_eq!(fruit_iterator.next_group, first_group);
+        assert!(!fruit_iterator.next_is_bomb);
+
+        // every retry draws the same fruits after the first
+        let expected: Vec<u8> = (0..5).map(|_| fruit_table.random_spawn_group(&mut expected_rng)).collect();
+        assert_eq!(next_groups(&mut world), expected);
+        assert_eq!(next_groups(&mut restarted(RestartSeed::Retry)), expected);
+
+        // a new seed is whatever restart_game picked, and GameRng follows it
+        let mut world: World = restarted(RestartSeed::New);
+        let mut new_rng: GameRng = GameRng(StdRng::seed_from_u64(world.resource::<GameSeed>().0));
+        fruit_table.random_spawn_group(&mut new_rng);
+        let expected: Vec<u8> = (0..5).map(|_| fruit_table.random_spawn_group(&mut new_rng)).collect();
+        assert_eq!(next_groups(&mut world), expected);
+    }
+
+    #[test]
+    fn holding_r_restarts_once_per_hold() {
+        let mut state: RestartInput = RestartInput::default();
+        let mut input: Input<KeyCode> = Input::default();
+        let frame: f32 = 1.0 / 60.0;
+        for _ in 0..2 {
+            input.press(RESTART_KEY);
+            let mut restarts: Vec<usize> = Vec::new();
+            // three times as long as it takes
+            for frame_index in 0..(3.0 * RESTART_HOLD_TIME / frame) as usize {
+                if restart_confirmed(&input, RestartConfirm::Hold, &mut state, frame) {
+                    restarts.push(frame_index);
+                }
+                input.clear();
+            }
+            assert_eq!(restarts.len(), 1, "restarted on frames {:?}", restarts);
+            assert!(((restarts[0] + 1) as f32 * frame - RESTART_HOLD_TIME).abs() <= frame);
+            // letting go and holding again is a new hold
+            input.release(RESTART_KEY);
+            assert!(!restart_confirmed(&input, RestartConfirm::Hold, &mut state, frame));
+            input.clear();
+        }
+    }
+
+    #[test]
+    fn double_press_needs_two_presses_close_together() {
+        let mut state: RestartInput = RestartInput::default();
+        let mut input: Input<KeyCode> = Input::default();
+        input.press(RESTART_KEY);
+        assert!(!restart_confirmed(&input, RestartConfirm::DoublePress, &mut state, 0.1));
+        input.release(RESTART_KEY);
+        input.clear();
+        assert!(!restart_confirmed(&input, RestartConfirm::DoublePress, &mut state, 0.1));
+        input.press(RESTART_KEY);
+        assert!(restart_confirmed(&input, RestartConfirm::DoublePress, &mut state, 0.1));
+    }
+}
@@ -0,0 +1,106 @@
+// Practice sandbox (F8 or --sandbox): number keys pick a group, left click spawns that fruit at
+// the cursor. Spawned fruits are ordinary fruits, they just skip the player and FruitIterator's
+// group sequence. A run with hand-placed fruits isn't the player's own, so it isn't saved on exit.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::{
+    clamp_player_x, spawn_fruit_bundle, ArenaConfig, FruitAssets, FruitBundle, FruitIterator, FruitTable, GameState,
+};
+
+const SANDBOX_KEY: KeyCode = KeyCode::F8;
+// 1 picks the smallest fruit, 0 the tenth
+const GROUP_KEYS: [KeyCode; 10] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+];
+
+#[derive(Resource, Default)]
+pub struct Sandbox {
+    pub active: bool,
+    pub placed: bool, // a fruit was placed this run
+    group: u8,
+}
+
+pub struct SandboxPlugin;
+
+impl Plugin for SandboxPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Sandbox>()
+            .add_systems(Update, sandbox_input)
+            .add_systems(OnEnter(GameState::Playing), reset_placed);
+    }
+}
+
+pub fn sandbox_active(sandbox: Res<Sandbox>) -> bool {
+    return sandbox.active;
+}
+
+fn reset_placed(mut sandbox: ResMut<Sandbox>) {
+    sandbox.placed = false;
+}
+
+fn sandbox_input(
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    fruit_table: Res<FruitTable>,
+    fruit_assets: Res<FruitAssets>,
+    arena_config: Res<ArenaConfig>,
+    mut sandbox: ResMut<Sandbox>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut iterator_query: Query<&mut FruitIterator>,
+    mut commands: Commands,
+){
+    if keys.just_pressed(SANDBOX_KEY) {
+        sandbox.active = !sandbox.active;
+    }
+    if !sandbox.active {
+        return;
+    }
+
+    for (group, key) in GROUP_KEYS.iter().enumerate() {
+        if keys.just_pressed(*key) && group < fruit_table.len() {
+            sandbox.group = group as u8;
+        }
+    }
+
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    else {
+        return;
+    };
+
+    // keep the new fruit inside the arena so it doesn't get shoved through a wall
+    let radius: f32 = fruit_table.get(sandbox.group).radius;
+    let pos: Vec2 = Vec2::new(
+        clamp_player_x(cursor.x, radius),
+        cursor.y.clamp(arena_config.floor() + radius, arena_config.top_wall),
+    );
+    let mut fruit_iterator = iterator_query.single_mut();
+    spawn_fruit_bundle(&mut commands, &fruit_assets, FruitBundle::new(
+        fruit_iterator.next_id,
+        sandbox.group,
+        pos,
+        &fruit_table,
+        fruit_assets.texture(sandbox.group),
+    ));
+    fruit_iterator.next_id += 1;
+    sandbox.placed = true;
+}
@@ -0,0 +1,788 @@
+// In-game settings screen (F10). Opening it pauses the game. Every change is written straight
+// into the resource it belongs to and saved to SAVE_DIR, and the saved settings are applied
+// when the plugin is added, before any command line flags.
+//
+// Works without a mouse too: up and down move the focused row (wrapping), left and right step
+// its value, Enter steps it forward and Esc closes the screen.
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::audio::MasterVolume;
+use crate::difficulty::{CooldownCurve, DifficultyMode, DynamicDifficulty};
+use crate::graphics::{GraphicsSettings, MsaaLevel, RenderMode};
+use crate::locale::{Localization, DEFAULT_LANGUAGE, LANGUAGES};
+use crate::preview::MergePreview;
+use crate::restart::{RestartConfig, RestartConfirm};
+use crate::skin::{available_packs, SkinPack};
+use crate::step::{AutoPause, StepMode};
+use crate::theme::{Theme, ThemePreset};
+use crate::{
+    DropMode, FruitOutlines, GameSettings, KeyLayout, MergeEnabled, MergeMagnet, RenderInterpolation, PERFECT_DROP_WINDOW, SAVE_DIR,
+    TEXT_COLOR,
+};
+
+const SETTINGS_FILE: &str = "settings.ron";
+const SETTINGS_KEY: KeyCode = KeyCode::F10;
+const VOLUME_STEP: f32 = 0.1;
+
+const PANEL_COLOR: Color = Color::rgba(0.1, 0.1, 0.15, 0.9);
+const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.35);
+const BUTTON_HOVER_COLOR: Color = Color::rgb(0.35, 0.35, 0.5);
+const FOCUS_COLOR: Color = Color::rgb(1.0, 0.85, 0.3); // label of the row the keyboard is on
+const SETTINGS_FONT_SIZE: f32 = 24.0;
+const PANEL_PADDING: f32 = 20.0;
+const ROW_SPACING: f32 = 10.0;
+const LABEL_WIDTH: f32 = 180.0;
+const VALUE_WIDTH: f32 = 120.0;
+const BUTTON_SIZE: f32 = 36.0;
+
+// What the settings screen saves, one field per row
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct SavedSettings {
+    volume: f32,
+    difficulty: Option<DifficultyMode>, // None is off
+    cooldown_curve: CooldownCurve,
+    merge_magnet: bool,
+    theme: ThemePreset,
+    skin: Option<String>, // None is the generic fruit icon
+    merge_preview: bool,
+    outlines: bool,
+    interpolate_render: bool,
+    msaa: MsaaLevel,
+    vsync: bool,
+    render_mode: RenderMode,
+    drop_mode: DropMode,
+    keys: KeyLayout,
+    throw_mode: bool,
+    flick: bool,
+    time_attack: bool,
+    perfect_drop_window: f32, // 0.0 is off
+    save_on_exit: bool,
+    merging: bool, // false is the no-merge chaos mode
+    gutters: bool,
+    language: String,
+    auto_pause: bool,
+    resume_on_focus: bool,
+    restart_confirm: RestartConfirm,
+}
+
+impl Default for SavedSettings {
+    fn default() -> Self {
+        SavedSettings::capture(&CurrentSettings {
+            volume: &MasterVolume::default(),
+            difficulty: &DynamicDifficulty::default(),
+            magnet: &MergeMagnet::default(),
+            theme: &Theme::default(),
+            skin: &SkinPack::default(),
+            preview: &MergePreview::default(),
+            outlines: &FruitOutlines::default(),
+            interpolation: &RenderInterpolation::default(),
+            graphics: &GraphicsSettings::default(),
+            settings: &GameSettings::default(),
+            merge_enabled: &MergeEnabled::default(),
+            language: DEFAULT_LANGUAGE,
+            auto_pause: &AutoPause::default(),
+            restart: &RestartConfig::default(),
+        })
+    }
+}
+
+// The resources a SavedSettings is captured from
+struct CurrentSettings<'a> {
+    volume: &'a MasterVolume,
+    difficulty: &'a DynamicDifficulty,
+    magnet: &'a MergeMagnet,
+    theme: &'a Theme,
+    skin: &'a SkinPack,
+    preview: &'a MergePreview,
+    outlines: &'a FruitOutlines,
+    interpolation: &'a RenderInterpolation,
+    graphics: &'a GraphicsSettings,
+    settings: &'a GameSettings,
+    merge_enabled: &'a MergeEnabled,
+    language: &'a str,
+    auto_pause: &'a AutoPause,
+    restart: &'a RestartConfig,
+}
+
+impl SavedSettings {
+    fn capture(current: &CurrentSettings) -> SavedSettings {
+        let CurrentSettings {
+            volume, difficulty, magnet, theme, skin, preview, outlines, interpolation, graphics, settings, merge_enabled, language, auto_pause, restart,
+        } = current;
+        SavedSettings {
+            volume: volume.0,
+            difficulty: if difficulty.enabled { Some(difficulty.mode) } else { None },
+            cooldown_curve: difficulty.cooldown_curve,
+            merge_magnet: magnet.0,
+            theme: theme.preset,
+            skin: skin.name.clone(),
+            merge_preview: preview.enabled,
+            outlines: outlines.enabled,
+            interpolate_render: interpolation.enabled,
+            msaa: graphics.msaa,
+            vsync: graphics.vsync,
+            render_mode: graphics.render_mode,
+            drop_mode: settings.drop_mode,
+            keys: settings.keys,
+            throw_mode: settings.throw_mode,
+            flick: settings.flick,
+            time_attack: settings.time_attack,
+            perfect_drop_window: settings.perfect_drop_window,
+            save_on_exit: settings.save_on_exit,
+            merging: merge_enabled.0,
+            gutters: settings.gutters,
+            language: language.to_string(),
+            auto_pause: auto_pause.enabled,
+            resume_on_focus: auto_pause.resume_on_focus,
+            restart_confirm: restart.confirm,
+        }
+    }
+
+    fn apply(&self, world: &mut World) {
+        world.resource_mut::<MasterVolume>().0 = self.volume.clamp(0.0, 1.0);
+        let mut difficulty = world.resource_mut::<DynamicDifficulty>();
+        difficulty.enabled = self.difficulty.is_some();
+        if let Some(mode) = self.difficulty {
+            difficulty.mode = mode;
+        }
+        difficulty.cooldown_curve = self.cooldown_curve;
+        world.resource_mut::<MergeMagnet>().0 = self.merge_magnet;
+        world.resource_mut::<Theme>().preset = self.theme;
+        world.resource_mut::<SkinPack>().name = self.skin.clone();
+        world.resource_mut::<MergePreview>().enabled = self.merge_preview;
+        world.resource_mut::<FruitOutlines>().enabled = self.outlines;
+        world.resource_mut::<RenderInterpolation>().enabled = self.interpolate_render;
+        let mut graphics = world.resource_mut::<GraphicsSettings>();
+        graphics.msaa = self.msaa;
+        graphics.vsync = self.vsync;
+        graphics.render_mode = self.render_mode;
+        let mut settings = world.resource_mut::<GameSettings>();
+        settings.drop_mode = self.drop_mode;
+        settings.keys = self.keys;
+        settings.throw_mode = self.throw_mode;
+        settings.flick = self.flick;
+        settings.time_attack = self.time_attack;
+        settings.perfect_drop_window = self.perfect_drop_window.max(0.0);
+        settings.save_on_exit = self.save_on_exit;
+        settings.gutters = self.gutters;
+        world.resource_mut::<MergeEnabled>().0 = self.merging;
+        world.insert_resource(Localization::load(&self.language));
+        let mut auto_pause = world.resource_mut::<AutoPause>();
+        auto_pause.enabled = self.auto_pause;
+        auto_pause.resume_on_focus = self.resume_on_focus;
+        world.resource_mut::<RestartConfig>().confirm = self.restart_confirm;
+    }
+
+    fn graphics(&self) -> GraphicsSettings {
+        GraphicsSettings {
+            msaa: self.msaa,
+            vsync: self.vsync,
+            render_mode: self.render_mode,
+        }
+    }
+
+    // Missing or unreadable settings just mean the defaults
+    fn load() -> Option<SavedSettings> {
+        let contents: String = fs::read_to_string(Path::new(SAVE_DIR).join(SETTINGS_FILE)).ok()?;
+        match ron::from_str(&contents) {
+            Ok(saved) => return Some(saved),
+            Err(err) => {
+                warn!("Could not parse {}: {}", SETTINGS_FILE, err);
+                return None;
+            }
+        }
+    }
+
+    fn save(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                fs::create_dir_all(SAVE_DIR)
+                    .and_then(|_| fs::write(Path::new(SAVE_DIR).join(SETTINGS_FILE), contents))
+                    .map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("Could not save settings: {}", err);
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct SettingsMenu {
+    open: bool,
+    was_paused: bool, // restored on close, so opening settings from pause stays paused
+}
+
+// Row the keyboard is on, an index into Setting::ALL
+#[derive(Resource, Default)]
+struct MenuSelection {
+    row: usize,
+}
+
+#[derive(Component)]
+struct SettingsPanel;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    Volume,
+    Difficulty,
+    CooldownCurve,
+    MergeMagnet,
+    Theme,
+    Skin,
+    MergePreview,
+    Outlines,
+    Interpolation,
+    Msaa,
+    Vsync,
+    RenderMode,
+    DropMode,
+    Keys,
+    ThrowMode,
+    Flick,
+    Mode,
+    PerfectDrops,
+    Merging,
+    Gutters,
+    Language,
+    AutoPause,
+    RestartConfirm,
+    SaveOnExit,
+}
+
+impl Setting {
+    const ALL: [Setting; 24] = [
+        Setting::Volume,
+        Setting::Difficulty,
+        Setting::CooldownCurve,
+        Setting::MergeMagnet,
+        Setting::Theme,
+        Setting::Skin,
+        Setting::MergePreview,
+        Setting::Outlines,
+        Setting::Interpolation,
+        Setting::Msaa,
+        Setting::Vsync,
+        Setting::RenderMode,
+        Setting::DropMode,
+        Setting::Keys,
+        Setting::ThrowMode,
+        Setting::Flick,
+        Setting::Mode,
+        Setting::PerfectDrops,
+        Setting::Merging,
+        Setting::Gutters,
+        Setting::Language,
+        Setting::AutoPause,
+        Setting::RestartConfirm,
+        Setting::SaveOnExit,
+    ];
+
+    // Localization key of the row label
+    fn label(&self) -> &'static str {
+        match self {
+            Setting::Volume => "setting_volume",
+            Setting::Difficulty => "setting_difficulty",
+            Setting::CooldownCurve => "setting_cooldown_curve",
+            Setting::MergeMagnet => "setting_merge_magnet",
+            Setting::Theme => "setting_theme",
+            Setting::Skin => "setting_skin",
+            Setting::MergePreview => "setting_merge_preview",
+            Setting::Outlines => "setting_outlines",
+            Setting::Interpolation => "setting_interpolation",
+            Setting::Msaa => "setting_msaa",
+            Setting::Vsync => "setting_vsync",
+            Setting::RenderMode => "setting_render_mode",
+            Setting::DropMode => "setting_drop_mode",
+            Setting::Keys => "setting_keys",
+            Setting::ThrowMode => "setting_throw_mode",
+            Setting::Flick => "setting_flick",
+            Setting::Mode => "setting_mode",
+            Setting::PerfectDrops => "setting_perfect_drops",
+            Setting::Merging => "setting_merging",
+            Setting::Gutters => "setting_gutters",
+            Setting::Language => "setting_language",
+            Setting::AutoPause => "setting_auto_pause",
+            Setting::RestartConfirm => "setting_restart_confirm",
+            Setting::SaveOnExit => "setting_save_on_exit",
+        }
+    }
+}
+
+// Buttons either side of a value: -1 steps back, +1 forward. Options with no order just cycle.
+#[derive(Component)]
+struct SettingButton {
+    setting: Setting,
+    step: i32,
+}
+
+#[derive(Component)]
+struct SettingValue(Setting);
+
+// Text relabelled when the language changes
+#[derive(Component)]
+struct SettingLabel(Setting);
+
+#[derive(Component)]
+struct SettingsTitle;
+
+// For the primary window, which exists before the plugin applies the rest
+pub fn saved_graphics_settings() -> GraphicsSettings {
+    return SavedSettings::load().map(|saved| saved.graphics()).unwrap_or_default();
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(saved) = SavedSettings::load() {
+            saved.apply(&mut app.world);
+        }
+        app.init_resource::<SettingsMenu>()
+            .init_resource::<MenuSelection>()
+            .add_systems(Startup, spawn_settings_panel)
+            // before anything in Update sees the Esc press, close_on_esc in particular
+            .add_systems(PreUpdate, close_settings_on_escape.after(InputSystem))
+            .add_systems(Update, (
+                toggle_settings,
+                navigate_settings,
+                press_setting_buttons,
+                update_setting_values,
+                update_setting_labels,
+                highlight_selection,
+            ).chain());
+    }
+}
+
+fn spawn_settings_panel(
+    mut commands: Commands,
+    localization: Res<Localization>,
+){
+    let text_style = TextStyle {
+        font_size: SETTINGS_FONT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    let fixed_width = |width: f32| Style {
+        width: Val::Px(width),
+        justify_content: JustifyContent::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            SettingsPanel,
+        ))
+        .with_children(|root| {
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(PANEL_PADDING)),
+                    row_gap: Val::Px(ROW_SPACING),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                ..default()
+            })
+            .with_children(|panel| {
+                panel.spawn((
+                    TextBundle::from_section(localization.get("settings_title"), text_style.clone()),
+                    SettingsTitle,
+                ));
+                for setting in Setting::ALL {
+                    panel.spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            TextBundle::from_section(localization.get(setting.label()), text_style.clone())
+                                .with_style(Style {
+                                    width: Val::Px(LABEL_WIDTH),
+                                    ..default()
+                                }),
+                            SettingLabel(setting),
+                        ));
+                        for step in [-1, 1] {
+                            if step == 1 {
+                                row.spawn((
+                                    TextBundle::from_section("", text_style.clone()).with_style(fixed_width(VALUE_WIDTH)),
+                                    SettingValue(setting),
+                                ));
+                            }
+                            row.spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(BUTTON_SIZE),
+                                        height: Val::Px(BUTTON_SIZE),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                SettingButton { setting, step },
+                            ))
+                            .with_children(|button| {
+                                button.spawn(TextBundle::from_section(if step < 0 { "<" } else { ">" }, text_style.clone()));
+                            });
+                        }
+                    });
+                }
+            });
+        });
+}
+
+fn toggle_settings(
+    input: Res<Input<KeyCode>>,
+    mut menu: ResMut<SettingsMenu>,
+    mut step_mode: ResMut<StepMode>,
+    mut panel_query: Query<&mut Visibility, With<SettingsPanel>>,
+){
+    if !input.just_pressed(SETTINGS_KEY) {
+        return;
+    }
+    let open: bool = !menu.open;
+    set_settings_open(open, &mut menu, &mut step_mode, &mut panel_query);
+}
+
+fn close_settings_on_escape(
+    mut input: ResMut<Input<KeyCode>>,
+    mut menu: ResMut<SettingsMenu>,
+    mut step_mode: ResMut<StepMode>,
+    mut panel_query: Query<&mut Visibility, With<SettingsPanel>>,
+){
+    if !menu.open || !input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    // backing out of the menu shouldn't also quit the game
+    input.reset(KeyCode::Escape);
+    set_settings_open(false, &mut menu, &mut step_mode, &mut panel_query);
+}
+
+fn set_settings_open(
+    open: bool,
+    menu: &mut SettingsMenu,
+    step_mode: &mut StepMode,
+    panel_query: &mut Query<&mut Visibility, With<SettingsPanel>>,
+){
+    menu.open = open;
+    if menu.open {
+        menu.was_paused = step_mode.paused;
+        step_mode.paused = true;
+    } else {
+        step_mode.paused = menu.was_paused;
+    }
+    for mut visibility in panel_query.iter_mut() {
+        *visibility = if menu.open { Visibility::Inherited } else { Visibility::Hidden };
+    }
+}
+
+// Arrow keys and Enter press the focused row's buttons, so a key does exactly what a click does
+fn navigate_settings(
+    input: Res<Input<KeyCode>>,
+    menu: Res<SettingsMenu>,
+    mut selection: ResMut<MenuSelection>,
+    mut button_query: Query<(&SettingButton, &mut Interaction)>,
+){
+    if !menu.open {
+        return;
+    }
+    let rows: usize = Setting::ALL.len();
+    if input.just_pressed(KeyCode::Up) {
+        selection.row = (selection.row + rows - 1) % rows;
+    }
+    if input.just_pressed(KeyCode::Down) {
+        selection.row = (selection.row + 1) % rows;
+    }
+    let step: i32 = if input.just_pressed(KeyCode::Left) {
+        -1
+    } else if input.just_pressed(KeyCode::Right) || input.just_pressed(KeyCode::Return) {
+        1
+    } else {
+        return;
+    };
+    let focused: Setting = Setting::ALL[selection.row];
+    for (button, mut interaction) in button_query.iter_mut() {
+        if button.setting == focused && button.step == step {
+            *interaction = Interaction::Pressed;
+        }
+    }
+}
+
+fn cycle<T: Clone + PartialEq>(options: &[T], current: T, step: i32) -> T {
+    let index: i32 = options.iter().position(|option| *option == current).unwrap_or(0) as i32;
+    return options[(index + step).rem_euclid(options.len() as i32) as usize].clone();
+}
+
+fn press_setting_buttons(
+    mut button_query: Query<(&Interaction, &SettingButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut volume: ResMut<MasterVolume>,
+    mut difficulty: ResMut<DynamicDifficulty>,
+    mut magnet: ResMut<MergeMagnet>,
+    mut theme: ResMut<Theme>,
+    mut skin: ResMut<SkinPack>,
+    mut preview: ResMut<MergePreview>,
+    mut outlines: ResMut<FruitOutlines>,
+    mut interpolation: ResMut<RenderInterpolation>,
+    mut graphics: ResMut<GraphicsSettings>,
+    mut settings: ResMut<GameSettings>,
+    mut merge_enabled: ResMut<MergeEnabled>,
+    mut localization: ResMut<Localization>,
+    mut auto_pause: ResMut<AutoPause>,
+    mut restart: ResMut<RestartConfig>,
+){
+    let mut changed: bool = false;
+    for (interaction, button, mut background) in button_query.iter_mut() {
+        *background = match interaction {
+            Interaction::Hovered => BUTTON_HOVER_COLOR.into(),
+            _ => BUTTON_COLOR.into(),
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        changed = true;
+        match button.setting {
+            Setting::Volume => {
+                volume.0 = (volume.0 + VOLUME_STEP * button.step as f32).clamp(0.0, 1.0);
+            }
+            Setting::Difficulty => {
+                let current: Option<DifficultyMode> = if difficulty.enabled { Some(difficulty.mode) } else { None };
+                let options = [None, Some(DifficultyMode::Relief), Some(DifficultyMode::Hard)];
+                let next: Option<DifficultyMode> = cycle(&options, current, button.step);
+                difficulty.enabled = next.is_some();
+                if let Some(mode) = next {
+                    difficulty.mode = mode;
+                }
+            }
+            Setting::CooldownCurve => {
+                let options = [CooldownCurve::Constant, CooldownCurve::Lengthen, CooldownCurve::Shorten];
+                difficulty.cooldown_curve = cycle(&options, difficulty.cooldown_curve, button.step);
+            }
+            Setting::MergeMagnet => {
+                magnet.0 = !magnet.0;
+            }
+            Setting::Theme => {
+                theme.preset = if button.step > 0 { theme.preset.next() } else { theme.preset.previous() };
+            }
+            Setting::Skin => {
+                // listed again on every press, so a pack added while the game runs shows up
+                let options: Vec<Option<String>> = std::iter::once(None).chain(available_packs().into_iter().map(Some)).collect();
+                skin.name = cycle(&options, skin.name.clone(), button.step);
+            }
+            Setting::MergePreview => {
+                preview.enabled = !preview.enabled;
+            }
+            Setting::Outlines => {
+                outlines.enabled = !outlines.enabled;
+            }
+            Setting::Interpolation => {
+                interpolation.enabled = !interpolation.enabled;
+            }
+            Setting::Msaa => {
+                graphics.msaa = cycle(&MsaaLevel::ALL, graphics.msaa, button.step);
+            }
+            Setting::Vsync => {
+                graphics.vsync = !graphics.vsync;
+            }
+            Setting::RenderMode => {
+                graphics.render_mode = cycle(&RenderMode::ALL, graphics.render_mode, button.step);
+            }
+            Setting::DropMode => {
+                settings.drop_mode = cycle(&[DropMode::Free, DropMode::FixedCenter], settings.drop_mode, button.step);
+            }
+            Setting::Keys => {
+                settings.keys = cycle(&KeyLayout::ALL, settings.keys, button.step);
+            }
+            Setting::ThrowMode => {
+                settings.throw_mode = !settings.throw_mode;
+            }
+            Setting::Flick => {
+                settings.flick = !settings.flick;
+            }
+            Setting::Mode => {
+                settings.time_attack = !settings.time_attack;
+            }
+            Setting::PerfectDrops => {
+                settings.perfect_drop_window = if settings.perfect_drop_window > 0.0 { 0.0 } else { PERFECT_DROP_WINDOW };
+            }
+            Setting::Merging => {
+                merge_enabled.0 = !merge_enabled.0;
+            }
+            Setting::Gutters => {
+                settings.gutters = !settings.gutters;
+            }
+            Setting::Language => {
+                let current: &str = LANGUAGES.iter().copied().find(|language| *language == localization.language).unwrap_or(DEFAULT_LANGUAGE);
+                *localization = Localization::load(cycle(&LANGUAGES, current, button.step));
+            }
+            Setting::AutoPause => {
+                // (enabled, resume_on_focus)
+                let options = [(false, false), (true, false), (true, true)];
+                let current = (auto_pause.enabled, auto_pause.enabled && auto_pause.resume_on_focus);
+                (auto_pause.enabled, auto_pause.resume_on_focus) = cycle(&options, current, button.step);
+            }
+            Setting::SaveOnExit => {
+                settings.save_on_exit = !settings.save_on_exit;
+            }
+            Setting::RestartConfirm => {
+                let options = [RestartConfirm::Hold, RestartConfirm::DoublePress, RestartConfirm::Off];
+                restart.confirm = cycle(&options, restart.confirm, button.step);
+            }
+        }
+    }
+    if changed {
+        SavedSettings::capture(&CurrentSettings {
+            volume: &volume,
+            difficulty: &difficulty,
+            magnet: &magnet,
+            theme: &theme,
+            skin: &skin,
+            preview: &preview,
+            outlines: &outlines,
+            interpolation: &interpolation,
+            graphics: &graphics,
+            settings: &settings,
+            merge_enabled: &merge_enabled,
+            language: &localization.language,
+            auto_pause: &auto_pause,
+            restart: &restart,
+        }).save();
+    }
+}
+
+fn update_setting_values(
+    menu: Res<SettingsMenu>,
+    volume: Res<MasterVolume>,
+    difficulty: Res<DynamicDifficulty>,
+    magnet: Res<MergeMagnet>,
+    theme: Res<Theme>,
+    skin: Res<SkinPack>,
+    preview: Res<MergePreview>,
+    outlines: Res<FruitOutlines>,
+    interpolation: Res<RenderInterpolation>,
+    graphics: Res<GraphicsSettings>,
+    settings: Res<GameSettings>,
+    merge_enabled: Res<MergeEnabled>,
+    localization: Res<Localization>,
+    auto_pause: Res<AutoPause>,
+    restart: Res<RestartConfig>,
+    mut value_query: Query<(&mut Text, &SettingValue)>,
+){
+    if !menu.open {
+        return;
+    }
+    for (mut text, SettingValue(setting)) in value_query.iter_mut() {
+        text.sections[0].value = match setting {
+            Setting::Volume => format!("{:.0}%", volume.0 * 100.0),
+            Setting::Difficulty => localization.get(match (difficulty.enabled, difficulty.mode) {
+                (false, _) => "off",
+                (true, DifficultyMode::Relief) => "difficulty_relief",
+                (true, DifficultyMode::Hard) => "difficulty_hard",
+            }).to_string(),
+            Setting::CooldownCurve => localization.get(match difficulty.cooldown_curve {
+                CooldownCurve::Constant => "cooldown_constant",
+                CooldownCurve::Lengthen => "cooldown_lengthen",
+                CooldownCurve::Shorten => "cooldown_shorten",
+            }).to_string(),
+            Setting::MergeMagnet => localization.get(if magnet.0 { "on" } else { "off" }).to_string(),
+            Setting::Theme => format!("{:?}", theme.preset),
+            Setting::Skin => match &skin.name {
+                Some(name) => name.clone(),
+                None => localization.get("skin_default").to_string(),
+            },
+            Setting::MergePreview => localization.get(if preview.enabled { "on" } else { "off" }).to_string(),
+            Setting::Outlines => localization.get(if outlines.enabled { "on" } else { "off" }).to_string(),
+            Setting::Interpolation => localization.get(if interpolation.enabled { "on" } else { "off" }).to_string(),
+            Setting::Msaa => match graphics.msaa {
+                MsaaLevel::Off => localization.get("off").to_string(),
+                MsaaLevel::X2 => "2x".to_string(),
+                MsaaLevel::X4 => "4x".to_string(),
+            },
+            Setting::Vsync => localization.get(if graphics.vsync { "on" } else { "off" }).to_string(),
+            Setting::RenderMode => localization.get(match graphics.render_mode {
+                RenderMode::Sprite => "render_sprite",
+                RenderMode::Mesh => "render_mesh",
+            }).to_string(),
+            Setting::DropMode => localization.get(match settings.drop_mode {
+                DropMode::Free => "drop_free",
+                DropMode::FixedCenter => "drop_center",
+            }).to_string(),
+            Setting::Keys => localization.get(match settings.keys {
+                KeyLayout::Letters => "keys_letters",
+                KeyLayout::Arrows => "keys_arrows",
+            }).to_string(),
+            Setting::ThrowMode => localization.get(if settings.throw_mode { "on" } else { "off" }).to_string(),
+            Setting::Flick => localization.get(if settings.flick { "on" } else { "off" }).to_string(),
+            Setting::Mode => localization.get(if settings.time_attack { "mode_time_attack" } else { "mode_endless" }).to_string(),
+            Setting::PerfectDrops => localization.get(if settings.perfect_drop_window > 0.0 { "on" } else { "off" }).to_string(),
+            Setting::Merging => localization.get(if merge_enabled.0 { "on" } else { "merging_off" }).to_string(),
+            Setting::Gutters => localization.get(if settings.gutters { "on" } else { "off" }).to_string(),
+            Setting::Language => localization.get("language_name").to_string(),
+            Setting::AutoPause => localization.get(match (auto_pause.enabled, auto_pause.resume_on_focus) {
+                (false, _) => "off",
+                (true, false) => "on",
+                (true, true) => "auto_pause_resume",
+            }).to_string(),
+            Setting::SaveOnExit => localization.get(if settings.save_on_exit { "on" } else { "off" }).to_string(),
+            Setting::RestartConfirm => localization.get(match restart.confirm {
+                RestartConfirm::Hold => "restart_hold",
+                RestartConfirm::DoublePress => "restart_double_press",
+                RestartConfirm::Off => "off",
+            }).to_string(),
+        };
+    }
+}
+
+fn update_setting_labels(
+    localization: Res<Localization>,
+    mut title_query: Query<&mut Text, (With<SettingsTitle>, Without<SettingLabel>)>,
+    mut label_query: Query<(&mut Text, &SettingLabel)>,
+){
+    if !localization.is_changed() {
+        return;
+    }
+    for mut text in title_query.iter_mut() {
+        text.sections[0].value = localization.get("settings_title").to_string();
+    }
+    for (mut text, SettingLabel(setting)) in label_query.iter_mut() {
+        text.sections[0].value = localization.get(setting.label()).to_string();
+    }
+}
+
+fn highlight_selection(
+    menu: Res<SettingsMenu>,
+    selection: Res<MenuSelection>,
+    mut label_query: Query<(&mut Text, &SettingLabel)>,
+){
+    if !menu.open || !(selection.is_changed() || menu.is_changed()) {
+        return;
+    }
+    let focused: Setting = Setting::ALL[selection.row];
+    for (mut text, SettingLabel(setting)) in label_query.iter_mut() {
+        text.sections[0].style.color = if *setting == focused { FOCUS_COLOR } else { TEXT_COLOR };
+    }
+}
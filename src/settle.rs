@@ -0,0 +1,45 @@
+// Fast-forward (F2): runs SETTLE_STEPS of the FixedUpdate simulation in a single frame, so a pile
+// that's still moving comes to rest at once. The steps are the same ones the game would have run,
+// so the result is the same for the same seed and drops. Nothing advances while the game is paused.
+
+use bevy::prelude::*;
+
+const SETTLE_KEY: KeyCode = KeyCode::F2;
+const SETTLE_STEPS: usize = 300; // five seconds at the default timestep
+
+pub struct SettlePlugin;
+
+impl Plugin for SettlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, settle_on_key);
+    }
+}
+
+// Also for headless runs and setting up a board, see golden.rs
+pub fn settle_board(world: &mut World, steps: usize) {
+    for _ in 0..steps {
+        world.run_schedule(FixedUpdate);
+    }
+}
+
+// The whole game without a window, seeded with `seed` if there is one and not started yet,
+// ready to step with settle_board
+#[cfg(test)]
+pub(crate) fn headless_game(seed: Option<u64>) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()));
+    crate::add_game(&mut app);
+    app.init_resource::<Input<KeyCode>>()
+        .insert_resource(FixedTime::new_from_secs(1.0 / 60.0));
+    if let Some(seed) = seed {
+        crate::seed_game(&mut app, seed);
+    }
+    return app;
+}
+
+fn settle_on_key(world: &mut World) {
+    if !world.resource::<Input<KeyCode>>().just_pressed(SETTLE_KEY) {
+        return;
+    }
+    settle_board(world, SETTLE_STEPS);
+}
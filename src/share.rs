@@ -0,0 +1,201 @@
+// Share codes: a run's seed written in base 36, short enough to read out or paste into chat.
+// `--seed <code>` starts a run from a code, and C on the game over screen copies the current
+// one to the clipboard. E on the game over screen types in someone else's code instead, and
+// Enter starts a run from it. Same code plus same settings is the same sequence of fruits.
+// Like the leaderboard's name prompt, the code is typed through ReceivedCharacter and every
+// other key is held back meanwhile.
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::gameover::final_score_shown;
+use crate::locale::Localization;
+use crate::restart::{RestartRequested, RestartSeed};
+use crate::{GameSeed, GameState};
+
+const COPY_KEY: KeyCode = KeyCode::C;
+const ENTER_CODE_KEY: KeyCode = KeyCode::E;
+const CODE_DIGITS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const MAX_CODE_LENGTH: usize = 13; // digits, enough for any u64
+
+// Shown under the final score, with the result of the last copy
+#[derive(Component)]
+pub struct ShareCodeText;
+
+// Opened on the first copy and kept for the rest of the session: on X11 and Wayland the copied
+// code is only on the clipboard for as long as the Clipboard that put it there is alive
+#[derive(Default)]
+struct ShareClipboard(Option<arboard::Clipboard>);
+
+// The code being typed, None while there's no prompt
+#[derive(Resource, Default)]
+struct CodeEntry {
+    typing: Option<String>,
+}
+
+pub struct SharePlugin;
+
+impl Plugin for SharePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CodeEntry>()
+            .init_non_send_resource::<ShareClipboard>()
+            // before anything in Update sees the keys the code is typed with
+            .add_systems(PreUpdate, type_share_code.after(InputSystem))
+            .add_systems(Update, (
+                copy_share_code,
+                start_code_entry.run_if(final_score_shown),
+            ).run_if(in_state(GameState::GameOver)))
+            .add_systems(OnExit(GameState::GameOver), cancel_code_entry);
+    }
+}
+
+pub fn share_code(seed: u64) -> String {
+    let mut digits: Vec<u8> = Vec::new();
+    let mut rest: u64 = seed;
+    loop {
+        digits.push(CODE_DIGITS[(rest % 36) as usize]);
+        rest /= 36;
+        if rest == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    return String::from_utf8(digits).unwrap();
+}
+
+// Case-insensitive, surrounding whitespace ignored. None if it isn't a valid code.
+pub fn parse_share_code(code: &str) -> Option<u64> {
+    return u64::from_str_radix(code.trim(), 36).ok();
+}
+
+fn copy_share_code(
+    input: Res<Input<KeyCode>>,
+    seed: Res<GameSeed>,
+    localization: Res<Localization>,
+    mut clipboard: NonSendMut<ShareClipboard>,
+    mut text_query: Query<&mut Text, With<ShareCodeText>>,
+){
+    if !input.just_pressed(COPY_KEY) {
+        return;
+    }
+    let code: String = share_code(seed.0);
+    // no clipboard on some platforms and headless sessions, the code is on screen either way
+    if clipboard.0.is_none() {
+        match arboard::Clipboard::new() {
+            Ok(opened) => clipboard.0 = Some(opened),
+            Err(err) => warn!("Could not open the clipboard: {}", err),
+        }
+    }
+    let copied: bool = match clipboard.0.as_mut().map(|open| open.set_text(code.clone())) {
+        Some(Ok(())) => true,
+        Some(Err(err)) => {
+            warn!("Could not copy share code {}: {}", code, err);
+            false
+        }
+        None => false,
+    };
+    let status: &str = localization.get(if copied { "share_copied" } else { "share_copy_failed" });
+    set_status(status, &mut text_query);
+}
+
+fn set_status(status: &str, text_query: &mut Query<&mut Text, With<ShareCodeText>>) {
+    for mut text in text_query.iter_mut() {
+        if let Some(section) = text.sections.get_mut(1) {
+            section.value = status.to_string();
+        }
+    }
+}
+
+fn start_code_entry(
+    input: Res<Input<KeyCode>>,
+    localization: Res<Localization>,
+    mut entry: ResMut<CodeEntry>,
+    mut text_query: Query<&mut Text, With<ShareCodeText>>,
+){
+    if entry.typing.is_some() || !input.just_pressed(ENTER_CODE_KEY) {
+        return;
+    }
+    entry.typing = Some(String::new());
+    set_status(&localization.format("share_enter", &[&""]), &mut text_query);
+}
+
+// Enter starts a run from the code, Esc puts the prompt away
+fn type_share_code(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    localization: Res<Localization>,
+    mut entry: ResMut<CodeEntry>,
+    mut restart_events: EventWriter<RestartRequested>,
+    mut text_query: Query<&mut Text, With<ShareCodeText>>,
+){
+    let Some(code) = entry.typing.as_mut() else {
+        characters.clear();
+        return;
+    };
+    for character in characters.iter() {
+        if character.char.is_ascii_alphanumeric() && code.len() < MAX_CODE_LENGTH {
+            code.push(character.char.to_ascii_uppercase());
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        code.pop();
+    }
+    let typed: String = code.clone();
+    let submitted: bool = keys.just_pressed(KeyCode::Return);
+    let cancelled: bool = keys.just_pressed(KeyCode::Escape);
+    keys.reset_all();
+
+    if cancelled {
+        entry.typing = None;
+        set_status("", &mut text_query);
+        return;
+    }
+    if !submitted {
+        set_status(&localization.format("share_enter", &[&typed]), &mut text_query);
+        return;
+    }
+    match parse_share_code(&typed) {
+        Some(seed) => {
+            entry.typing = None;
+            restart_events.send(RestartRequested(RestartSeed::Code(seed)));
+        }
+        None => set_status(&localization.format("share_invalid", &[&typed]), &mut text_query),
+    }
+}
+
+fn cancel_code_entry(mut entry: ResMut<CodeEntry>) {
+    entry.typing = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What type_share_code makes of `typed` followed by Enter
+    fn submitted(typed: &str) -> Option<RestartSeed> {
+        let mut world: World = World::new();
+        world.init_resource::<Input<KeyCode>>();
+        world.init_resource::<Events<ReceivedCharacter>>();
+        world.init_resource::<Events<RestartRequested>>();
+        world.insert_resource(Localization::default());
+        world.insert_resource(CodeEntry { typing: Some(String::new()) });
+        let window: Entity = world.spawn_empty().id();
+        for char in typed.chars() {
+            world.resource_mut::<Events<ReceivedCharacter>>().send(ReceivedCharacter { window, char });
+        }
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Return);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(type_share_code);
+        schedule.run(&mut world);
+        let events = world.resource::<Events<RestartRequested>>();
+        return events.iter_current_update_events().last().map(|event| event.0);
+    }
+
+    #[test]
+    fn a_typed_code_restarts_from_its_seed() {
+        assert_eq!(submitted(&share_code(123456789).to_lowercase()), Some(RestartSeed::Code(123456789)));
+        // too big for a seed
+        assert_eq!(submitted(&"Z".repeat(MAX_CODE_LENGTH)), None);
+        assert_eq!(submitted(""), None);
+    }
+}
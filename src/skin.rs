@@ -0,0 +1,124 @@
+// Skin packs: community fruit art in assets/skins/<name>/, one <group>.png per tier (0.png is
+// the smallest). Picked on the settings screen. The art replaces the generic fruit icon and is
+// tinted the same way, and any tier the pack has no file for keeps the generic icon. A pack can
+// also give tiers a voice, <group>.wav, played when that fruit is dropped (see audio.rs).
+
+use bevy::prelude::*;
+use std::fs;
+use std::path::Path;
+
+use crate::{CollisionShape, Fruit, FruitAssets, FruitIterator, FruitTable, Outline, Player};
+
+pub const ASSETS_DIR: &str = "assets";
+const SKINS_DIR: &str = "skins"; // under ASSETS_DIR
+
+#[derive(Resource, Default)]
+pub struct SkinPack {
+    pub name: Option<String>, // None is the generic icon
+}
+
+pub struct SkinPlugin;
+
+impl Plugin for SkinPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkinPack>()
+            .add_systems(Update, (apply_skin_pack, update_held_texture).chain());
+    }
+}
+
+// Names of the packs in SKINS_DIR, sorted
+pub fn available_packs() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(Path::new(ASSETS_DIR).join(SKINS_DIR)) else {
+        return Vec::new();
+    };
+    let mut packs: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    packs.sort();
+    return packs;
+}
+
+// One texture per group, the generic icon wherever the pack has no file
+fn load_pack(name: &str, groups: usize, asset_server: &AssetServer, fallback: &Handle<Image>) -> Vec<Handle<Image>> {
+    if !Path::new(ASSETS_DIR).join(SKINS_DIR).join(name).is_dir() {
+        warn!("Skin pack {} not found in {}/{}, using the default fruit icon", name, ASSETS_DIR, SKINS_DIR);
+        return vec![fallback.clone(); groups];
+    }
+    let mut missing: Vec<String> = Vec::new();
+    let textures: Vec<Handle<Image>> = (0..groups)
+        .map(|group| {
+            let path: String = format!("{}/{}/{}.png", SKINS_DIR, name, group);
+            if Path::new(ASSETS_DIR).join(&path).is_file() {
+                return asset_server.load(path);
+            }
+            missing.push(format!("{}.png", group));
+            return fallback.clone();
+        })
+        .collect();
+    if !missing.is_empty() {
+        warn!("Skin pack {} is missing {}, using the default fruit icon for those", name, missing.join(", "));
+    }
+    return textures;
+}
+
+// The voice for each group, None wherever the pack has no clip. Voices are optional, so unlike
+// the art a missing one isn't worth a warning.
+pub fn load_pack_voices(name: &str, groups: usize, asset_server: &AssetServer) -> Vec<Option<Handle<AudioSource>>> {
+    return (0..groups)
+        .map(|group| {
+            let path: String = format!("{}/{}/{}.wav", SKINS_DIR, name, group);
+            if Path::new(ASSETS_DIR).join(&path).is_file() {
+                return Some(asset_server.load(path));
+            }
+            return None;
+        })
+        .collect();
+}
+
+// Loads the pack on startup and whenever it changes, and reskins the fruits already in play
+fn apply_skin_pack(
+    skin: Res<SkinPack>,
+    asset_server: Res<AssetServer>,
+    fruit_table: Res<FruitTable>,
+    mut fruit_assets: ResMut<FruitAssets>,
+    mut fruit_query: Query<(&Fruit, &mut Handle<Image>, Option<&Children>)>,
+    mut outline_query: Query<&mut Handle<Image>, (With<Outline>, Without<Fruit>)>,
+){
+    if !skin.is_changed() {
+        return;
+    }
+    let groups: usize = fruit_table.len();
+    fruit_assets.skin = match &skin.name {
+        Some(name) => load_pack(name, groups, &asset_server, &fruit_assets.fruit_icon),
+        None => vec![fruit_assets.fruit_icon.clone(); groups],
+    };
+    for (fruit, mut texture, children) in fruit_query.iter_mut() {
+        // boxes are drawn untextured
+        if !matches!(fruit.shape, CollisionShape::Circle) {
+            continue;
+        }
+        *texture = fruit_assets.texture(fruit.group);
+        let Some(children) = children else {
+            continue;
+        };
+        for child in children.iter() {
+            if let Ok(mut outline_texture) = outline_query.get_mut(*child) {
+                *outline_texture = texture.clone();
+            }
+        }
+    }
+}
+
+fn update_held_texture(
+    fruit_assets: Res<FruitAssets>,
+    mut player_query: Query<(&FruitIterator, &mut Handle<Image>), With<Player>>,
+){
+    for (fruit_iterator, mut texture) in player_query.iter_mut() {
+        let held: Handle<Image> = fruit_assets.texture(fruit_iterator.next_group);
+        if *texture != held {
+            *texture = held;
+        }
+    }
+}
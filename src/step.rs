@@ -0,0 +1,99 @@
+// Pause and frame stepping for debugging the physics. P freezes the simulation, period advances
+// it by exactly one FixedUpdate step while frozen, P again resumes.
+// The game also pauses itself when the window loses focus, see AutoPause.
+
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+
+const PAUSE_KEY: KeyCode = KeyCode::P;
+const STEP_KEY: KeyCode = KeyCode::Period;
+
+// Every FixedUpdate system that advances the game, gated on StepMode
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Simulation;
+
+#[derive(Resource, Default)]
+pub struct StepMode {
+    pub paused: bool,
+    step_requested: bool, // run one step while paused, cleared once it has run
+}
+
+// Pause on losing window focus, and optionally resume on getting it back. Only a pause this
+// made is undone, so a game paused by hand before alt-tabbing stays paused.
+#[derive(Resource)]
+pub struct AutoPause {
+    pub enabled: bool,
+    pub resume_on_focus: bool,
+    paused_by_blur: bool,
+}
+
+impl Default for AutoPause {
+    fn default() -> Self {
+        AutoPause {
+            enabled: true,
+            resume_on_focus: false,
+            paused_by_blur: false,
+        }
+    }
+}
+
+pub struct StepPlugin;
+
+impl Plugin for StepPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StepMode>()
+            .init_resource::<AutoPause>()
+            .configure_set(FixedUpdate, Simulation.run_if(simulation_running))
+            .add_systems(FixedUpdate, finish_step.after(Simulation))
+            .add_systems(Update, (
+                step_input,
+                auto_pause,
+            ));
+    }
+}
+
+fn simulation_running(step_mode: Res<StepMode>) -> bool {
+    return !step_mode.paused || step_mode.step_requested;
+}
+
+// A frame can run several FixedUpdate steps; only the first one after a request goes through
+fn finish_step(mut step_mode: ResMut<StepMode>) {
+    step_mode.step_requested = false;
+}
+
+fn step_input(
+    input: Res<Input<KeyCode>>,
+    mut step_mode: ResMut<StepMode>,
+    mut auto_pause: ResMut<AutoPause>,
+){
+    if input.just_pressed(PAUSE_KEY) {
+        step_mode.paused = !step_mode.paused;
+        step_mode.step_requested = false;
+    }
+    if !step_mode.paused {
+        auto_pause.paused_by_blur = false;
+    }
+    if step_mode.paused && input.just_pressed(STEP_KEY) {
+        step_mode.step_requested = true;
+    }
+}
+
+fn auto_pause(
+    mut step_mode: ResMut<StepMode>,
+    mut auto_pause: ResMut<AutoPause>,
+    mut focus_events: EventReader<WindowFocused>,
+){
+    for event in focus_events.iter() {
+        if !event.focused {
+            if auto_pause.enabled && !step_mode.paused {
+                step_mode.paused = true;
+                auto_pause.paused_by_blur = true;
+            }
+        } else if auto_pause.paused_by_blur {
+            if auto_pause.resume_on_focus {
+                step_mode.paused = false;
+            }
+            auto_pause.paused_by_blur = false;
+        }
+    }
+}
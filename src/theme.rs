@@ -0,0 +1,163 @@
+// Themed backgrounds: a vertical gradient drawn behind the arena, sized to the window.
+// F7 cycles through the presets.
+
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::PrimaryWindow;
+
+use serde::{Deserialize, Serialize};
+
+use crate::BACKGROUND_COLOR;
+
+const THEME_KEY: KeyCode = KeyCode::F7;
+const GRADIENT_STEPS: u32 = 256; // texture rows, smoothed further by linear sampling
+// Camera2dBundle only renders z > -0.1 and the arena sits at z = 0
+const BACKGROUND_Z: f32 = -0.05;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ThemePreset {
+    Classic,
+    Sunset,
+    Ocean,
+    Night,
+}
+
+impl ThemePreset {
+    const ALL: [ThemePreset; 4] = [
+        ThemePreset::Classic,
+        ThemePreset::Sunset,
+        ThemePreset::Ocean,
+        ThemePreset::Night,
+    ];
+
+    // (top, bottom) colors of the gradient
+    fn colors(&self) -> (Color, Color) {
+        match self {
+            ThemePreset::Classic => (BACKGROUND_COLOR, BACKGROUND_COLOR),
+            ThemePreset::Sunset => (Color::rgb(0.98, 0.75, 0.45), Color::rgb(0.85, 0.35, 0.45)),
+            ThemePreset::Ocean => (Color::rgb(0.6, 0.85, 0.95), Color::rgb(0.1, 0.35, 0.6)),
+            ThemePreset::Night => (Color::rgb(0.1, 0.1, 0.25), Color::rgb(0.02, 0.02, 0.08)),
+        }
+    }
+
+    pub fn next(&self) -> ThemePreset {
+        let index: usize = ThemePreset::ALL.iter().position(|preset| preset == self).unwrap_or(0);
+        return ThemePreset::ALL[(index + 1) % ThemePreset::ALL.len()];
+    }
+
+    pub fn previous(&self) -> ThemePreset {
+        let index: usize = ThemePreset::ALL.iter().position(|preset| preset == self).unwrap_or(0);
+        return ThemePreset::ALL[(index + ThemePreset::ALL.len() - 1) % ThemePreset::ALL.len()];
+    }
+}
+
+#[derive(Resource)]
+pub struct Theme {
+    pub preset: ThemePreset,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            preset: ThemePreset::Classic,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Background;
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .add_systems(Startup, spawn_background)
+            .add_systems(Update, (
+                cycle_theme,
+                apply_theme,
+                resize_background,
+            ).chain());
+    }
+}
+
+fn gradient_image(top: Color, bottom: Color) -> Image {
+    let mut data: Vec<u8> = Vec::with_capacity((GRADIENT_STEPS * 4) as usize);
+    for row in 0..GRADIENT_STEPS {
+        let t: f32 = row as f32 / (GRADIENT_STEPS - 1) as f32; // row 0 is the top of the image
+        for (a, b) in [(top.r(), bottom.r()), (top.g(), bottom.g()), (top.b(), bottom.b())] {
+            data.push(((a + (b - a) * t) * 255.0).round() as u8);
+        }
+        data.push(255);
+    }
+    return Image::new(
+        Extent3d {
+            width: 1,
+            height: GRADIENT_STEPS,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+}
+
+fn spawn_background(mut commands: Commands) {
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_xyz(0.0, 0.0, BACKGROUND_Z),
+            ..default()
+        },
+        Background,
+    ));
+}
+
+fn cycle_theme(
+    input: Res<Input<KeyCode>>,
+    mut theme: ResMut<Theme>,
+){
+    if input.just_pressed(THEME_KEY) {
+        theme.preset = theme.preset.next();
+    }
+}
+
+fn apply_theme(
+    theme: Res<Theme>,
+    mut images: ResMut<Assets<Image>>,
+    mut clear_color: ResMut<ClearColor>,
+    mut background_query: Query<&mut Handle<Image>, With<Background>>,
+){
+    if !theme.is_changed() {
+        return;
+    }
+    let (top, bottom) = theme.preset.colors();
+    clear_color.0 = bottom;
+    for mut texture in background_query.iter_mut() {
+        *texture = images.add(gradient_image(top, bottom));
+    }
+}
+
+// The visible world size is the window's logical size times the camera's zoom (see camera.rs).
+// The background also follows the camera, which scrolls in the tall arena.
+fn resize_background(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), (With<Camera2d>, Without<Background>)>,
+    mut background_query: Query<(&mut Sprite, &mut Transform), With<Background>>,
+){
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Ok((camera_transform, projection)) = camera_query.get_single() else {
+        return;
+    };
+    let size: Vec2 = Vec2::new(window.width(), window.height()) * projection.scale;
+    let center: Vec2 = camera_transform.translation.truncate();
+    for (mut sprite, mut transform) in background_query.iter_mut() {
+        if sprite.custom_size != Some(size) {
+            sprite.custom_size = Some(size);
+        }
+        if transform.translation.truncate() != center {
+            transform.translation = center.extend(BACKGROUND_Z);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// Score over time for the game over screen. The score is sampled every TIMELINE_INTERVAL of
+// simulation time; once MAX_TIMELINE_SAMPLES are taken every other one is dropped and the
+// interval doubles, so a long run costs the same memory as a short one, just coarser.
+
+use bevy::prelude::*;
+
+use crate::gameover::final_score_shown;
+use crate::step::Simulation;
+use crate::win::RunClock;
+use crate::{apply_merges, GameState, Scoreboard, SCORE_COLOR, TEXT_COLOR};
+
+const TIMELINE_INTERVAL: f32 = 1.0; // seconds between samples at the start of a run
+const MAX_TIMELINE_SAMPLES: usize = 256;
+const GRAPH_SIZE: Vec2 = Vec2::new(400.0, 150.0); // world units
+const GRAPH_OFFSET: f32 = -170.0; // graph center below the camera, clear of the final score text
+
+#[derive(Resource)]
+struct ScoreTimeline {
+    samples: Vec<(f32, u32)>, // (seconds into the run, score), oldest first
+    interval: f32,
+    next_sample: f32, // RunClock time of the next sample
+}
+
+impl Default for ScoreTimeline {
+    fn default() -> Self {
+        ScoreTimeline {
+            samples: Vec::new(),
+            interval: TIMELINE_INTERVAL,
+            next_sample: 0.0,
+        }
+    }
+}
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScoreTimeline>()
+            .add_systems(FixedUpdate, sample_score.after(apply_merges).in_set(Simulation))
+            // a restart goes straight back to Playing
+            .add_systems(OnEnter(GameState::Playing), reset_timeline)
+            .add_systems(Update, draw_timeline.run_if(in_state(GameState::GameOver)).run_if(final_score_shown));
+    }
+}
+
+fn reset_timeline(mut timeline: ResMut<ScoreTimeline>) {
+    *timeline = ScoreTimeline::default();
+}
+
+fn sample_score(
+    clock: Res<RunClock>,
+    scoreboard: Res<Scoreboard>,
+    mut timeline: ResMut<ScoreTimeline>,
+){
+    let now: f32 = clock.seconds();
+    if now < timeline.next_sample {
+        return;
+    }
+    timeline.samples.push((now, scoreboard.score));
+    timeline.next_sample = now + timeline.interval;
+    if timeline.samples.len() >= MAX_TIMELINE_SAMPLES {
+        // keep the even samples, which are still evenly spaced at twice the interval
+        let mut index: usize = 0;
+        timeline.samples.retain(|_| {
+            index += 1;
+            return index % 2 == 1;
+        });
+        timeline.interval *= 2.0;
+    }
+}
+
+fn draw_timeline(
+    mut gizmos: Gizmos,
+    timeline: Res<ScoreTimeline>,
+    clock: Res<RunClock>,
+    scoreboard: Res<Scoreboard>,
+    camera_query: Query<&Transform, With<Camera2d>>,
+){
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+    let origin: Vec2 = camera_transform.translation.truncate() + Vec2::new(-GRAPH_SIZE.x / 2.0, GRAPH_OFFSET - GRAPH_SIZE.y / 2.0);
+    gizmos.line_2d(origin, origin + Vec2::new(GRAPH_SIZE.x, 0.0), TEXT_COLOR);
+    gizmos.line_2d(origin, origin + Vec2::new(0.0, GRAPH_SIZE.y), TEXT_COLOR);
+
+    // the run ended after the last sample, finish the line at the final score
+    let end: (f32, u32) = (clock.seconds(), scoreboard.score);
+    let duration: f32 = end.0.max(f32::EPSILON);
+    let top: f32 = end.1.max(1) as f32;
+    let points = timeline.samples.iter().chain(std::iter::once(&end)).map(|(time, score)| {
+        return origin + Vec2::new(time / duration, *score as f32 / top) * GRAPH_SIZE;
+    });
+    gizmos.linestrip_2d(points, SCORE_COLOR);
+}
@@ -0,0 +1,128 @@
+// First-launch tutorial: a paused overlay showing two of the same fruit merging into the next one,
+// dismissed with any key or click. Whether it's been seen is kept in the achievements file, so it
+// only comes back after --reset-tutorial.
+
+use bevy::prelude::*;
+
+use crate::achievements::Achievements;
+use crate::attract::AttractMode;
+use crate::locale::Localization;
+use crate::step::StepMode;
+use crate::{FruitAssets, FruitTable, SCORE_COLOR, SCOREBOARD_FONT_SIZE, TEXT_COLOR};
+
+const TUTORIAL_BACKDROP: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const TUTORIAL_FONT_SIZE: f32 = 26.0;
+const EXAMPLE_SCALE: f32 = 1.5; // example fruits drawn at this times their radius
+const EXAMPLE_GAP: f32 = 16.0; // px between the example fruits and the symbols between them
+
+#[derive(Component)]
+struct TutorialOverlay;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        // after setup has inserted FruitAssets
+        app.add_systems(PostStartup, show_tutorial)
+            .add_systems(Update, dismiss_tutorial);
+    }
+}
+
+fn show_tutorial(
+    mut commands: Commands,
+    achievements: Res<Achievements>,
+    attract: Res<AttractMode>,
+    localization: Res<Localization>,
+    fruit_assets: Res<FruitAssets>,
+    fruit_table: Res<FruitTable>,
+    mut step_mode: ResMut<StepMode>,
+){
+    if achievements.tutorial_seen || attract.active {
+        return;
+    }
+    step_mode.paused = true;
+
+    let text_style = TextStyle {
+        font_size: TUTORIAL_FONT_SIZE,
+        color: TEXT_COLOR,
+        ..default()
+    };
+    let symbol_style = TextStyle {
+        font_size: SCOREBOARD_FONT_SIZE,
+        color: SCORE_COLOR,
+        ..default()
+    };
+    let example = |group: u8| ImageBundle {
+        style: Style {
+            width: Val::Px(2.0 * fruit_table.get(group).radius * EXAMPLE_SCALE),
+            height: Val::Px(2.0 * fruit_table.get(group).radius * EXAMPLE_SCALE),
+            ..default()
+        },
+        image: UiImage::new(fruit_assets.texture(group)),
+        background_color: fruit_table.color(group, 1.0).into(), // tints the image
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(2.0 * EXAMPLE_GAP),
+                    ..default()
+                },
+                background_color: TUTORIAL_BACKDROP.into(),
+                ..default()
+            },
+            TutorialOverlay,
+        ))
+        .with_children(|root| {
+            root.spawn(TextBundle::from_section(localization.get("tutorial_merge"), text_style.clone()));
+            // fruit -> <- fruit = next fruit
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(EXAMPLE_GAP),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn(example(0));
+                row.spawn(TextBundle::from_section("\u{2192}", symbol_style.clone()));
+                row.spawn(TextBundle::from_section("\u{2190}", symbol_style.clone()));
+                row.spawn(example(0));
+                row.spawn(TextBundle::from_section("=", symbol_style.clone()));
+                row.spawn(example(1));
+            });
+            root.spawn(TextBundle::from_section(localization.get("tutorial_dismiss"), text_style));
+        });
+}
+
+fn dismiss_tutorial(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    mut achievements: ResMut<Achievements>,
+    mut step_mode: ResMut<StepMode>,
+    overlay_query: Query<Entity, With<TutorialOverlay>>,
+){
+    if overlay_query.is_empty() {
+        return;
+    }
+    if keys.get_just_pressed().next().is_none() && mouse.get_just_pressed().next().is_none() {
+        return;
+    }
+    for entity in overlay_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    step_mode.paused = false;
+    achievements.tutorial_seen = true;
+    achievements.save();
+}
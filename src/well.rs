@@ -0,0 +1,191 @@
+// Gravity well toy (F5 or --wells): left click in the arena opens a well that pulls nearby fruits
+// toward it for WELL_DURATION, for herding them together. The pull fades with distance and stops
+// adding speed past WELL_MAX_SPEED, and wells only open inside the arena below the danger line,
+// so nothing gets thrown over a wall or out of the top. The sandbox keeps left click while it's on.
+// A click opens its well on the next simulation step, which is the step a recording remembers.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::replay::{ReplayPlayer, WellOpened};
+use crate::sandbox::Sandbox;
+use crate::step::Simulation;
+use crate::{
+    apply_collisions, apply_gravity, ArenaConfig, Fruit, FruitAssets, GameState, LEFT_WALL, RIGHT_WALL,
+    WALL_THICKNESS,
+};
+
+const WELL_KEY: KeyCode = KeyCode::F5;
+const MAX_WELLS: usize = 3; // a click with this many open replaces the oldest
+const WELL_DURATION: f32 = 1.5; // seconds of simulation
+const WELL_RADIUS: f32 = 200.0; // px from the center a fruit starts to feel the pull
+const WELL_PULL: f32 = 3000.0; // px/s^2 at the center, a little over gravity so a well can lift
+const WELL_MAX_SPEED: f32 = 400.0; // px/s, faster fruits get no more pull
+const WELL_COLOR: Color = Color::rgba(0.6, 0.4, 1.0, 0.25);
+const WELL_Z: f32 = -0.02; // behind the fruits, in front of the background at -0.05
+
+#[derive(Resource, Default)]
+pub struct GravityWell {
+    pub enabled: bool,
+    requested: Option<Vec2>, // clicked, opens on the next step
+    wells: Vec<Well>, // oldest first
+}
+
+struct Well {
+    pos: Vec2,
+    remaining: f32, // seconds
+    marker: Entity,
+}
+
+pub struct GravityWellPlugin;
+
+impl Plugin for GravityWellPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GravityWell>()
+            .add_systems(Update, (toggle_gravity_wells, open_gravity_well).chain())
+            .add_systems(FixedUpdate, (
+                place_gravity_well,
+                apply_gravity_wells,
+            ).chain().after(apply_gravity).before(apply_collisions).in_set(Simulation))
+            .add_systems(Update, fade_well_markers)
+            .add_systems(OnEnter(GameState::Playing), reset_gravity_wells);
+    }
+}
+
+// A new run starts without the last run's wells, the toy stays switched on or off
+fn reset_gravity_wells(
+    mut commands: Commands,
+    mut gravity_well: ResMut<GravityWell>,
+){
+    gravity_well.requested = None;
+    for well in gravity_well.wells.drain(..) {
+        commands.entity(well.marker).despawn();
+    }
+}
+
+fn toggle_gravity_wells(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut gravity_well: ResMut<GravityWell>,
+){
+    if !keys.just_pressed(WELL_KEY) {
+        return;
+    }
+    gravity_well.enabled = !gravity_well.enabled;
+    if !gravity_well.enabled {
+        gravity_well.requested = None;
+        for well in gravity_well.wells.drain(..) {
+            commands.entity(well.marker).despawn();
+        }
+    }
+}
+
+fn open_gravity_well(
+    mouse: Res<Input<MouseButton>>,
+    sandbox: Res<Sandbox>,
+    replay: Res<ReplayPlayer>,
+    arena_config: Res<ArenaConfig>,
+    mut gravity_well: ResMut<GravityWell>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+){
+    // a replay opens its own wells
+    if !gravity_well.enabled || sandbox.active || replay.active || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    else {
+        return;
+    };
+    let inside_x: bool = cursor.x > LEFT_WALL + WALL_THICKNESS/2.0 && cursor.x < RIGHT_WALL - WALL_THICKNESS/2.0;
+    let inside_y: bool = cursor.y > arena_config.floor() && cursor.y < arena_config.danger_line_y;
+    if !inside_x || !inside_y {
+        return;
+    }
+    gravity_well.requested = Some(cursor);
+}
+
+// Opens the clicked or replayed well; a replayed one opens even with the toy switched off
+fn place_gravity_well(
+    mut commands: Commands,
+    fruit_assets: Res<FruitAssets>,
+    mut replay: ResMut<ReplayPlayer>,
+    mut gravity_well: ResMut<GravityWell>,
+    mut opened_events: EventWriter<WellOpened>,
+){
+    let Some(pos) = gravity_well.requested.take().or_else(|| replay.well_due.take()) else {
+        return;
+    };
+    if gravity_well.wells.len() >= MAX_WELLS {
+        let oldest: Well = gravity_well.wells.remove(0);
+        commands.entity(oldest.marker).despawn();
+    }
+    let marker: Entity = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(2.0 * WELL_RADIUS)),
+                color: WELL_COLOR,
+                ..default()
+            },
+            texture: fruit_assets.fruit_icon.clone(),
+            transform: Transform::from_translation(pos.extend(WELL_Z)),
+            ..default()
+        })
+        .id();
+    gravity_well.wells.push(Well {
+        pos,
+        remaining: WELL_DURATION,
+        marker,
+    });
+    opened_events.send(WellOpened { pos });
+}
+
+fn apply_gravity_wells(
+    mut commands: Commands,
+    time_step: Res<FixedTime>,
+    mut gravity_well: ResMut<GravityWell>,
+    mut fruit_query: Query<&mut Fruit>,
+){
+    if gravity_well.wells.is_empty() {
+        return;
+    }
+    let dt = time_step.period.as_secs_f32();
+    for well in gravity_well.wells.iter() {
+        for mut fruit in fruit_query.iter_mut() {
+            let offset: Vec2 = well.pos - fruit.pos;
+            let distance: f32 = offset.length();
+            if distance >= WELL_RADIUS || distance < f32::EPSILON || fruit.get_vel(dt).length() > WELL_MAX_SPEED {
+                continue;
+            }
+            let falloff: f32 = 1.0 - distance / WELL_RADIUS;
+            fruit.inc_vel(dt, offset / distance * WELL_PULL * falloff * dt);
+        }
+    }
+    for well in gravity_well.wells.iter_mut() {
+        well.remaining -= dt;
+    }
+    gravity_well.wells.retain(|well| {
+        if well.remaining > 0.0 {
+            return true;
+        }
+        commands.entity(well.marker).despawn();
+        return false;
+    });
+}
+
+// Markers fade out as their well runs down
+fn fade_well_markers(
+    gravity_well: Res<GravityWell>,
+    mut sprite_query: Query<&mut Sprite>,
+){
+    for well in gravity_well.wells.iter() {
+        if let Ok(mut sprite) = sprite_query.get_mut(well.marker) {
+            sprite.color.set_a(WELL_COLOR.a() * well.remaining / WELL_DURATION);
+        }
+    }
+}
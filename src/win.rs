@@ -0,0 +1,252 @@
+// Goal mode (--win <n>): making the top-tier fruit n times wins the run. The simulation stops
+// and a "You win" screen shows how long it took, in simulated time, so pauses don't count.
+// Time attack (--time-attack or the settings screen): the run ends after TIME_ATTACK_DURATION
+// seconds of that same clock, with a countdown on screen, and the score goes on its own
+// leaderboard (see leaderboard.rs).
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::locale::Localization;
+use crate::step::Simulation;
+use crate::{
+    apply_merges, FruitMerged, FruitTable, GameSettings, GameState, OVERLAY_FONT_SIZE, SCOREBOARD_FONT_SIZE,
+    SCOREBOARD_TEXT_PADDING, SCORE_COLOR, TEXT_COLOR,
+};
+
+pub const TIME_ATTACK_DURATION: f32 = 60.0; // seconds per time attack run
+const TIME_LEFT_TEXT_TOP: Val = Val::Px(80.0); // below the biggest fruit
+
+#[derive(Resource, Default)]
+pub struct WinCondition {
+    pub target: Option<u32>, // top-tier fruits to make, None is the endless game
+    pub made: u32,
+}
+
+// Simulation time this run, reset by a restart. Kept as a Duration, which adds up steps exactly,
+// so a minute of 1/60 s steps ends on its 3600th step rather than drifting a step late.
+#[derive(Resource, Default)]
+pub struct RunClock(pub Duration);
+
+impl RunClock {
+    pub fn seconds(&self) -> f32 {
+        return self.0.as_secs_f32();
+    }
+}
+
+// How long this run lasts, None plays until the board overflows. Taken from the settings as each
+// run starts, so switching modes mid-run only changes the next one.
+#[derive(Resource, Default)]
+pub struct RoundTimer {
+    pub limit: Option<f32>, // seconds
+}
+
+impl RoundTimer {
+    pub fn expired(&self, clock: &RunClock) -> bool {
+        return self.limit.is_some_and(|limit| clock.0 >= Duration::from_secs_f32(limit));
+    }
+}
+
+#[derive(Component)]
+struct TimeLeftText;
+
+#[derive(Component)]
+struct WinScreen;
+
+pub struct WinPlugin;
+
+impl Plugin for WinPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WinCondition>()
+            .init_resource::<RunClock>()
+            .init_resource::<RoundTimer>()
+            .add_systems(Startup, spawn_time_left_text)
+            .add_systems(FixedUpdate, (
+                tick_run_clock,
+                check_time_up.after(tick_run_clock),
+                check_win.after(apply_merges),
+            ).in_set(Simulation))
+            .add_systems(Update, update_time_left_text)
+            .add_systems(OnEnter(GameState::Won), show_win_screen)
+            .add_systems(OnEnter(GameState::Playing), (clear_win_screen, start_round_timer));
+    }
+}
+
+fn tick_run_clock(
+    time_step: Res<FixedTime>,
+    mut clock: ResMut<RunClock>,
+){
+    clock.0 += time_step.period;
+}
+
+fn start_round_timer(
+    settings: Res<GameSettings>,
+    mut round_timer: ResMut<RoundTimer>,
+){
+    round_timer.limit = if settings.time_attack { Some(TIME_ATTACK_DURATION) } else { None };
+}
+
+// Time's up is a game over, the results are on the final score screen
+fn check_time_up(
+    clock: Res<RunClock>,
+    round_timer: Res<RoundTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+){
+    if round_timer.expired(&clock) {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+fn spawn_time_left_text(mut commands: Commands) {
+    let mut text = TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: OVERLAY_FONT_SIZE,
+            color: TEXT_COLOR,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: TIME_LEFT_TEXT_TOP,
+        left: SCOREBOARD_TEXT_PADDING,
+        ..default()
+    });
+    text.visibility = Visibility::Hidden;
+    commands.spawn((text, TimeLeftText));
+}
+
+fn update_time_left_text(
+    clock: Res<RunClock>,
+    round_timer: Res<RoundTimer>,
+    localization: Res<Localization>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<TimeLeftText>>,
+){
+    for (mut text, mut visibility) in text_query.iter_mut() {
+        let Some(limit) = round_timer.limit else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        // rounded up, so 0:00 is only shown once time is up
+        let seconds: u32 = (limit - clock.seconds()).max(0.0).ceil() as u32;
+        text.sections[0].value = localization.format("time_left", &[&format!("{}:{:02}", seconds / 60, seconds % 60)]);
+    }
+}
+
+fn check_win(
+    fruit_table: Res<FruitTable>,
+    mut win: ResMut<WinCondition>,
+    mut merged_events: EventReader<FruitMerged>,
+    mut next_state: ResMut<NextState<GameState>>,
+){
+    for merged in merged_events.iter() {
+        if fruit_table.is_top(merged.group) {
+            win.made += 1;
+        }
+    }
+    if win.target.is_some_and(|target| win.made >= target) {
+        next_state.set(GameState::Won);
+    }
+}
+
+fn show_win_screen(
+    mut commands: Commands,
+    clock: Res<RunClock>,
+    localization: Res<Localization>,
+){
+    let seconds: u32 = clock.0.as_secs() as u32;
+    let time: String = format!("{}:{:02}", seconds / 60, seconds % 60);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            WinScreen,
+        ))
+        .with_children(|root| {
+            root.spawn(TextBundle::from_section(
+                localization.format("you_win", &[&time]),
+                TextStyle {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    color: SCORE_COLOR,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn clear_win_screen(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<WinScreen>>,
+){
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn made(group: u8) -> FruitMerged {
+        return FruitMerged { group, pos: Vec2::ZERO, score: 0, cascade_depth: 1 };
+    }
+
+    #[test]
+    fn making_the_target_number_of_top_fruits_wins() {
+        let mut world: World = World::new();
+        let fruit_table: FruitTable = FruitTable::default();
+        let top: u8 = fruit_table.len() as u8 - 1;
+        world.insert_resource(fruit_table);
+        world.insert_resource(WinCondition { target: Some(2), made: 0 });
+        world.init_resource::<NextState<GameState>>();
+        world.init_resource::<Events<FruitMerged>>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(check_win);
+
+        world.resource_mut::<Events<FruitMerged>>().send(made(top));
+        world.resource_mut::<Events<FruitMerged>>().send(made(top - 1));
+        schedule.run(&mut world);
+        assert!(world.resource::<NextState<GameState>>().0.is_none());
+
+        world.resource_mut::<Events<FruitMerged>>().send(made(top));
+        schedule.run(&mut world);
+        assert_eq!(world.resource::<WinCondition>().made, 2);
+        assert_eq!(world.resource::<NextState<GameState>>().0, Some(GameState::Won));
+    }
+
+    #[test]
+    fn time_attack_ends_on_the_step_the_clock_reaches_the_limit() {
+        let mut world: World = World::new();
+        world.insert_resource(GameSettings { time_attack: true, ..default() });
+        world.insert_resource(FixedTime::new_from_secs(1.0 / 60.0));
+        world.init_resource::<RoundTimer>();
+        world.init_resource::<RunClock>();
+        world.init_resource::<NextState<GameState>>();
+        let mut start = Schedule::default();
+        start.add_systems(start_round_timer);
+        start.run(&mut world);
+        let mut step = Schedule::default();
+        step.add_systems((tick_run_clock, check_time_up).chain());
+
+        let steps: u32 = (TIME_ATTACK_DURATION * 60.0) as u32;
+        for _ in 0..steps - 1 {
+            step.run(&mut world);
+        }
+        assert!(world.resource::<NextState<GameState>>().0.is_none(), "over after {} s", world.resource::<RunClock>().seconds());
+        step.run(&mut world);
+        assert_eq!(world.resource::<NextState<GameState>>().0, Some(GameState::GameOver));
+
+        let endless: Duration = Duration::from_secs_f32(1000.0 * TIME_ATTACK_DURATION);
+        assert!(!RoundTimer::default().expired(&RunClock(endless)));
+    }
+}